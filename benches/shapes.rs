@@ -0,0 +1,30 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use std::hint::black_box;
+use valhalla_client::shapes::{simplify_for_zoom, ShapePoint};
+
+/// A wiggly line of `points` points, about as dense as a valhalla route shape
+fn route_shape(points: usize) -> Vec<ShapePoint> {
+    (0..points)
+        .map(|i| {
+            let t = i as f64;
+            ShapePoint {
+                lon: 4.9 + t * 2e-5,
+                lat: 52.37 + (t / 50.0).sin() * 1e-3 + (t / 3.0).sin() * 1e-5,
+            }
+        })
+        .collect()
+}
+
+fn simplify(c: &mut Criterion) {
+    let shape = route_shape(50_000);
+    let mut group = c.benchmark_group("simplify_for_zoom");
+    for zoom in [5, 10, 15, 20] {
+        group.bench_with_input(BenchmarkId::from_parameter(zoom), &zoom, |b, &zoom| {
+            b.iter(|| simplify_for_zoom(black_box(&shape), zoom))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, simplify);
+criterion_main!(benches);
@@ -0,0 +1,164 @@
+//! Progress reporting and cancellation for long running batch operations
+//!
+//! Batch helpers such as [`crate::Valhalla::matrix_batched`] and
+//! [`crate::Valhalla::travel_time_grid`] split their work into many requests.
+//! A [`Control`] allows observing how many of them have completed, aborting the
+//! remaining ones and adjusting the individual requests.
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// How far a batch operation has progressed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Progress {
+    /// Number of requests which have completed
+    pub completed: usize,
+    /// Number of requests the operation consists of
+    pub total: usize,
+}
+
+/// Signals a batch operation to stop before sending its next request
+///
+/// Clones share the same state, so one clone can be handed to another thread (e.g. a UI
+/// thread) to cancel the operation.
+/// A request which is already in flight is not interrupted.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Requests cancellation of all operations using this token
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+    /// Whether [`Self::cancel`] was called on this token or one of its clones
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+type ProgressCallback = Arc<dyn Fn(Progress) + Send + Sync>;
+type ChunkCosting =
+    Arc<dyn Fn(&crate::matrix::Chunk) -> Option<crate::costing::Costing> + Send + Sync>;
+
+/// Progress callback and cancellation token for a batch operation
+///
+/// The default reports no progress and cannot be cancelled.
+#[derive(Clone, Default)]
+pub struct Control {
+    on_progress: Option<ProgressCallback>,
+    cancellation_token: Option<CancellationToken>,
+    chunk_costing: Option<ChunkCosting>,
+}
+impl Control {
+    pub fn builder() -> Self {
+        Self::default()
+    }
+    /// Called after each request of the batch has completed
+    ///
+    /// # Example:
+    /// ```rust
+    /// use valhalla_client::batch::Control;
+    ///
+    /// let control = Control::builder()
+    ///   .on_progress(|p| println!("{}/{} requests done", p.completed, p.total));
+    /// ```
+    pub fn on_progress(mut self, on_progress: impl Fn(Progress) + Send + Sync + 'static) -> Self {
+        self.on_progress = Some(Arc::new(on_progress));
+        self
+    }
+    /// Stops the batch with [`crate::Error::Cancelled`] once the token is cancelled
+    pub fn cancellation_token(mut self, token: CancellationToken) -> Self {
+        self.cancellation_token = Some(token);
+        self
+    }
+    /// Overrides the costing of individual matrix requests
+    ///
+    /// Called with each part of a matrix split up by [`crate::Valhalla::matrix_batched`],
+    /// [`crate::Valhalla::matrix_chunked`] or [`crate::Valhalla::travel_time_grid`] before
+    /// it is sent.
+    /// Returning `Some` replaces the costing of that request, `None` keeps the costing of the
+    /// whole matrix.
+    /// Options which suit the center of a large study area can leave pairs at its periphery
+    /// unreachable, which an override for the chunks concerned avoids.
+    ///
+    /// # Example:
+    /// ```rust
+    /// use valhalla_client::batch::Control;
+    /// use valhalla_client::costing::{AutoCostingOptions, Costing};
+    ///
+    /// // long distance pairs in the east of the study area
+    /// let control = Control::builder().chunk_costing(|chunk| {
+    ///   let east = chunk.targets().iter().all(|target| target.coordinate().0 > 10.0);
+    ///   east.then(|| Costing::Auto(AutoCostingOptions::builder().disable_hierarchy_pruning()))
+    /// });
+    /// ```
+    pub fn chunk_costing(
+        mut self,
+        chunk_costing: impl Fn(&crate::matrix::Chunk) -> Option<crate::costing::Costing>
+            + Send
+            + Sync
+            + 'static,
+    ) -> Self {
+        self.chunk_costing = Some(Arc::new(chunk_costing));
+        self
+    }
+    /// Applies [`Self::chunk_costing`] to `chunk`
+    pub(crate) fn adjust(&self, mut chunk: crate::matrix::Chunk) -> crate::matrix::Chunk {
+        if let Some(costing) = self.chunk_costing.as_ref().and_then(|f| f(&chunk)) {
+            chunk.manifest = chunk.manifest.costing(costing);
+        }
+        chunk
+    }
+    pub(crate) fn check_cancelled(&self) -> Result<(), crate::Error> {
+        match &self.cancellation_token {
+            Some(token) if token.is_cancelled() => Err(crate::Error::Cancelled),
+            _ => Ok(()),
+        }
+    }
+    pub(crate) fn report(&self, completed: usize, total: usize) {
+        if let Some(on_progress) = &self.on_progress {
+            on_progress(Progress { completed, total });
+        }
+    }
+}
+impl std::fmt::Debug for Control {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Control")
+            .field("on_progress", &self.on_progress.is_some())
+            .field("cancellation_token", &self.cancellation_token)
+            .field("chunk_costing", &self.chunk_costing.is_some())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+    #[test]
+    fn progress_and_cancellation() {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let token = CancellationToken::new();
+        let control = Control::builder()
+            .on_progress({
+                let seen = seen.clone();
+                move |p| seen.lock().unwrap().push(p)
+            })
+            .cancellation_token(token.clone());
+        assert!(control.check_cancelled().is_ok());
+        control.report(1, 2);
+        token.clone().cancel();
+        assert!(matches!(
+            control.check_cancelled(),
+            Err(crate::Error::Cancelled)
+        ));
+        assert_eq!(
+            *seen.lock().unwrap(),
+            [Progress {
+                completed: 1,
+                total: 2
+            }]
+        );
+    }
+}
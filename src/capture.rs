@@ -0,0 +1,85 @@
+use crate::transport::HttpResponse;
+use log::warn;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime};
+
+/// Writes every request and its response to a directory, one JSON file each
+///
+/// Files are named `<unix time in ms>-<sequence number>-<action>.json`, so they sort in the
+/// order the requests were sent.
+/// Failing to write a file is logged, but does not fail the request.
+#[derive(Debug)]
+pub(crate) struct Capture {
+    directory: PathBuf,
+    sequence: AtomicU64,
+}
+
+impl Capture {
+    pub(crate) fn new(directory: PathBuf) -> Self {
+        Self {
+            directory,
+            sequence: AtomicU64::new(0),
+        }
+    }
+
+    /// Records a single exchange with the server
+    ///
+    /// `manifest` is the JSON sent, before compression.
+    /// `response` is the response, or the message of the error, free of credentials.
+    pub(crate) fn record(
+        &self,
+        action: &str,
+        manifest: Option<&str>,
+        response: Result<&HttpResponse, String>,
+        latency: Duration,
+    ) {
+        if let Err(e) = self.write(action, manifest, response, latency) {
+            warn!(
+                "cannot capture {action} request to {}: {e}",
+                self.directory.display()
+            );
+        }
+    }
+
+    fn write(
+        &self,
+        action: &str,
+        manifest: Option<&str>,
+        response: Result<&HttpResponse, String>,
+        latency: Duration,
+    ) -> std::io::Result<()> {
+        let mut capture = serde_json::json!({
+            "action": action,
+            "request": manifest.map(json_or_text),
+            "latency_ms": latency.as_secs_f64() * 1000.0,
+        });
+        match response {
+            Ok(response) => {
+                capture["status"] = response.status.into();
+                capture["response"] = json_or_text(&String::from_utf8_lossy(&response.body));
+            }
+            Err(e) => capture["error"] = e.into(),
+        }
+        let millis = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis();
+        let sequence = self.sequence.fetch_add(1, Ordering::Relaxed);
+        // actions passed to `Valhalla::call` may contain anything
+        let action: String = action
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect();
+        std::fs::create_dir_all(&self.directory)?;
+        let path = self
+            .directory
+            .join(format!("{millis}-{sequence:06}-{action}.json"));
+        std::fs::write(path, serde_json::to_vec_pretty(&capture)?)
+    }
+}
+
+/// Parsed `text` if it is JSON, so it is readable in the capture, else `text` as string
+fn json_or_text(text: &str) -> serde_json::Value {
+    serde_json::from_str(text).unwrap_or_else(|_| text.into())
+}
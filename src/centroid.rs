@@ -0,0 +1,136 @@
+use crate::costing;
+use crate::route::{DirectionsType, Location, ShapePoint, Trip};
+use serde::{Deserialize, Serialize};
+
+#[serde_with::skip_serializing_none]
+#[derive(Serialize, Default, Debug, Clone)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Manifest {
+    #[serde(flatten)]
+    costing: Option<costing::Costing>,
+    locations: Vec<Location>,
+    units: Option<super::Units>,
+    id: Option<String>,
+    #[cfg_attr(feature = "schemars", schemars(with = "Option<String>"))]
+    language: Option<crate::Language>,
+    directions_type: Option<DirectionsType>,
+}
+
+impl Manifest {
+    #[must_use]
+    pub fn builder() -> Self {
+        Self::default()
+    }
+    /// Checks that there are at least 2 locations and that the costing options are in range
+    ///
+    /// See [`crate::validation`].
+    pub fn validate(&self) -> Result<(), crate::ValidationError> {
+        let mut validator = crate::validation::Validator::default();
+        validator.min_locations("locations", self.locations.len(), 2);
+        validator.costing(self.costing.as_ref());
+        validator.finish()
+    }
+    /// Configures the costing model
+    ///
+    /// The least-cost convergence point is computed based on this costing model.
+    ///
+    /// Default: [`costing::Costing::Auto`]
+    pub fn costing(mut self, costing: costing::Costing) -> Self {
+        self.costing = Some(costing);
+        self
+    }
+    /// The locations which should converge
+    ///
+    /// Minimum number of locations: 2
+    pub fn locations(mut self, locations: impl IntoIterator<Item = Location>) -> Self {
+        self.locations = locations.into_iter().collect();
+        self
+    }
+    /// Sets the distance units for output.
+    ///
+    /// Possible unit types are
+    /// - miles via [`super::Units::Imperial`] and
+    /// - kilometers via [`super::Units::Metric`].
+    ///
+    /// Default: [`super::Units::Metric`]
+    pub fn units(mut self, units: super::Units) -> Self {
+        self.units = Some(units);
+        self
+    }
+    /// Name of the centroid request
+    ///
+    /// If id is specified, the naming will be sent through to the response.
+    pub fn id(mut self, id: impl ToString) -> Self {
+        self.id = Some(id.to_string());
+        self
+    }
+    /// The language of the narration instructions
+    ///
+    /// See [`crate::route::Manifest::language`] for details.
+    ///
+    /// Default: `en-US` (United States-based English)
+    pub fn language(mut self, language: impl Into<crate::Language>) -> Self {
+        self.language = Some(language.into());
+        self
+    }
+    /// Sets the directions type
+    ///
+    /// See [`crate::route::Manifest::directions_type`] for details.
+    ///
+    /// Default: [`DirectionsType::Instructions`]
+    pub fn directions_type(mut self, directions_type: DirectionsType) -> Self {
+        self.directions_type = Some(directions_type);
+        self
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub(crate) struct RawResponse {
+    trip: Trip,
+    #[serde(default)]
+    alternates: Vec<crate::route::Alternate>,
+}
+
+/// Routes from every input location to their least-cost convergence point
+#[derive(Debug, Clone)]
+pub struct Response {
+    /// One route per input location, in the order of [`Manifest::locations`].
+    ///
+    /// Each route starts at its input location and ends at the centroid.
+    pub routes: Vec<Trip>,
+}
+impl Response {
+    /// The least-cost convergence point of all locations
+    ///
+    /// `None` if the server returned no shape, e.g. for [`DirectionsType::None`].
+    pub fn centroid(&self) -> Option<&ShapePoint> {
+        self.routes.first()?.legs.last()?.shape.last()
+    }
+}
+impl From<RawResponse> for Response {
+    fn from(raw: RawResponse) -> Self {
+        let mut routes = Vec::with_capacity(1 + raw.alternates.len());
+        routes.push(raw.trip);
+        routes.extend(raw.alternates.into_iter().map(|a| a.trip));
+        Self { routes }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn serialisation() {
+        let manifest = Manifest::builder()
+            .locations([
+                Location::new(4.9041, 52.3676),
+                Location::new(5.1214, 52.0907),
+            ])
+            .costing(costing::Costing::Pedestrian(Default::default()))
+            .id("meet");
+        let value = serde_json::to_value(&manifest).unwrap();
+        assert_eq!(value["costing"], "pedestrian");
+        assert_eq!(value["id"], "meet");
+        assert_eq!(value["locations"].as_array().unwrap().len(), 2);
+    }
+}
@@ -0,0 +1,99 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    /// Requests are sent, counting the failures in a row
+    Closed { failures: u32 },
+    /// Requests fail fast until the given time
+    Open { until: Instant },
+    /// A single probe request is in flight, deciding whether to close or open again
+    HalfOpen,
+}
+
+/// Stops sending requests to a server which keeps failing
+///
+/// Opens after `threshold` consecutive failures.
+/// Once `open_for` has passed, a single probe request is let through: its success closes the
+/// breaker again, its failure keeps it open for another `open_for`.
+/// Clones share the same state.
+#[derive(Debug, Clone)]
+pub(crate) struct CircuitBreaker {
+    threshold: u32,
+    open_for: Duration,
+    state: Arc<Mutex<State>>,
+}
+
+impl CircuitBreaker {
+    pub(crate) fn new(threshold: u32, open_for: Duration) -> Self {
+        Self {
+            threshold: threshold.max(1),
+            open_for,
+            state: Arc::new(Mutex::new(State::Closed { failures: 0 })),
+        }
+    }
+    /// Whether a request may be sent now
+    ///
+    /// Every permitted request must be followed by a call to [`Self::record`].
+    pub(crate) fn try_acquire(&self, now: Instant) -> bool {
+        let mut state = self.state.lock().unwrap();
+        match *state {
+            State::Closed { .. } => true,
+            State::Open { until } if now >= until => {
+                *state = State::HalfOpen;
+                true
+            }
+            State::Open { .. } | State::HalfOpen => false,
+        }
+    }
+    /// Records the outcome of a request permitted by [`Self::try_acquire`]
+    pub(crate) fn record(&self, success: bool, now: Instant) {
+        let mut state = self.state.lock().unwrap();
+        *state = match (*state, success) {
+            (_, true) => State::Closed { failures: 0 },
+            (State::Closed { failures }, false) if failures + 1 < self.threshold => State::Closed {
+                failures: failures + 1,
+            },
+            (State::Open { until }, false) => State::Open { until },
+            (_, false) => State::Open {
+                until: now + self.open_for,
+            },
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn opens_and_probes() {
+        let breaker = CircuitBreaker::new(2, Duration::from_secs(10));
+        let start = Instant::now();
+        assert!(breaker.try_acquire(start));
+        breaker.record(false, start);
+        assert!(breaker.try_acquire(start));
+        breaker.record(true, start);
+        for _ in 0..2 {
+            assert!(breaker.try_acquire(start));
+            breaker.record(false, start);
+        }
+        assert!(!breaker.try_acquire(start + Duration::from_secs(5)));
+
+        // a failing probe keeps the breaker open
+        let later = start + Duration::from_secs(10);
+        assert!(breaker.try_acquire(later));
+        assert!(
+            !breaker.try_acquire(later),
+            "only a single probe is let through"
+        );
+        breaker.record(false, later);
+        assert!(!breaker.try_acquire(later + Duration::from_secs(5)));
+
+        // a successful probe closes it
+        let even_later = later + Duration::from_secs(10);
+        assert!(breaker.try_acquire(even_later));
+        breaker.record(true, even_later);
+        assert!(breaker.try_acquire(even_later));
+        assert!(breaker.try_acquire(even_later));
+    }
+}
@@ -0,0 +1,164 @@
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::Instant;
+
+#[derive(Debug, Default)]
+struct State {
+    in_flight: usize,
+    queued: usize,
+}
+
+#[derive(Debug, Default)]
+struct Shared {
+    state: Mutex<State>,
+    released: Condvar,
+}
+
+/// Counting semaphore bounding the number of in-flight requests of a client
+///
+/// Without a maximum it only keeps track of the in-flight requests.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct Limiter {
+    max: Option<usize>,
+    shared: Arc<Shared>,
+}
+
+impl Limiter {
+    pub(crate) fn new(max: usize) -> Self {
+        Self {
+            max: Some(max),
+            shared: Default::default(),
+        }
+    }
+    /// Blocks until a request may be sent
+    ///
+    /// The request counts as in flight until the returned permit is dropped.
+    #[cfg(test)]
+    pub(crate) fn acquire(&self) -> Permit<'_> {
+        self.acquire_until(None)
+            .expect("waiting without a deadline cannot time out")
+    }
+    /// Like [`Self::acquire`], but gives up once `deadline` has passed
+    pub(crate) fn acquire_until(&self, deadline: Option<Instant>) -> Option<Permit<'_>> {
+        let mut state = self.shared.state.lock().unwrap();
+        if let Some(max) = self.max {
+            state.queued += 1;
+            while state.in_flight >= max {
+                state = match deadline {
+                    None => self.shared.released.wait(state).unwrap(),
+                    Some(deadline) => {
+                        let now = Instant::now();
+                        if now >= deadline {
+                            state.queued -= 1;
+                            return None;
+                        }
+                        let (state, _) = self
+                            .shared
+                            .released
+                            .wait_timeout(state, deadline - now)
+                            .unwrap();
+                        state
+                    }
+                };
+            }
+            state.queued -= 1;
+        }
+        state.in_flight += 1;
+        Some(Permit {
+            shared: &self.shared,
+        })
+    }
+    pub(crate) fn in_flight(&self) -> usize {
+        self.shared.state.lock().unwrap().in_flight
+    }
+    pub(crate) fn queued(&self) -> usize {
+        self.shared.state.lock().unwrap().queued
+    }
+}
+
+/// Maps `items` with `f` on up to `max_concurrency` scoped threads, keeping the order of `items`
+pub(crate) fn map_concurrently<I: Send, O: Send>(
+    items: Vec<I>,
+    max_concurrency: usize,
+    f: impl Fn(I) -> O + Sync,
+) -> Vec<O> {
+    let count = items.len();
+    let workers = max_concurrency.max(1).min(count);
+    let queue = Mutex::new(items.into_iter().enumerate());
+    let mut results: Vec<_> = std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..workers)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut results = Vec::new();
+                    // the lock is released before calling `f`
+                    while let Some((i, item)) = { queue.lock().unwrap().next() } {
+                        results.push((i, f(item)));
+                    }
+                    results
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().expect("worker thread panicked"))
+            .collect()
+    });
+    results.sort_unstable_by_key(|(i, _)| *i);
+    debug_assert_eq!(results.len(), count);
+    results.into_iter().map(|(_, result)| result).collect()
+}
+
+pub(crate) struct Permit<'a> {
+    shared: &'a Shared,
+}
+impl Drop for Permit<'_> {
+    fn drop(&mut self) {
+        self.shared.state.lock().unwrap().in_flight -= 1;
+        self.shared.released.notify_one();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    #[test]
+    fn limits_in_flight() {
+        let limiter = Limiter::new(1);
+        let permit = limiter.acquire();
+        assert_eq!(limiter.in_flight(), 1);
+        std::thread::scope(|scope| {
+            let waiting = scope.spawn(|| {
+                let _permit = limiter.acquire();
+                limiter.in_flight()
+            });
+            while limiter.queued() == 0 {
+                std::thread::sleep(Duration::from_millis(1));
+            }
+            assert_eq!(limiter.in_flight(), 1);
+            drop(permit);
+            assert_eq!(waiting.join().unwrap(), 1);
+        });
+        assert_eq!(limiter.in_flight(), 0);
+        assert_eq!(limiter.queued(), 0);
+    }
+
+    #[test]
+    fn gives_up_at_deadline() {
+        let limiter = Limiter::new(1);
+        let _permit = limiter.acquire();
+        let deadline = Instant::now() + Duration::from_millis(20);
+        assert!(limiter.acquire_until(Some(deadline)).is_none());
+        assert!(Instant::now() >= deadline);
+        assert_eq!(limiter.queued(), 0);
+    }
+
+    #[test]
+    fn unlimited_still_counts() {
+        let limiter = Limiter::default();
+        let a = limiter.acquire();
+        let _b = limiter.acquire();
+        assert_eq!(limiter.in_flight(), 2);
+        drop(a);
+        assert_eq!(limiter.in_flight(), 1);
+    }
+}
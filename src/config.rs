@@ -0,0 +1,149 @@
+//! Client configuration kept outside the code
+//!
+//! [`ClientConfig`] can be deserialized from any format serde supports, e.g. TOML, JSON or
+//! YAML, so that routing endpoints and policies can live in configuration management.
+//! Pass it to [`crate::Valhalla::from_config`] to create the client.
+//!
+//! # Example:
+//! ```toml
+//! base_url = "https://valhalla.internal/"
+//! auth = { header = { name = "x-api-key", value = "secret" } }
+//! timeout_secs = 10
+//! retries = 3
+//! max_concurrent_requests = 32
+//! rate_limit = { requests_per_second = 50, burst = 10 }
+//! circuit_breaker = { failures = 5, open_for_secs = 30 }
+//! ```
+//!
+//! ```rust
+//! use valhalla_client::{ClientConfig, Valhalla};
+//!
+//! let config: ClientConfig = serde_json::from_str(r#"{
+//!   "base_url": "https://valhalla.internal/",
+//!   "auth": {"bearer": "secret"},
+//!   "retries": 3
+//! }"#).unwrap();
+//! let valhalla = Valhalla::from_config(&config).unwrap();
+//! ```
+
+use crate::Auth;
+use serde::Deserialize;
+
+/// Settings of a [`crate::Valhalla`] client, see the [module documentation](self)
+///
+/// Every field is optional; unknown fields are rejected to catch typos.
+/// Durations are given in (fractional) seconds or milliseconds, as the unit in their name says.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct ClientConfig {
+    /// Base url of the server, see [`crate::Valhalla::new`]
+    ///
+    /// Default: the public FOSSGIS instance
+    pub base_url: Option<String>,
+    /// See [`crate::Valhalla::auth`]
+    ///
+    /// Written as `{"bearer": "<token>"}`, `{"header": {"name": "x-api-key", "value": "<key>"}}`
+    /// or `{"query": {"name": "api_key", "value": "<key>"}}`.
+    pub auth: Option<Auth>,
+    /// See [`crate::transport::TransportConfig::timeout`]
+    pub timeout_secs: Option<f64>,
+    /// See [`crate::Valhalla::retries`]
+    pub retries: u32,
+    /// Backoff before the first retry, see [`crate::Valhalla::retries`]
+    ///
+    /// Default: 200
+    pub retry_backoff_ms: u64,
+    /// See [`crate::Valhalla::max_concurrent_requests`]
+    pub max_concurrent_requests: Option<usize>,
+    /// See [`crate::Valhalla::rate_limit`]
+    pub rate_limit: Option<RateLimitConfig>,
+    /// See [`crate::Valhalla::circuit_breaker`]
+    pub circuit_breaker: Option<CircuitBreakerConfig>,
+    /// See [`crate::transport::TransportConfig::pool_max_idle_per_host`]
+    pub pool_max_idle_per_host: Option<usize>,
+    /// See [`crate::transport::TransportConfig::pool_idle_timeout`]
+    pub pool_idle_timeout_secs: Option<f64>,
+}
+
+impl Default for ClientConfig {
+    fn default() -> Self {
+        Self {
+            base_url: None,
+            auth: None,
+            timeout_secs: None,
+            retries: 0,
+            retry_backoff_ms: 200,
+            max_concurrent_requests: None,
+            rate_limit: None,
+            circuit_breaker: None,
+            pool_max_idle_per_host: None,
+            pool_idle_timeout_secs: None,
+        }
+    }
+}
+
+/// See [`crate::Valhalla::rate_limit`]
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct RateLimitConfig {
+    pub requests_per_second: f64,
+    pub burst: u32,
+}
+
+/// See [`crate::Valhalla::circuit_breaker`]
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct CircuitBreakerConfig {
+    pub failures: u32,
+    pub open_for_secs: f64,
+}
+
+/// Converts the setting `name` to a [`std::time::Duration`]
+#[cfg(any(feature = "reqwest", feature = "ureq"))]
+pub(crate) fn seconds(name: &str, seconds: f64) -> Result<std::time::Duration, crate::Error> {
+    std::time::Duration::try_from_secs_f64(seconds)
+        .map_err(|e| crate::Error::Config(format!("{name}: {e}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deserialize() {
+        let config: ClientConfig = serde_json::from_value(serde_json::json!({
+            "base_url": "https://valhalla.internal/",
+            "auth": {"header": {"name": "x-api-key", "value": "secret"}},
+            "timeout_secs": 2.5,
+            "rate_limit": {"requests_per_second": 50, "burst": 10},
+            "circuit_breaker": {"failures": 5, "open_for_secs": 30}
+        }))
+        .unwrap();
+        assert_eq!(
+            config,
+            ClientConfig {
+                base_url: Some("https://valhalla.internal/".into()),
+                auth: Some(Auth::Header {
+                    name: "x-api-key".into(),
+                    value: "secret".into()
+                }),
+                timeout_secs: Some(2.5),
+                rate_limit: Some(RateLimitConfig {
+                    requests_per_second: 50.0,
+                    burst: 10
+                }),
+                circuit_breaker: Some(CircuitBreakerConfig {
+                    failures: 5,
+                    open_for_secs: 30.0
+                }),
+                ..Default::default()
+            }
+        );
+        assert!(format!("{config:?}").contains("Header(x-api-key: <redacted>)"));
+
+        let typo = serde_json::from_value::<ClientConfig>(serde_json::json!({"retry": 3}));
+        assert!(typo.is_err());
+        #[cfg(any(feature = "reqwest", feature = "ureq"))]
+        assert!(seconds("timeout_secs", -1.0).is_err());
+    }
+}
@@ -2,6 +2,7 @@ use serde::Serialize;
 
 #[serde_with::skip_serializing_none]
 #[derive(Serialize, Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 struct AutoCostingOptionsInner {
     maneuver_penalty: Option<f32>,
     gate_cost: Option<f32>,
@@ -11,17 +12,17 @@ struct AutoCostingOptionsInner {
     toll_booth_cost: Option<f32>,
     toll_booth_penalty: Option<f32>,
     ferry_cost: Option<f32>,
-    use_ferry: Option<f32>,
-    use_highways: Option<f32>,
-    use_tolls: Option<f32>,
-    use_living_streets: Option<f32>,
-    use_tracks: Option<f32>,
+    use_ferry: Option<super::Willingness>,
+    use_highways: Option<super::Willingness>,
+    use_tolls: Option<super::Willingness>,
+    use_living_streets: Option<super::Willingness>,
+    use_tracks: Option<super::Willingness>,
     service_penalty: Option<f32>,
     service_factor: Option<f32>,
     country_crossing_cost: Option<f32>,
     country_crossing_penalty: Option<f32>,
     shortest: Option<bool>,
-    use_distance: Option<f32>,
+    use_distance: Option<super::Willingness>,
     disable_hierarchy_pruning: Option<bool>,
     top_speed: Option<f32>,
     fixed_speed: Option<u32>,
@@ -43,6 +44,7 @@ struct AutoCostingOptionsInner {
 }
 
 #[derive(Serialize, Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct AutoCostingOptions {
     auto: AutoCostingOptionsInner,
 }
@@ -51,6 +53,34 @@ impl AutoCostingOptions {
     pub fn builder() -> Self {
         Self::default()
     }
+    /// Checks the options against the ranges valhalla accepts, reporting every violation
+    pub fn validate(&self) -> Result<(), Vec<super::RangeViolation>> {
+        let mut check = super::RangeCheck::default();
+        check.check("top_speed", self.auto.top_speed, 10.0, 252.0);
+        // 0 disables the fixed speed
+        let fixed_speed = self.auto.fixed_speed.filter(|speed| *speed != 0);
+        check.check("fixed_speed", fixed_speed, 1.0, 252.0);
+        check.check("closure_factor", self.auto.closure_factor, 1.0, 10.0);
+        check.willingness("use_ferry", self.auto.use_ferry);
+        check.willingness("use_highways", self.auto.use_highways);
+        check.willingness("use_tolls", self.auto.use_tolls);
+        check.willingness("use_living_streets", self.auto.use_living_streets);
+        check.willingness("use_tracks", self.auto.use_tracks);
+        check.willingness("use_distance", self.auto.use_distance);
+        check.finish()
+    }
+
+    /// A penalty applied when transitioning between roads that do not have consistent naming–in
+    /// other words, no road names in common.
+    ///
+    /// This penalty can be used to create simpler routes that tend to have fewer maneuvers or
+    /// narrative guidance instructions.
+    ///
+    /// Default: `5` seconds
+    pub fn maneuver_penalty(mut self, penalty: f32) -> Self {
+        self.auto.maneuver_penalty = Some(penalty);
+        self
+    }
 
     /// A cost applied when a [gate](http://wiki.openstreetmap.org/wiki/Tag:barrier%3Dgate) with
     /// undefined or private access is encountered.
@@ -123,10 +153,8 @@ impl AutoCostingOptions {
     /// **Note:** sometimes ferries are required to complete a route so values of `0` are not guaranteed to avoid ferries entirely.
     ///
     /// Default: `0.5`
-    pub fn use_ferry(mut self, use_ferry: f32) -> Self {
-        debug_assert!(use_ferry >= 0.0);
-        debug_assert!(use_ferry <= 1.0);
-        self.auto.use_ferry = Some(use_ferry);
+    pub fn use_ferry(mut self, use_ferry: impl Into<super::Willingness>) -> Self {
+        self.auto.use_ferry = Some(use_ferry.into());
         self
     }
     /// This value indicates the willingness to take highways.
@@ -138,10 +166,8 @@ impl AutoCostingOptions {
     /// **Note:** sometimes highways are required to complete a route so values of `0` are not guaranteed to avoid highways entirely.
     ///
     /// Default: `1.0`
-    pub fn use_highways(mut self, use_highways: f32) -> Self {
-        debug_assert!(use_highways >= 0.0);
-        debug_assert!(use_highways <= 1.0);
-        self.auto.use_highways = Some(use_highways);
+    pub fn use_highways(mut self, use_highways: impl Into<super::Willingness>) -> Self {
+        self.auto.use_highways = Some(use_highways.into());
         self
     }
     /// This value indicates the willingness to take roads with tolls.
@@ -153,10 +179,8 @@ impl AutoCostingOptions {
     /// **Note:** sometimes roads with tolls are required to complete a route so values of `0` are not guaranteed to avoid them entirely.
     ///
     /// Default: `0.5`
-    pub fn use_tolls(mut self, use_tolls: f32) -> Self {
-        debug_assert!(use_tolls >= 0.0);
-        debug_assert!(use_tolls <= 1.0);
-        self.auto.use_tolls = Some(use_tolls);
+    pub fn use_tolls(mut self, use_tolls: impl Into<super::Willingness>) -> Self {
+        self.auto.use_tolls = Some(use_tolls.into());
         self
     }
     /// This value indicates the willingness to take living streets.
@@ -170,10 +194,8 @@ impl AutoCostingOptions {
     /// Default:
     /// - `truck`: `0`
     /// - `cars`/`buses`/`motor scooters`/`motorcycles`: `0.1`
-    pub fn use_living_streets(mut self, use_living_streets: f32) -> Self {
-        debug_assert!(use_living_streets >= 0.0);
-        debug_assert!(use_living_streets <= 1.0);
-        self.auto.use_living_streets = Some(use_living_streets);
+    pub fn use_living_streets(mut self, use_living_streets: impl Into<super::Willingness>) -> Self {
+        self.auto.use_living_streets = Some(use_living_streets.into());
         self
     }
     /// This value indicates the willingness to take track roads.
@@ -187,10 +209,8 @@ impl AutoCostingOptions {
     /// Default:
     /// - `0` for autos,
     /// - `0.5` for motor scooters and motorcycles.
-    pub fn use_tracks(mut self, use_tracks: f32) -> Self {
-        debug_assert!(use_tracks >= 0.0);
-        debug_assert!(use_tracks <= 1.0);
-        self.auto.use_tracks = Some(use_tracks);
+    pub fn use_tracks(mut self, use_tracks: impl Into<super::Willingness>) -> Self {
+        self.auto.use_tracks = Some(use_tracks.into());
         self
     }
     /// A penalty applied for transition to generic service road.
@@ -247,10 +267,8 @@ impl AutoCostingOptions {
     /// - `1` only distance.
     ///
     /// **Note:** this costing is currently only available for [`super::Costing::Auto`].
-    pub fn use_distance(mut self, use_distance: f32) -> Self {
-        debug_assert!(use_distance >= 0.0);
-        debug_assert!(use_distance <= 1.0);
-        self.auto.use_distance = Some(use_distance);
+    pub fn use_distance(mut self, use_distance: impl Into<super::Willingness>) -> Self {
+        self.auto.use_distance = Some(use_distance.into());
         self
     }
     /// Disable hierarchies to calculate the actual optimal route.
@@ -266,27 +284,29 @@ impl AutoCostingOptions {
     /// Top speed the vehicle can go.
     ///
     /// Also used to avoid roads with higher speeds than this value.
-    /// Must be between `10` and `252 KPH`.
+    /// Must be between `10` and `252 KPH`, see [`Self::validate`].
     ///
     /// Default:
     /// - `truck`: `120 KPH`
     /// - `auto`/`bus`: `140 KPH`
     pub fn top_speed(mut self, top_speed: f32) -> Self {
-        debug_assert!(top_speed >= 10.0);
-        debug_assert!(top_speed <= 252.0);
         self.auto.top_speed = Some(top_speed);
         self
     }
+    /// Top speed the vehicle can go in miles per hour.
+    ///
+    /// Converted to KPH, see [`Self::top_speed`].
+    pub fn top_speed_mph(self, top_speed_mph: f32) -> Self {
+        self.top_speed(super::mph_to_kph(top_speed_mph))
+    }
     /// Fixed speed the vehicle can go. Used to override the calculated speed.
     ///
     /// Can be useful if speed of vehicle is known.
-    /// Must be between `1` and `252 KPH`.
+    /// Must be `0` or between `1` and `252 KPH`, see [`Self::validate`].
     ///
     /// Default: `0KPH` which disables fixed speed and falls back to the standard calculated speed
     /// based on the road attribution.
     pub fn fixed_speed(mut self, fixed_speed: u32) -> Self {
-        debug_assert!(fixed_speed >= 1);
-        debug_assert!(fixed_speed <= 252);
         self.auto.fixed_speed = Some(fixed_speed);
         self
     }
@@ -430,6 +450,7 @@ impl AutoCostingOptions {
 }
 
 #[derive(Serialize, Debug, Clone, Copy, Eq, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub enum UsedSpeedSources {
     #[serde(rename = "all")]
     All,
@@ -1,6 +1,7 @@
 use serde::Serialize;
 
 #[derive(Serialize, Default, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub enum BicycleType {
     /// Road
     ///
@@ -27,13 +28,14 @@ pub enum BicycleType {
 
 #[serde_with::skip_serializing_none]
 #[derive(Serialize, Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 struct BicycleCostingOptionsInner {
     bicycle_type: Option<BicycleType>,
     cycling_speed: Option<f32>,
-    use_roads: Option<f32>,
-    use_hills: Option<f32>,
-    use_ferry: Option<f32>,
-    use_living_streets: Option<f32>,
+    use_roads: Option<super::Willingness>,
+    use_hills: Option<super::Willingness>,
+    use_ferry: Option<super::Willingness>,
+    use_living_streets: Option<super::Willingness>,
     avoid_bad_surfaces: Option<f32>,
     bss_return_cost: Option<f32>,
     bss_return_penalty: Option<f32>,
@@ -47,6 +49,7 @@ struct BicycleCostingOptionsInner {
 }
 
 #[derive(Serialize, Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct BicycleCostingOptions {
     bicycle: BicycleCostingOptionsInner,
 }
@@ -55,6 +58,21 @@ impl BicycleCostingOptions {
     pub fn builder() -> Self {
         Self::default()
     }
+    /// Checks the options against the ranges valhalla accepts, reporting every violation
+    pub fn validate(&self) -> Result<(), Vec<super::RangeViolation>> {
+        let mut check = super::RangeCheck::default();
+        check.willingness("use_roads", self.bicycle.use_roads);
+        check.willingness("use_hills", self.bicycle.use_hills);
+        check.willingness("use_ferry", self.bicycle.use_ferry);
+        check.willingness("use_living_streets", self.bicycle.use_living_streets);
+        check.check(
+            "avoid_bad_surfaces",
+            self.bicycle.avoid_bad_surfaces,
+            0.0,
+            1.0,
+        );
+        check.finish()
+    }
 
     /// Specifies the [`BicycleType`].
     ///
@@ -82,6 +100,12 @@ impl BicycleCostingOptions {
         self.bicycle.cycling_speed = Some(speed);
         self
     }
+    /// Speed the bicycle travels along smooth, flat roads in miles per hour.
+    ///
+    /// Converted to KPH, see [`Self::cycling_speed`].
+    pub fn cycling_speed_mph(self, speed_mph: f32) -> Self {
+        self.cycling_speed(super::mph_to_kph(speed_mph))
+    }
 
     /// A cyclist's propensity to use roads alongside other vehicles.
     ///
@@ -93,8 +117,8 @@ impl BicycleCostingOptions {
     /// in an attempt to avoid them when finding the best path.
     ///
     /// Default: `0.5`
-    pub fn use_roads(mut self, willingness: f32) -> Self {
-        self.bicycle.use_roads = Some(willingness);
+    pub fn use_roads(mut self, willingness: impl Into<super::Willingness>) -> Self {
+        self.bicycle.use_roads = Some(willingness.into());
         self
     }
 
@@ -111,8 +135,8 @@ impl BicycleCostingOptions {
     /// areas).
     ///
     /// Default: `0.5`
-    pub fn use_hills(mut self, willingness: f32) -> Self {
-        self.bicycle.use_hills = Some(willingness);
+    pub fn use_hills(mut self, willingness: impl Into<super::Willingness>) -> Self {
+        self.bicycle.use_hills = Some(willingness.into());
         self
     }
 
@@ -126,8 +150,8 @@ impl BicycleCostingOptions {
     /// guaranteed to avoid ferries entirely.
     ///
     /// Default: `0.5`
-    pub fn use_ferry(mut self, willingness: f32) -> Self {
-        self.bicycle.use_ferry = Some(willingness);
+    pub fn use_ferry(mut self, willingness: impl Into<super::Willingness>) -> Self {
+        self.bicycle.use_ferry = Some(willingness.into());
         self
     }
 
@@ -141,8 +165,8 @@ impl BicycleCostingOptions {
     /// guaranteed to avoid living streets entirely.
     ///
     /// Default: `0.5`
-    pub fn use_living_streets(mut self, willingness: f32) -> Self {
-        self.bicycle.use_living_streets = Some(willingness);
+    pub fn use_living_streets(mut self, willingness: impl Into<super::Willingness>) -> Self {
+        self.bicycle.use_living_streets = Some(willingness.into());
         self
     }
 
@@ -157,6 +181,8 @@ impl BicycleCostingOptions {
     ///   including start and end points.
     ///
     /// Default: `0.25`
+    ///
+    /// Values outside that range are reported by [`Self::validate`].
     pub fn avoid_bad_surfaces(mut self, willingness: f32) -> Self {
         self.bicycle.avoid_bad_surfaces = Some(willingness);
         self
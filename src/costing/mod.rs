@@ -6,6 +6,7 @@ pub mod multimodal;
 pub mod pedestrian;
 pub mod transit;
 pub mod truck;
+pub mod vehicle_profile;
 
 pub use auto::AutoCostingOptions;
 pub use bicycle::BicycleCostingOptions;
@@ -14,10 +15,13 @@ pub use motorcycle::MotorcycleCostingOptions;
 pub use multimodal::MultimodalCostingOptions;
 pub use pedestrian::PedestrianCostingOptions;
 use serde::Serialize;
+use std::collections::BTreeMap;
 pub use transit::TransitCostingOptions;
 pub use truck::TruckCostingOptions;
+pub use vehicle_profile::VehicleProfile;
 
 #[derive(Serialize, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 #[serde(tag = "costing", content = "costing_options")]
 #[allow(clippy::large_enum_variant)]
 pub enum Costing {
@@ -90,12 +94,302 @@ pub enum Costing {
     Pedestrian(PedestrianCostingOptions),
 }
 
+/// A preference between `0` and `1` taken by the `use_*` costing options
+///
+/// - `0` avoids the feature as far as possible,
+/// - `0.5` treats it neutrally and
+/// - `1` favours it.
+///
+/// Always within `0..=1`: plain numbers convert into a `Willingness` clamped like
+/// [`Self::new`], so `use_tolls(0.2)` keeps working.
+/// An out of range number is remembered though, and reported by [`Costing::validate`], which
+/// the methods of [`crate::Valhalla`] call before sending a request.
+/// Use [`Self::new`] to clamp silently or [`Self::try_new`] to reject right away instead.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct Willingness {
+    value: f32,
+    /// The out of range number converted via `From`, if any
+    clamped_from: Option<f32>,
+}
+impl Willingness {
+    /// Clamps `value` to `0..=1`; `NaN` becomes the neutral `0.5`
+    pub fn new(value: f32) -> Self {
+        let value = if value.is_nan() {
+            0.5
+        } else {
+            value.clamp(0.0, 1.0)
+        };
+        Self {
+            value,
+            clamped_from: None,
+        }
+    }
+    /// `None` unless `value` is within `0..=1`
+    pub fn try_new(value: f32) -> Option<Self> {
+        (0.0..=1.0).contains(&value).then(|| Self::new(value))
+    }
+    pub fn get(self) -> f32 {
+        self.value
+    }
+}
+impl From<f32> for Willingness {
+    fn from(value: f32) -> Self {
+        Self {
+            clamped_from: (!(0.0..=1.0).contains(&value)).then_some(value),
+            ..Self::new(value)
+        }
+    }
+}
+impl From<f64> for Willingness {
+    fn from(value: f64) -> Self {
+        Self::from(value as f32)
+    }
+}
+impl Serialize for Willingness {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_f32(self.value)
+    }
+}
+#[cfg(feature = "schemars")]
+impl schemars::JsonSchema for Willingness {
+    fn schema_name() -> std::borrow::Cow<'static, str> {
+        "Willingness".into()
+    }
+    fn json_schema(_: &mut schemars::SchemaGenerator) -> schemars::Schema {
+        schemars::json_schema!({"type": "number", "minimum": 0, "maximum": 1})
+    }
+}
+
+/// An option which is outside the range valhalla accepts
+///
+/// Returned by the `validate` methods of the costing options.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RangeViolation {
+    /// Name of the option, as sent to valhalla
+    pub field: &'static str,
+    pub value: f64,
+    /// Smallest allowed value
+    pub min: f64,
+    /// Largest allowed value
+    pub max: f64,
+}
+impl std::fmt::Display for RangeViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} is {}, but must be between {} and {}",
+            self.field, self.value, self.min, self.max
+        )
+    }
+}
+impl std::error::Error for RangeViolation {}
+
+/// Collects every [`RangeViolation`] instead of stopping at the first one
+#[derive(Default)]
+pub(crate) struct RangeCheck(Vec<RangeViolation>);
+impl RangeCheck {
+    fn check(&mut self, field: &'static str, value: Option<impl Into<f64>>, min: f64, max: f64) {
+        if let Some(value) = value.map(Into::into) {
+            if !(min..=max).contains(&value) {
+                self.0.push(RangeViolation {
+                    field,
+                    value,
+                    min,
+                    max,
+                });
+            }
+        }
+    }
+    /// A `use_*` option, see [`Willingness`]
+    fn willingness(&mut self, field: &'static str, value: Option<Willingness>) {
+        self.check(field, value.and_then(|value| value.clamped_from), 0.0, 1.0);
+    }
+    fn finish(self) -> Result<(), Vec<RangeViolation>> {
+        if self.0.is_empty() {
+            Ok(())
+        } else {
+            Err(self.0)
+        }
+    }
+}
+
+/// Converts the miles per hour of the `*_mph` setters to the KPH valhalla expects
+fn mph_to_kph(mph: f32) -> f32 {
+    mph * 1.609_344
+}
+
 impl Default for Costing {
     fn default() -> Self {
         Self::Auto(Default::default())
     }
 }
 
+impl Costing {
+    /// Name of the costing model as sent to valhalla
+    ///
+    /// Example: `"truck"`
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Auto(_) => "auto",
+            Self::Bicycle(_) => "bicycle",
+            Self::Bus(_) => "bus",
+            Self::Bikeshare(_) => "bikeshare",
+            Self::Truck(_) => "truck",
+            Self::Taxi(_) => "taxi",
+            Self::MotorScooter(_) => "motor_scooter",
+            Self::Motorcycle(_) => "motorcycle",
+            Self::Multimodal(_) => "multimodal",
+            Self::Pedestrian(_) => "pedestrian",
+        }
+    }
+    /// Checks the options of the costing model against the ranges valhalla accepts
+    ///
+    /// All violations are returned at once, which makes it easy to show them next to the form
+    /// fields they came from.
+    ///
+    /// # Example:
+    /// ```rust
+    /// use valhalla_client::costing::{Costing, PedestrianCostingOptions};
+    ///
+    /// let costing = Costing::Pedestrian(
+    ///     PedestrianCostingOptions::builder()
+    ///         .walking_speed(30.0)
+    ///         .max_hiking_difficulty(7.0),
+    /// );
+    /// let violations = costing.validate().unwrap_err();
+    /// assert_eq!(violations.len(), 2);
+    /// assert_eq!(violations[0].field, "walking_speed");
+    /// ```
+    pub fn validate(&self) -> Result<(), Vec<RangeViolation>> {
+        match self {
+            Self::Auto(options) | Self::Bus(options) | Self::Taxi(options) => options.validate(),
+            Self::Bicycle(options) | Self::Bikeshare(options) => options.validate(),
+            Self::Truck(options) => options.validate(),
+            Self::MotorScooter(options) => options.validate(),
+            Self::Motorcycle(options) => options.validate(),
+            Self::Multimodal(options) => options.validate(),
+            Self::Pedestrian(options) => options.validate(),
+        }
+    }
+    /// The options which differ between `self` and `other`
+    ///
+    /// Useful to find out why two route requests returned different routes.
+    /// A different costing model is reported as change of the `costing` option.
+    ///
+    /// # Example:
+    /// ```rust
+    /// use valhalla_client::costing::{Costing, TruckCostingOptions};
+    ///
+    /// let before = Costing::Truck(TruckCostingOptions::builder().height(4.0));
+    /// let after = Costing::Truck(TruckCostingOptions::builder().height(4.5).use_tolls(0.0));
+    /// let changes: Vec<String> = before.diff(&after).iter().map(|c| c.to_string()).collect();
+    /// assert_eq!(changes, ["truck.height: 4.0 -> 4.5", "truck.use_tolls: unset -> 0.0"]);
+    /// ```
+    pub fn diff(&self, other: &Costing) -> Vec<CostingChange> {
+        let old = self.options();
+        let new = other.options();
+        let mut changes = Vec::new();
+        for (option, old_value) in &old {
+            let new_value = new.get(option);
+            if new_value != Some(old_value) {
+                changes.push(CostingChange {
+                    option: option.clone(),
+                    old: Some(old_value.clone()),
+                    new: new_value.cloned(),
+                });
+            }
+        }
+        for (option, new_value) in &new {
+            if !old.contains_key(option) {
+                changes.push(CostingChange {
+                    option: option.clone(),
+                    old: None,
+                    new: Some(new_value.clone()),
+                });
+            }
+        }
+        changes.sort_by(|a, b| a.option.cmp(&b.option));
+        changes
+    }
+    /// All explicitly configured options, keyed by their dotted path
+    fn options(&self) -> BTreeMap<String, serde_json::Value> {
+        let mut options = BTreeMap::new();
+        let value = serde_json::to_value(self).expect("costing is always serializable");
+        if let Some(costing) = value.get("costing") {
+            options.insert("costing".to_string(), costing.clone());
+        }
+        if let Some(costing_options) = value.get("costing_options") {
+            flatten_options(String::new(), costing_options, &mut options);
+        }
+        options
+    }
+}
+
+fn flatten_options(
+    prefix: String,
+    value: &serde_json::Value,
+    options: &mut BTreeMap<String, serde_json::Value>,
+) {
+    match value {
+        serde_json::Value::Object(map) => {
+            for (key, value) in map {
+                let path = if prefix.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{prefix}.{key}")
+                };
+                flatten_options(path, value, options);
+            }
+        }
+        _ => {
+            options.insert(prefix, value.clone());
+        }
+    }
+}
+
+/// Prints the costing model with only the options which were explicitly configured
+///
+/// Example: `truck(truck.hazmat=true, truck.height=4.0)`
+impl std::fmt::Display for Costing {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut options = self.options();
+        let costing = options.remove("costing").unwrap_or_default();
+        write!(f, "{}(", costing.as_str().unwrap_or_default())?;
+        for (i, (option, value)) in options.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{option}={value}")?;
+        }
+        write!(f, ")")
+    }
+}
+
+/// A single option which differs between two [`Costing`]s, see [`Costing::diff`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct CostingChange {
+    /// Dotted path of the option, e.g. `truck.height`
+    pub option: String,
+    /// `None` if the option was not configured
+    pub old: Option<serde_json::Value>,
+    /// `None` if the option was not configured
+    pub new: Option<serde_json::Value>,
+}
+impl std::fmt::Display for CostingChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: ", self.option)?;
+        match &self.old {
+            Some(old) => write!(f, "{old}")?,
+            None => write!(f, "unset")?,
+        }
+        write!(f, " -> ")?;
+        match &self.new {
+            Some(new) => write!(f, "{new}"),
+            None => write!(f, "unset"),
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -106,4 +400,119 @@ mod test {
             serde_json::json!({"costing": "auto", "costing_options": {"auto":{}}})
         );
     }
+
+    #[test]
+    fn willingness() {
+        assert_eq!(Willingness::try_new(0.3).map(Willingness::get), Some(0.3));
+        assert_eq!(Willingness::try_new(1.5), None);
+        assert_eq!(Willingness::try_new(f32::NAN), None);
+        assert_eq!(Willingness::new(-2.0).get(), 0.0);
+        assert_eq!(Willingness::new(f32::NAN).get(), 0.5);
+        assert_eq!(Willingness::from(5.0).get(), 1.0);
+        assert_eq!(Willingness::from(f32::NAN).get(), 0.5);
+        let violations = Costing::Bicycle(BicycleCostingOptions::builder().use_hills(1.5))
+            .validate()
+            .unwrap_err();
+        assert_eq!(
+            violations[0].to_string(),
+            "use_hills is 1.5, but must be between 0 and 1"
+        );
+        assert_eq!(
+            serde_json::to_value(TruckCostingOptions::builder().use_tolls(0.25_f64)).unwrap(),
+            serde_json::json!({"truck": {"use_tolls": 0.25}})
+        );
+    }
+
+    #[test]
+    fn maneuver_penalty() {
+        assert_eq!(
+            serde_json::to_value(Costing::Auto(
+                AutoCostingOptions::builder().maneuver_penalty(30.0)
+            ))
+            .unwrap(),
+            serde_json::json!({"costing": "auto", "costing_options": {"auto": {"maneuver_penalty": 30.0}}})
+        );
+    }
+
+    #[test]
+    fn validate() {
+        assert_eq!(Costing::default().validate(), Ok(()));
+        let truck = TruckCostingOptions::builder()
+            .top_speed(300.0)
+            .fixed_speed(300)
+            .closure_factor(5.0);
+        let violations = truck.validate().unwrap_err();
+        assert_eq!(
+            violations
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            [
+                "top_speed is 300, but must be between 10 and 252",
+                "fixed_speed is 300, but must be between 1 and 252",
+            ]
+        );
+        // disables the fixed speed
+        assert_eq!(
+            TruckCostingOptions::builder().fixed_speed(0).validate(),
+            Ok(())
+        );
+        let bicycle = BicycleCostingOptions::builder().avoid_bad_surfaces(2.0);
+        assert_eq!(
+            Costing::Bicycle(bicycle).validate().unwrap_err()[0].field,
+            "avoid_bad_surfaces"
+        );
+        let multimodal = MultimodalCostingOptions::builder()
+            .pedestrian(PedestrianCostingOptions::builder().walking_speed(0.1));
+        assert_eq!(
+            Costing::Multimodal(multimodal).validate().unwrap_err()[0].field,
+            "walking_speed"
+        );
+    }
+
+    #[test]
+    fn imperial_speeds() {
+        let auto = serde_json::to_value(AutoCostingOptions::builder().top_speed_mph(62.5)).unwrap();
+        assert!((auto["auto"]["top_speed"].as_f64().unwrap() - 100.584).abs() < 1e-4);
+        let bicycle =
+            serde_json::to_value(BicycleCostingOptions::builder().cycling_speed_mph(10.0)).unwrap();
+        assert!((bicycle["bicycle"]["cycling_speed"].as_f64().unwrap() - 16.09344).abs() < 1e-4);
+    }
+
+    #[test]
+    fn diff_and_display() {
+        let truck = Costing::Truck(TruckCostingOptions::builder().height(4.0).axle_count(3));
+        assert_eq!(
+            truck.to_string(),
+            "truck(truck.axle_count=3, truck.height=4.0)"
+        );
+        assert_eq!(Costing::default().to_string(), "auto()");
+        assert!(truck.diff(&truck.clone()).is_empty());
+        assert_eq!(truck.name(), "truck");
+        assert_eq!(
+            truck.diff(&Costing::Bus(AutoCostingOptions::builder().height(4.0))),
+            [
+                CostingChange {
+                    option: "auto.height".to_string(),
+                    old: None,
+                    new: Some(serde_json::json!(4.0)),
+                },
+                CostingChange {
+                    option: "costing".to_string(),
+                    old: Some(serde_json::json!("truck")),
+                    new: Some(serde_json::json!("bus")),
+                },
+                CostingChange {
+                    option: "truck.axle_count".to_string(),
+                    old: Some(serde_json::json!(3)),
+                    new: None,
+                },
+                CostingChange {
+                    option: "truck.height".to_string(),
+                    old: Some(serde_json::json!(4.0)),
+                    new: None,
+                },
+            ]
+        );
+    }
 }
@@ -2,6 +2,7 @@ use serde::Serialize;
 
 #[serde_with::skip_serializing_none]
 #[derive(Serialize, Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 struct MotorScooterCostingOptionsInner {
     maneuver_penalty: Option<f32>,
     gate_cost: Option<f32>,
@@ -11,17 +12,17 @@ struct MotorScooterCostingOptionsInner {
     toll_booth_cost: Option<f32>,
     toll_booth_penalty: Option<f32>,
     ferry_cost: Option<f32>,
-    use_ferry: Option<f32>,
-    use_highways: Option<f32>,
-    use_tolls: Option<f32>,
-    use_living_streets: Option<f32>,
-    use_tracks: Option<f32>,
+    use_ferry: Option<super::Willingness>,
+    use_highways: Option<super::Willingness>,
+    use_tolls: Option<super::Willingness>,
+    use_living_streets: Option<super::Willingness>,
+    use_tracks: Option<super::Willingness>,
     service_penalty: Option<f32>,
     service_factor: Option<f32>,
     country_crossing_cost: Option<f32>,
     country_crossing_penalty: Option<f32>,
     shortest: Option<bool>,
-    use_distance: Option<f32>,
+    use_distance: Option<super::Willingness>,
     disable_hierarchy_pruning: Option<bool>,
     top_speed: Option<f32>,
     fixed_speed: Option<u32>,
@@ -41,8 +42,8 @@ struct MotorScooterCostingOptionsInner {
     include_hov3: Option<bool>,
     include_hot: Option<bool>,
     // -- ↓ motor_scooter only ↓ --
-    use_primary: Option<f32>,
-    use_hills: Option<f32>,
+    use_primary: Option<super::Willingness>,
+    use_hills: Option<super::Willingness>,
 }
 
 /// Will avoid higher class roads unless the country overrides allows motor scooters on these roads.
@@ -53,6 +54,7 @@ struct MotorScooterCostingOptionsInner {
 ///
 /// Factors unique to travel by motor_scooter influence the resulting route.
 #[derive(Serialize, Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct MotorScooterCostingOptions {
     motor_scooter: MotorScooterCostingOptionsInner,
 }
@@ -62,6 +64,41 @@ impl MotorScooterCostingOptions {
     pub fn builder() -> Self {
         Self::default()
     }
+    /// Checks the options against the ranges valhalla accepts, reporting every violation
+    pub fn validate(&self) -> Result<(), Vec<super::RangeViolation>> {
+        let mut check = super::RangeCheck::default();
+        check.check("top_speed", self.motor_scooter.top_speed, 10.0, 252.0);
+        // 0 disables the fixed speed
+        let fixed_speed = self.motor_scooter.fixed_speed.filter(|speed| *speed != 0);
+        check.check("fixed_speed", fixed_speed, 1.0, 252.0);
+        check.check(
+            "closure_factor",
+            self.motor_scooter.closure_factor,
+            1.0,
+            10.0,
+        );
+        check.willingness("use_ferry", self.motor_scooter.use_ferry);
+        check.willingness("use_highways", self.motor_scooter.use_highways);
+        check.willingness("use_tolls", self.motor_scooter.use_tolls);
+        check.willingness("use_living_streets", self.motor_scooter.use_living_streets);
+        check.willingness("use_tracks", self.motor_scooter.use_tracks);
+        check.willingness("use_distance", self.motor_scooter.use_distance);
+        check.willingness("use_primary", self.motor_scooter.use_primary);
+        check.willingness("use_hills", self.motor_scooter.use_hills);
+        check.finish()
+    }
+
+    /// A penalty applied when transitioning between roads that do not have consistent naming–in
+    /// other words, no road names in common.
+    ///
+    /// This penalty can be used to create simpler routes that tend to have fewer maneuvers or
+    /// narrative guidance instructions.
+    ///
+    /// Default: `5` seconds
+    pub fn maneuver_penalty(mut self, penalty: f32) -> Self {
+        self.motor_scooter.maneuver_penalty = Some(penalty);
+        self
+    }
 
     /// A cost applied when a [gate](http://wiki.openstreetmap.org/wiki/Tag:barrier%3Dgate) with
     /// undefined or private access is encountered.
@@ -134,10 +171,8 @@ impl MotorScooterCostingOptions {
     /// **Note:** sometimes ferries are required to complete a route so values of `0` are not guaranteed to avoid ferries entirely.
     ///
     /// Default: `0.5`
-    pub fn use_ferry(mut self, use_ferry: f32) -> Self {
-        debug_assert!(use_ferry >= 0.0);
-        debug_assert!(use_ferry <= 1.0);
-        self.motor_scooter.use_ferry = Some(use_ferry);
+    pub fn use_ferry(mut self, use_ferry: impl Into<super::Willingness>) -> Self {
+        self.motor_scooter.use_ferry = Some(use_ferry.into());
         self
     }
     /// This value indicates the willingness to take highways.
@@ -149,10 +184,8 @@ impl MotorScooterCostingOptions {
     /// **Note:** sometimes highways are required to complete a route so values of `0` are not guaranteed to avoid highways entirely.
     ///
     /// Default: `1.0`
-    pub fn use_highways(mut self, use_highways: f32) -> Self {
-        debug_assert!(use_highways >= 0.0);
-        debug_assert!(use_highways <= 1.0);
-        self.motor_scooter.use_highways = Some(use_highways);
+    pub fn use_highways(mut self, use_highways: impl Into<super::Willingness>) -> Self {
+        self.motor_scooter.use_highways = Some(use_highways.into());
         self
     }
     /// This value indicates the willingness to take roads with tolls.
@@ -164,10 +197,8 @@ impl MotorScooterCostingOptions {
     /// **Note:** sometimes roads with tolls are required to complete a route so values of `0` are not guaranteed to avoid them entirely.
     ///
     /// Default: `0.5`
-    pub fn use_tolls(mut self, use_tolls: f32) -> Self {
-        debug_assert!(use_tolls >= 0.0);
-        debug_assert!(use_tolls <= 1.0);
-        self.motor_scooter.use_tolls = Some(use_tolls);
+    pub fn use_tolls(mut self, use_tolls: impl Into<super::Willingness>) -> Self {
+        self.motor_scooter.use_tolls = Some(use_tolls.into());
         self
     }
     /// This value indicates the willingness to take living streets.
@@ -181,10 +212,8 @@ impl MotorScooterCostingOptions {
     /// Default:
     /// - `truck`: `0`
     /// - `cars`/`buses`/`motor scooters`/`motorcycles`: `0.1`
-    pub fn use_living_streets(mut self, use_living_streets: f32) -> Self {
-        debug_assert!(use_living_streets >= 0.0);
-        debug_assert!(use_living_streets <= 1.0);
-        self.motor_scooter.use_living_streets = Some(use_living_streets);
+    pub fn use_living_streets(mut self, use_living_streets: impl Into<super::Willingness>) -> Self {
+        self.motor_scooter.use_living_streets = Some(use_living_streets.into());
         self
     }
     /// This value indicates the willingness to take track roads.
@@ -198,10 +227,8 @@ impl MotorScooterCostingOptions {
     /// Default:
     /// - `0` for autos,
     /// - `0.5` for motor scooters and motorcycles.
-    pub fn use_tracks(mut self, use_tracks: f32) -> Self {
-        debug_assert!(use_tracks >= 0.0);
-        debug_assert!(use_tracks <= 1.0);
-        self.motor_scooter.use_tracks = Some(use_tracks);
+    pub fn use_tracks(mut self, use_tracks: impl Into<super::Willingness>) -> Self {
+        self.motor_scooter.use_tracks = Some(use_tracks.into());
         self
     }
     /// A penalty applied for transition to generic service road.
@@ -258,10 +285,8 @@ impl MotorScooterCostingOptions {
     /// - `1` only distance.
     ///
     /// **Note:** this costing is currently only available for [`super::Costing::Auto`].
-    pub fn use_distance(mut self, use_distance: f32) -> Self {
-        debug_assert!(use_distance >= 0.0);
-        debug_assert!(use_distance <= 1.0);
-        self.motor_scooter.use_distance = Some(use_distance);
+    pub fn use_distance(mut self, use_distance: impl Into<super::Willingness>) -> Self {
+        self.motor_scooter.use_distance = Some(use_distance.into());
         self
     }
     /// Disable hierarchies to calculate the actual optimal route.
@@ -277,27 +302,29 @@ impl MotorScooterCostingOptions {
     /// Top speed the vehicle can go.
     ///
     /// Also used to avoid roads with higher speeds than this value.
-    /// Must be between `10` and `252 KPH`.
+    /// Must be between `10` and `252 KPH`, see [`Self::validate`].
     ///
     /// Default:
     /// - `truck`: `120 KPH`
     /// - `auto`/`bus`: `140 KPH`
     pub fn top_speed(mut self, top_speed: f32) -> Self {
-        debug_assert!(top_speed >= 10.0);
-        debug_assert!(top_speed <= 252.0);
         self.motor_scooter.top_speed = Some(top_speed);
         self
     }
+    /// Top speed the vehicle can go in miles per hour.
+    ///
+    /// Converted to KPH, see [`Self::top_speed`].
+    pub fn top_speed_mph(self, top_speed_mph: f32) -> Self {
+        self.top_speed(super::mph_to_kph(top_speed_mph))
+    }
     /// Fixed speed the vehicle can go. Used to override the calculated speed.
     ///
     /// Can be useful if speed of vehicle is known.
-    /// Must be between `1` and `252 KPH`.
+    /// Must be `0` or between `1` and `252 KPH`, see [`Self::validate`].
     ///
     /// Default: `0KPH` which disables fixed speed and falls back to the standard calculated speed
     /// based on the road attribution.
     pub fn fixed_speed(mut self, fixed_speed: u32) -> Self {
-        debug_assert!(fixed_speed >= 1);
-        debug_assert!(fixed_speed <= 252);
         self.motor_scooter.fixed_speed = Some(fixed_speed);
         self
     }
@@ -448,8 +475,8 @@ impl MotorScooterCostingOptions {
     /// in an attempt to avoid them when finding the best path.
     ///
     /// Default: `0.5`
-    pub fn use_primary(mut self, use_primary: f32) -> Self {
-        self.motor_scooter.use_primary = Some(use_primary);
+    pub fn use_primary(mut self, use_primary: impl Into<super::Willingness>) -> Self {
+        self.motor_scooter.use_primary = Some(use_primary.into());
         self
     }
     /// A rider's desire to tackle hills in their routes.
@@ -466,13 +493,14 @@ impl MotorScooterCostingOptions {
     /// (for example when route locations are in mountainous areas).
     ///
     /// Default: `0.5`
-    pub fn use_hills(mut self, use_hills: f32) -> Self {
-        self.motor_scooter.use_hills = Some(use_hills);
+    pub fn use_hills(mut self, use_hills: impl Into<super::Willingness>) -> Self {
+        self.motor_scooter.use_hills = Some(use_hills.into());
         self
     }
 }
 
 #[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub enum UsedSpeedSources {
     #[serde(rename = "all")]
     All,
@@ -2,6 +2,7 @@ use serde::Serialize;
 
 #[serde_with::skip_serializing_none]
 #[derive(Serialize, Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 struct MotorcycleCostingOptionsInner {
     maneuver_penalty: Option<f32>,
     gate_cost: Option<f32>,
@@ -11,17 +12,17 @@ struct MotorcycleCostingOptionsInner {
     toll_booth_cost: Option<f32>,
     toll_booth_penalty: Option<f32>,
     ferry_cost: Option<f32>,
-    use_ferry: Option<f32>,
-    use_highways: Option<f32>,
-    use_tolls: Option<f32>,
-    use_living_streets: Option<f32>,
-    use_tracks: Option<f32>,
+    use_ferry: Option<super::Willingness>,
+    use_highways: Option<super::Willingness>,
+    use_tolls: Option<super::Willingness>,
+    use_living_streets: Option<super::Willingness>,
+    use_tracks: Option<super::Willingness>,
     service_penalty: Option<f32>,
     service_factor: Option<f32>,
     country_crossing_cost: Option<f32>,
     country_crossing_penalty: Option<f32>,
     shortest: Option<bool>,
-    use_distance: Option<f32>,
+    use_distance: Option<super::Willingness>,
     disable_hierarchy_pruning: Option<bool>,
     top_speed: Option<f32>,
     fixed_speed: Option<u32>,
@@ -41,13 +42,14 @@ struct MotorcycleCostingOptionsInner {
     include_hov3: Option<bool>,
     include_hot: Option<bool>,
     // -- ↓ motorcycle only ↓ --
-    use_trails: Option<f32>,
+    use_trails: Option<super::Willingness>,
 }
 
 /// By default, motorcycle costing will default to higher class roads.
 /// The costing model recognizes factors unique to motorcycle travel and offers options for tuning
 /// motorcycle routes.
 #[derive(Serialize, Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct MotorcycleCostingOptions {
     motorcycle: MotorcycleCostingOptionsInner,
 }
@@ -56,6 +58,35 @@ impl MotorcycleCostingOptions {
     pub fn builder() -> Self {
         Self::default()
     }
+    /// Checks the options against the ranges valhalla accepts, reporting every violation
+    pub fn validate(&self) -> Result<(), Vec<super::RangeViolation>> {
+        let mut check = super::RangeCheck::default();
+        check.check("top_speed", self.motorcycle.top_speed, 10.0, 252.0);
+        // 0 disables the fixed speed
+        let fixed_speed = self.motorcycle.fixed_speed.filter(|speed| *speed != 0);
+        check.check("fixed_speed", fixed_speed, 1.0, 252.0);
+        check.check("closure_factor", self.motorcycle.closure_factor, 1.0, 10.0);
+        check.willingness("use_ferry", self.motorcycle.use_ferry);
+        check.willingness("use_highways", self.motorcycle.use_highways);
+        check.willingness("use_tolls", self.motorcycle.use_tolls);
+        check.willingness("use_living_streets", self.motorcycle.use_living_streets);
+        check.willingness("use_tracks", self.motorcycle.use_tracks);
+        check.willingness("use_distance", self.motorcycle.use_distance);
+        check.willingness("use_trails", self.motorcycle.use_trails);
+        check.finish()
+    }
+
+    /// A penalty applied when transitioning between roads that do not have consistent naming–in
+    /// other words, no road names in common.
+    ///
+    /// This penalty can be used to create simpler routes that tend to have fewer maneuvers or
+    /// narrative guidance instructions.
+    ///
+    /// Default: `5` seconds
+    pub fn maneuver_penalty(mut self, penalty: f32) -> Self {
+        self.motorcycle.maneuver_penalty = Some(penalty);
+        self
+    }
 
     /// A cost applied when a [gate](http://wiki.openstreetmap.org/wiki/Tag:barrier%3Dgate) with
     /// undefined or private access is encountered.
@@ -128,10 +159,8 @@ impl MotorcycleCostingOptions {
     /// **Note:** sometimes ferries are required to complete a route so values of `0` are not guaranteed to avoid ferries entirely.
     ///
     /// Default: `0.5`
-    pub fn use_ferry(mut self, use_ferry: f32) -> Self {
-        debug_assert!(use_ferry >= 0.0);
-        debug_assert!(use_ferry <= 1.0);
-        self.motorcycle.use_ferry = Some(use_ferry);
+    pub fn use_ferry(mut self, use_ferry: impl Into<super::Willingness>) -> Self {
+        self.motorcycle.use_ferry = Some(use_ferry.into());
         self
     }
     /// This value indicates the willingness to take highways.
@@ -143,10 +172,8 @@ impl MotorcycleCostingOptions {
     /// **Note:** sometimes highways are required to complete a route so values of `0` are not guaranteed to avoid highways entirely.
     ///
     /// Default: `1.0`
-    pub fn use_highways(mut self, use_highways: f32) -> Self {
-        debug_assert!(use_highways >= 0.0);
-        debug_assert!(use_highways <= 1.0);
-        self.motorcycle.use_highways = Some(use_highways);
+    pub fn use_highways(mut self, use_highways: impl Into<super::Willingness>) -> Self {
+        self.motorcycle.use_highways = Some(use_highways.into());
         self
     }
     /// This value indicates the willingness to take roads with tolls.
@@ -158,10 +185,8 @@ impl MotorcycleCostingOptions {
     /// **Note:** sometimes roads with tolls are required to complete a route so values of `0` are not guaranteed to avoid them entirely.
     ///
     /// Default: `0.5`
-    pub fn use_tolls(mut self, use_tolls: f32) -> Self {
-        debug_assert!(use_tolls >= 0.0);
-        debug_assert!(use_tolls <= 1.0);
-        self.motorcycle.use_tolls = Some(use_tolls);
+    pub fn use_tolls(mut self, use_tolls: impl Into<super::Willingness>) -> Self {
+        self.motorcycle.use_tolls = Some(use_tolls.into());
         self
     }
     /// This value indicates the willingness to take living streets.
@@ -175,10 +200,8 @@ impl MotorcycleCostingOptions {
     /// Default:
     /// - `truck`: `0`
     /// - `cars`/`buses`/`motor scooters`/`motorcycles`: `0.1`
-    pub fn use_living_streets(mut self, use_living_streets: f32) -> Self {
-        debug_assert!(use_living_streets >= 0.0);
-        debug_assert!(use_living_streets <= 1.0);
-        self.motorcycle.use_living_streets = Some(use_living_streets);
+    pub fn use_living_streets(mut self, use_living_streets: impl Into<super::Willingness>) -> Self {
+        self.motorcycle.use_living_streets = Some(use_living_streets.into());
         self
     }
     /// This value indicates the willingness to take track roads.
@@ -192,10 +215,8 @@ impl MotorcycleCostingOptions {
     /// Default:
     /// - `0` for autos,
     /// - `0.5` for motor scooters and motorcycles.
-    pub fn use_tracks(mut self, use_tracks: f32) -> Self {
-        debug_assert!(use_tracks >= 0.0);
-        debug_assert!(use_tracks <= 1.0);
-        self.motorcycle.use_tracks = Some(use_tracks);
+    pub fn use_tracks(mut self, use_tracks: impl Into<super::Willingness>) -> Self {
+        self.motorcycle.use_tracks = Some(use_tracks.into());
         self
     }
     /// A penalty applied for transition to generic service road.
@@ -252,10 +273,8 @@ impl MotorcycleCostingOptions {
     /// - `1` only distance.
     ///
     /// **Note:** this costing is currently only available for [`super::Costing::Auto`].
-    pub fn use_distance(mut self, use_distance: f32) -> Self {
-        debug_assert!(use_distance >= 0.0);
-        debug_assert!(use_distance <= 1.0);
-        self.motorcycle.use_distance = Some(use_distance);
+    pub fn use_distance(mut self, use_distance: impl Into<super::Willingness>) -> Self {
+        self.motorcycle.use_distance = Some(use_distance.into());
         self
     }
     /// Disable hierarchies to calculate the actual optimal route.
@@ -271,27 +290,29 @@ impl MotorcycleCostingOptions {
     /// Top speed the vehicle can go.
     ///
     /// Also used to avoid roads with higher speeds than this value.
-    /// Must be between `10` and `252 KPH`.
+    /// Must be between `10` and `252 KPH`, see [`Self::validate`].
     ///
     /// Default:
     /// - `truck`: `120 KPH`
     /// - `auto`/`bus`: `140 KPH`
     pub fn top_speed(mut self, top_speed: f32) -> Self {
-        debug_assert!(top_speed >= 10.0);
-        debug_assert!(top_speed <= 252.0);
         self.motorcycle.top_speed = Some(top_speed);
         self
     }
+    /// Top speed the vehicle can go in miles per hour.
+    ///
+    /// Converted to KPH, see [`Self::top_speed`].
+    pub fn top_speed_mph(self, top_speed_mph: f32) -> Self {
+        self.top_speed(super::mph_to_kph(top_speed_mph))
+    }
     /// Fixed speed the vehicle can go. Used to override the calculated speed.
     ///
     /// Can be useful if speed of vehicle is known.
-    /// Must be between `1` and `252 KPH`.
+    /// Must be `0` or between `1` and `252 KPH`, see [`Self::validate`].
     ///
     /// Default: `0KPH` which disables fixed speed and falls back to the standard calculated speed
     /// based on the road attribution.
     pub fn fixed_speed(mut self, fixed_speed: u32) -> Self {
-        debug_assert!(fixed_speed >= 1);
-        debug_assert!(fixed_speed <= 252);
         self.motorcycle.fixed_speed = Some(fixed_speed);
         self
     }
@@ -439,13 +460,14 @@ impl MotorcycleCostingOptions {
     /// - values near `1` will ftend to avoid major roads and route on secondary roads.
     ///
     /// Default: `0.0`.
-    pub fn use_trails(mut self, use_trails: f32) -> Self {
-        self.motorcycle.use_trails = Some(use_trails);
+    pub fn use_trails(mut self, use_trails: impl Into<super::Willingness>) -> Self {
+        self.motorcycle.use_trails = Some(use_trails.into());
         self
     }
 }
 
 #[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub enum UsedSpeedSources {
     #[serde(rename = "all")]
     All,
@@ -2,6 +2,7 @@ use serde::Serialize;
 
 #[serde_with::skip_serializing_none]
 #[derive(Serialize, Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct MultimodalCostingOptions {
     pedestrian: Option<super::pedestrian::PedestrianCostingOptionsInner>,
     transit: Option<super::transit::TransitCostingOptionsInner>,
@@ -11,6 +12,17 @@ impl MultimodalCostingOptions {
     pub fn builder() -> Self {
         Self::default()
     }
+    /// Checks the options against the ranges valhalla accepts, reporting every violation
+    pub fn validate(&self) -> Result<(), Vec<super::RangeViolation>> {
+        let mut check = super::RangeCheck::default();
+        if let Some(pedestrian) = &self.pedestrian {
+            pedestrian.check(&mut check);
+        }
+        if let Some(transit) = &self.transit {
+            transit.check(&mut check);
+        }
+        check.finish()
+    }
     /// Allows configuration of the transit Costing options
     ///
     /// See [`super::transit::TransitCostingOptions`] for further details on options
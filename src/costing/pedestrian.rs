@@ -2,6 +2,7 @@ use serde::Serialize;
 
 #[serde_with::skip_serializing_none]
 #[derive(Serialize, Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub(crate) struct PedestrianCostingOptionsInner {
     walking_speed: Option<f32>,
     walkway_factor: Option<f32>,
@@ -9,11 +10,11 @@ pub(crate) struct PedestrianCostingOptionsInner {
     alley_factor: Option<f32>,
     driveway_factor: Option<f32>,
     step_penalty: Option<f32>,
-    use_ferry: Option<f32>,
-    use_living_streets: Option<f32>,
-    use_tracks: Option<f32>,
-    use_hills: Option<f32>,
-    use_lit: Option<f32>,
+    use_ferry: Option<super::Willingness>,
+    use_living_streets: Option<super::Willingness>,
+    use_tracks: Option<super::Willingness>,
+    use_hills: Option<super::Willingness>,
+    use_lit: Option<super::Willingness>,
     service_penalty: Option<f32>,
     service_factor: Option<f32>,
     destination_only_penalty: Option<f32>,
@@ -27,7 +28,24 @@ pub(crate) struct PedestrianCostingOptionsInner {
     r#type: Option<PedestrianType>,
     mode_factor: Option<f32>,
 }
+impl PedestrianCostingOptionsInner {
+    pub(crate) fn check(&self, check: &mut super::RangeCheck) {
+        check.check("walking_speed", self.walking_speed, 0.5, 25.0);
+        check.check(
+            "max_hiking_difficulty",
+            self.max_hiking_difficulty,
+            0.0,
+            6.0,
+        );
+        check.willingness("use_ferry", self.use_ferry);
+        check.willingness("use_living_streets", self.use_living_streets);
+        check.willingness("use_tracks", self.use_tracks);
+        check.willingness("use_hills", self.use_hills);
+        check.willingness("use_lit", self.use_lit);
+    }
+}
 #[derive(Serialize, Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct PedestrianCostingOptions {
     pub(crate) pedestrian: PedestrianCostingOptionsInner,
 }
@@ -36,18 +54,28 @@ impl PedestrianCostingOptions {
     pub fn builder() -> Self {
         Self::default()
     }
+    /// Checks the options against the ranges valhalla accepts, reporting every violation
+    pub fn validate(&self) -> Result<(), Vec<super::RangeViolation>> {
+        let mut check = super::RangeCheck::default();
+        self.pedestrian.check(&mut check);
+        check.finish()
+    }
 
     /// Walking speed in kilometers per hour.
     ///
-    /// Must be between  `0.5`  and `25 km/hr`.
+    /// Must be between `0.5` and `25 km/hr`, see [`Self::validate`].
     ///
     /// Default: `5.1 km/hr` (`3.1 miles/hour`)
     pub fn walking_speed(mut self, walking_speed: f32) -> Self {
-        debug_assert!(walking_speed >= 0.5);
-        debug_assert!(walking_speed <= 25.0);
         self.pedestrian.walking_speed = Some(walking_speed);
         self
     }
+    /// Walking speed in miles per hour.
+    ///
+    /// Converted to km/hr, see [`Self::walking_speed`].
+    pub fn walking_speed_mph(self, walking_speed_mph: f32) -> Self {
+        self.walking_speed(super::mph_to_kph(walking_speed_mph))
+    }
     /// A factor that modifies the cost when encountering roads classified as `footway`
     /// (no motorized vehicles allowed), which may be designated footpaths or designated sidewalks
     /// along residential roads.
@@ -107,10 +135,8 @@ impl PedestrianCostingOptions {
     /// guaranteed to avoid ferries entirely
     ///
     /// Default: `0.5`
-    pub fn use_ferry(mut self, use_ferry: f32) -> Self {
-        debug_assert!(use_ferry >= 0.0);
-        debug_assert!(use_ferry <= 1.0);
-        self.pedestrian.use_ferry = Some(use_ferry);
+    pub fn use_ferry(mut self, use_ferry: impl Into<super::Willingness>) -> Self {
+        self.pedestrian.use_ferry = Some(use_ferry.into());
         self
     }
     /// Willingness to take living streets.
@@ -123,10 +149,8 @@ impl PedestrianCostingOptions {
     /// not guaranteed to avoid living streets entirely
     ///
     /// Default: `0.6`
-    pub fn use_living_streets(mut self, use_living_streets: f32) -> Self {
-        debug_assert!(use_living_streets >= 0.0);
-        debug_assert!(use_living_streets <= 1.0);
-        self.pedestrian.use_living_streets = Some(use_living_streets);
+    pub fn use_living_streets(mut self, use_living_streets: impl Into<super::Willingness>) -> Self {
+        self.pedestrian.use_living_streets = Some(use_living_streets.into());
         self
     }
     /// Willingness to take track roads.
@@ -139,10 +163,8 @@ impl PedestrianCostingOptions {
     /// guaranteed to avoid tracks entirely
     ///
     /// Default: `0.5`
-    pub fn use_tracks(mut self, use_tracks: f32) -> Self {
-        debug_assert!(use_tracks >= 0.0);
-        debug_assert!(use_tracks <= 1.0);
-        self.pedestrian.use_tracks = Some(use_tracks);
+    pub fn use_tracks(mut self, use_tracks: impl Into<super::Willingness>) -> Self {
+        self.pedestrian.use_tracks = Some(use_tracks.into());
         self
     }
     /// Desire to tackle hills in routes.
@@ -157,8 +179,8 @@ impl PedestrianCostingOptions {
     /// avoid hills (for example when route locations are in mountainous areas).
     ///
     /// Default: `0.5`
-    pub fn use_hills(mut self, use_hills: f32) -> Self {
-        self.pedestrian.use_hills = Some(use_hills);
+    pub fn use_hills(mut self, use_hills: impl Into<super::Willingness>) -> Self {
+        self.pedestrian.use_hills = Some(use_hills.into());
         self
     }
 
@@ -171,10 +193,8 @@ impl PedestrianCostingOptions {
     /// **Note:** even values near `1`, there is no guarantee the returned route will include lit segments.
     ///
     /// Default: `0`
-    pub fn use_lit(mut self, use_lit: f32) -> Self {
-        debug_assert!(use_lit >= 0.0);
-        debug_assert!(use_lit <= 1.0);
-        self.pedestrian.use_lit = Some(use_lit);
+    pub fn use_lit(mut self, use_lit: impl Into<super::Willingness>) -> Self {
+        self.pedestrian.use_lit = Some(use_lit.into());
         self
     }
     /// A penalty applied for transition to generic service road.
@@ -199,15 +219,13 @@ impl PedestrianCostingOptions {
     }
     /// Maximum difficulty of hiking trails that is allowed.
     ///
-    /// Values between `0` and `6` are allowed.
+    /// Values between `0` and `6` are allowed, see [`Self::validate`].
     /// The values correspond to [`sac_scale`](https://wiki.openstreetmap.org/wiki/Key:sac_scale)
     /// values within OpenStreetMap.
     /// Higher difficulty trails can be allowed by specifying a higher value.
     ///
     /// Default: `1` (well cleared trails that are mostly flat or slightly sloped are allowed)
     pub fn max_hiking_difficulty(mut self, max_hiking_difficulty: f32) -> Self {
-        debug_assert!(max_hiking_difficulty >= 0.0);
-        debug_assert!(max_hiking_difficulty <= 6.0);
         self.pedestrian.max_hiking_difficulty = Some(max_hiking_difficulty);
         self
     }
@@ -291,6 +309,7 @@ impl PedestrianCostingOptions {
 }
 
 #[derive(Serialize, Default, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub enum PedestrianType {
     #[default]
     #[serde(rename = "foot")]
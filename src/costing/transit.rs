@@ -2,14 +2,23 @@ use serde::Serialize;
 
 #[serde_with::skip_serializing_none]
 #[derive(Serialize, Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub(crate) struct TransitCostingOptionsInner {
-    use_bus: Option<f32>,
-    use_rail: Option<f32>,
-    use_transfers: Option<f32>,
+    use_bus: Option<super::Willingness>,
+    use_rail: Option<super::Willingness>,
+    use_transfers: Option<super::Willingness>,
     filters: Option<Filters>,
 }
+impl TransitCostingOptionsInner {
+    pub(crate) fn check(&self, check: &mut super::RangeCheck) {
+        check.willingness("use_bus", self.use_bus);
+        check.willingness("use_rail", self.use_rail);
+        check.willingness("use_transfers", self.use_transfers);
+    }
+}
 
 #[derive(Serialize, Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct TransitCostingOptions {
     pub(crate) transit: TransitCostingOptionsInner,
 }
@@ -23,8 +32,8 @@ impl TransitCostingOptions {
     /// Range of values from
     /// - `0` (try to avoid buses) to
     /// - `1` (strong preference for riding buses).
-    pub fn use_bus(mut self, use_bus: f32) -> Self {
-        self.transit.use_bus = Some(use_bus);
+    pub fn use_bus(mut self, use_bus: impl Into<super::Willingness>) -> Self {
+        self.transit.use_bus = Some(use_bus.into());
         self
     }
     /// User's desire to use rail/subway/metro.
@@ -32,8 +41,8 @@ impl TransitCostingOptions {
     /// Range of values from
     /// - `0` (try to avoid rail) to
     /// - `1` (strong preference for riding rail).
-    pub fn use_rail(mut self, use_rail: f32) -> Self {
-        self.transit.use_rail = Some(use_rail);
+    pub fn use_rail(mut self, use_rail: impl Into<super::Willingness>) -> Self {
+        self.transit.use_rail = Some(use_rail.into());
         self
     }
     /// User's desire to favor transfers.
@@ -41,8 +50,8 @@ impl TransitCostingOptions {
     /// Range of values from
     /// - `0` (try to avoid transfers) to
     /// - `1` (totally comfortable with transfers).
-    pub fn use_transfers(mut self, use_transfers: f32) -> Self {
-        self.transit.use_transfers = Some(use_transfers);
+    pub fn use_transfers(mut self, use_transfers: impl Into<super::Willingness>) -> Self {
+        self.transit.use_transfers = Some(use_transfers.into());
         self
     }
     /// Sets a filter for one or more ~~`stops`~~ (TODO: need to re-enable)
@@ -142,6 +151,7 @@ impl TransitCostingOptions {
 }
 
 #[derive(Serialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub enum Action {
     /// Include only the `ids` listed in the filter
     #[default]
@@ -152,6 +162,7 @@ pub enum Action {
 
 #[serde_with::skip_serializing_none]
 #[derive(Serialize, Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 struct Filters {
     routes: Option<Filter>,
     operators: Option<Filter>,
@@ -159,6 +170,7 @@ struct Filters {
 }
 
 #[derive(Serialize, Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 struct Filter {
     ids: Vec<String>,
     action: Action,
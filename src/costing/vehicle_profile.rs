@@ -0,0 +1,192 @@
+use super::{AutoCostingOptions, TruckCostingOptions};
+use serde::{Deserialize, Serialize};
+
+/// Physical properties of a vehicle, independent of the costing model
+///
+/// Fleets can store a profile per vehicle (e.g. as TOML or JSON via serde) and turn it into the
+/// costing options of a request via [`Self::truck`] or [`Self::auto`].
+/// Properties left unset fall back to valhalla's defaults.
+///
+/// # Example:
+/// ```rust
+/// use valhalla_client::costing::{Costing, TruckCostingOptions, VehicleProfile};
+///
+/// let profile: VehicleProfile = serde_json::from_str(
+///     r#"{"height": 4.0, "weight": 18.0, "axle_count": 3, "hazmat": true}"#,
+/// ).unwrap();
+/// let costing = Costing::Truck(profile.truck(TruckCostingOptions::builder().use_tolls(0.2)));
+/// ```
+#[serde_with::skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct VehicleProfile {
+    /// Height in meters
+    pub height: Option<f32>,
+    /// Width in meters
+    pub width: Option<f32>,
+    /// Length in meters
+    pub length: Option<f32>,
+    /// Weight in metric tons
+    pub weight: Option<f32>,
+    /// Axle load in metric tons
+    pub axle_load: Option<f32>,
+    /// Number of axles
+    pub axle_count: Option<u32>,
+    /// Whether the vehicle carries hazardous materials
+    pub hazmat: Option<bool>,
+    /// Top speed in KPH
+    pub top_speed: Option<f32>,
+}
+
+impl VehicleProfile {
+    #[must_use]
+    pub fn builder() -> Self {
+        Self::default()
+    }
+    /// Height of the vehicle in meters
+    pub fn height(mut self, height: f32) -> Self {
+        self.height = Some(height);
+        self
+    }
+    /// Width of the vehicle in meters
+    pub fn width(mut self, width: f32) -> Self {
+        self.width = Some(width);
+        self
+    }
+    /// Length of the vehicle in meters
+    pub fn length(mut self, length: f32) -> Self {
+        self.length = Some(length);
+        self
+    }
+    /// Weight of the vehicle in metric tons
+    pub fn weight(mut self, weight: f32) -> Self {
+        self.weight = Some(weight);
+        self
+    }
+    /// Axle load of the vehicle in metric tons
+    pub fn axle_load(mut self, axle_load: f32) -> Self {
+        self.axle_load = Some(axle_load);
+        self
+    }
+    /// Number of axles of the vehicle
+    pub fn axle_count(mut self, axle_count: u32) -> Self {
+        self.axle_count = Some(axle_count);
+        self
+    }
+    /// Whether the vehicle carries hazardous materials
+    pub fn hazmat(mut self, hazmat: bool) -> Self {
+        self.hazmat = Some(hazmat);
+        self
+    }
+    /// Top speed of the vehicle in KPH
+    ///
+    /// Must be between `10` and `252 KPH`.
+    pub fn top_speed(mut self, top_speed: f32) -> Self {
+        self.top_speed = Some(top_speed);
+        self
+    }
+    /// Top speed of the vehicle in miles per hour.
+    ///
+    /// Converted to KPH, see [`Self::top_speed`].
+    pub fn top_speed_mph(self, top_speed_mph: f32) -> Self {
+        self.top_speed(super::mph_to_kph(top_speed_mph))
+    }
+
+    /// Applies all properties of this profile to `options`
+    pub fn truck(&self, mut options: TruckCostingOptions) -> TruckCostingOptions {
+        if let Some(height) = self.height {
+            options = options.height(height);
+        }
+        if let Some(width) = self.width {
+            options = options.width(width);
+        }
+        if let Some(length) = self.length {
+            options = options.length(length);
+        }
+        if let Some(weight) = self.weight {
+            options = options.weight(weight);
+        }
+        if let Some(axle_load) = self.axle_load {
+            options = options.axle_load(axle_load);
+        }
+        if let Some(axle_count) = self.axle_count {
+            options = options.axle_count(axle_count);
+        }
+        if self.hazmat == Some(true) {
+            options = options.carries_hazardous_materials();
+        }
+        if let Some(top_speed) = self.top_speed {
+            options = options.top_speed(top_speed);
+        }
+        options
+    }
+    /// Applies the properties the auto costing knows about to `options`
+    ///
+    /// These are [`Self::height`], [`Self::width`] and [`Self::top_speed`].
+    /// The remaining properties are only considered by [`Self::truck`].
+    pub fn auto(&self, mut options: AutoCostingOptions) -> AutoCostingOptions {
+        if let Some(height) = self.height {
+            options = options.height(height);
+        }
+        if let Some(width) = self.width {
+            options = options.width(width);
+        }
+        if let Some(top_speed) = self.top_speed {
+            options = options.top_speed(top_speed);
+        }
+        options
+    }
+}
+
+impl From<&VehicleProfile> for TruckCostingOptions {
+    fn from(profile: &VehicleProfile) -> Self {
+        profile.truck(Self::default())
+    }
+}
+
+impl From<&VehicleProfile> for AutoCostingOptions {
+    fn from(profile: &VehicleProfile) -> Self {
+        profile.auto(Self::default())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    #[test]
+    fn toml_roundtrip_and_conversion() {
+        let profile: VehicleProfile = toml::from_str(
+            r#"
+            height = 4.0
+            length = 16.5
+            axle_count = 3
+            hazmat = true
+            top_speed = 90.0
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            profile,
+            VehicleProfile::builder()
+                .height(4.0)
+                .length(16.5)
+                .axle_count(3)
+                .hazmat(true)
+                .top_speed(90.0)
+        );
+        assert_eq!(
+            toml::from_str::<VehicleProfile>(&toml::to_string(&profile).unwrap()).unwrap(),
+            profile
+        );
+        assert_eq!(
+            serde_json::to_value(TruckCostingOptions::from(&profile)).unwrap(),
+            serde_json::json!({"truck": {
+                "height": 4.0, "length": 16.5, "axle_count": 3, "hazmat": true, "top_speed": 90.0
+            }})
+        );
+        assert_eq!(
+            serde_json::to_value(AutoCostingOptions::from(&profile)).unwrap(),
+            serde_json::json!({"auto": {"height": 4.0, "top_speed": 90.0}})
+        );
+    }
+}
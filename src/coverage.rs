@@ -0,0 +1,118 @@
+//! Which actions and request options of valhalla this crate models, and since when
+//!
+//! Applications which are built against several versions of this crate can detect at runtime
+//! whether a feature is available, instead of consulting the documentation of each version.
+//!
+//! The table starts with version 0.3.0: everything modelled by then is listed as added in 0.3.0.
+//! Additions which have not been released yet are listed with the next version.
+//! Only the top level options of the requests are listed, not those of locations or costing
+//! models.
+//!
+//! # Example:
+//! ```rust
+//! use valhalla_client::coverage;
+//!
+//! assert!(coverage::supports("route", Some("exclude_polygons")));
+//! assert!(!coverage::supports("trace_route", None));
+//! assert_eq!(coverage::since("status", None), Some(&semver::Version::new(0, 3, 0)));
+//! ```
+
+use semver::Version;
+
+/// An action or a request option of an action
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Coverage {
+    /// The action as sent to the server, e.g. `"sources_to_targets"` for the matrix
+    pub action: &'static str,
+    /// The option as serialized into the request, or `None` for the action itself
+    pub option: Option<&'static str>,
+    /// The first version of this crate modelling it
+    pub since: Version,
+}
+
+const fn covered(action: &'static str, option: Option<&'static str>, since: Version) -> Coverage {
+    Coverage {
+        action,
+        option,
+        since,
+    }
+}
+
+const V0_3_0: Version = Version::new(0, 3, 0);
+const V0_4_0: Version = Version::new(0, 4, 0);
+
+/// Everything this crate models
+pub static COVERAGE: &[Coverage] = &[
+    covered("route", None, V0_3_0),
+    covered("route", Some("locations"), V0_3_0),
+    covered("route", Some("costing"), V0_3_0),
+    covered("route", Some("units"), V0_3_0),
+    covered("route", Some("id"), V0_3_0),
+    covered("route", Some("language"), V0_3_0),
+    covered("route", Some("directions_type"), V0_3_0),
+    covered("route", Some("alternates"), V0_3_0),
+    covered("route", Some("exclude_locations"), V0_3_0),
+    covered("route", Some("exclude_polygons"), V0_3_0),
+    covered("route", Some("linear_references"), V0_3_0),
+    covered("route", Some("prioritize_bidirectional"), V0_3_0),
+    covered("route", Some("roundabout_exits"), V0_3_0),
+    covered("sources_to_targets", None, V0_3_0),
+    covered("sources_to_targets", Some("sources"), V0_3_0),
+    covered("sources_to_targets", Some("targets"), V0_3_0),
+    covered("sources_to_targets", Some("costing"), V0_3_0),
+    covered("sources_to_targets", Some("id"), V0_3_0),
+    covered("sources_to_targets", Some("matrix_locations"), V0_3_0),
+    covered("sources_to_targets", Some("date_time"), V0_3_0),
+    covered("sources_to_targets", Some("verbose"), V0_3_0),
+    covered("sources_to_targets", Some("shape_format"), V0_3_0),
+    covered("height", None, V0_3_0),
+    covered("height", Some("id"), V0_3_0),
+    covered("height", Some("height_precision"), V0_3_0),
+    covered("height", Some("range"), V0_3_0),
+    covered("height", Some("resample_distance"), V0_3_0),
+    covered("height", Some("shape"), V0_3_0),
+    covered("height", Some("encoded_polyline"), V0_3_0),
+    covered("height", Some("shape_format"), V0_3_0),
+    covered("status", None, V0_3_0),
+    covered("status", Some("verbose"), V0_3_0),
+    covered("centroid", None, V0_4_0),
+    covered("centroid", Some("locations"), V0_4_0),
+    covered("centroid", Some("costing"), V0_4_0),
+    covered("centroid", Some("units"), V0_4_0),
+    covered("centroid", Some("id"), V0_4_0),
+    covered("centroid", Some("language"), V0_4_0),
+    covered("centroid", Some("directions_type"), V0_4_0),
+];
+
+/// The first version of this crate modelling `option` of `action` (or `action` itself if `None`)
+///
+/// `None` if it is not modelled.
+pub fn since(action: &str, option: Option<&str>) -> Option<&'static Version> {
+    COVERAGE
+        .iter()
+        .find(|c| c.action == action && c.option == option)
+        .map(|c| &c.since)
+}
+
+/// Whether this crate models `option` of `action`, or `action` itself if `None`
+pub fn supports(action: &str, option: Option<&str>) -> bool {
+    since(action, option).is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn options_belong_to_covered_actions() {
+        for entry in COVERAGE.iter().filter(|c| c.option.is_some()) {
+            let action = since(entry.action, None);
+            assert!(
+                action.is_some_and(|since| *since <= entry.since),
+                "{entry:?}"
+            );
+        }
+        assert_eq!(since("centroid", Some("locations")), Some(&V0_4_0));
+        assert!(!supports("route", Some("verbose")));
+    }
+}
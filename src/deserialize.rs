@@ -0,0 +1,171 @@
+use serde::de::DeserializeOwned;
+
+/// How much of the response is shown on either side of the position of an error
+const SNIPPET_CONTEXT: usize = 40;
+
+/// A response of the server which does not match the models of this crate
+///
+/// Points at the offending part of the response, which usually makes it obvious whether the
+/// server returned something unexpected or the models are out of date.
+#[derive(Debug)]
+pub struct DeserializeError {
+    /// The action whose response it is, e.g. `"route"`
+    pub action: String,
+    /// HTTP status code of the response
+    pub status: u16,
+    /// Where in the response deserialization failed, e.g. `trip.legs[0].maneuvers[3].type`
+    ///
+    /// `.` for the response itself
+    pub path: String,
+    /// The part of the response around the error, shortened to a few dozen characters
+    pub snippet: Option<String>,
+    /// The underlying error of serde_json
+    pub source: serde_json::Error,
+}
+
+impl std::fmt::Display for DeserializeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{} at `{}`", self.source, self.path)?;
+        if let Some(snippet) = &self.snippet {
+            write!(f, " near `{snippet}`")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for DeserializeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.source)
+    }
+}
+
+/// Like [`serde_json::from_str`], but remembers where it failed
+pub(crate) fn from_str<T: DeserializeOwned>(
+    action: &str,
+    status: u16,
+    text: &str,
+) -> Result<T, DeserializeError> {
+    let deserializer = &mut serde_json::Deserializer::from_str(text);
+    serde_path_to_error::deserialize(deserializer).map_err(|e| {
+        let path = e.path().to_string();
+        let source = e.into_inner();
+        DeserializeError {
+            action: action.to_string(),
+            status,
+            path,
+            snippet: snippet_at(text, source.line(), source.column()),
+            source,
+        }
+    })
+}
+
+/// Like [`serde_json::from_value`], but remembers where it failed
+pub(crate) fn from_value<T: DeserializeOwned>(
+    action: &str,
+    status: u16,
+    value: &serde_json::Value,
+) -> Result<T, DeserializeError> {
+    serde_path_to_error::deserialize(value).map_err(|e| {
+        let pointer: String = e
+            .path()
+            .iter()
+            .map(|segment| match segment {
+                serde_path_to_error::Segment::Seq { index } => format!("/{index}"),
+                serde_path_to_error::Segment::Map { key } => {
+                    format!("/{}", key.replace('~', "~0").replace('/', "~1"))
+                }
+                serde_path_to_error::Segment::Enum { .. }
+                | serde_path_to_error::Segment::Unknown => String::new(),
+            })
+            .collect();
+        DeserializeError {
+            action: action.to_string(),
+            status,
+            path: e.path().to_string(),
+            snippet: value
+                .pointer(&pointer)
+                .map(|value| shorten(&value.to_string(), 0, 2 * SNIPPET_CONTEXT)),
+            source: e.into_inner(),
+        }
+    })
+}
+
+/// The text around the 1-based `line` and `column`, as reported by serde_json
+fn snippet_at(text: &str, line: usize, column: usize) -> Option<String> {
+    let line = text.lines().nth(line.checked_sub(1)?)?;
+    let position = column.saturating_sub(1).min(line.len());
+    Some(shorten(
+        line,
+        position.saturating_sub(SNIPPET_CONTEXT),
+        position + SNIPPET_CONTEXT,
+    ))
+}
+
+/// `text[start..end]`, widened to character boundaries and marking what was cut off with `…`
+fn shorten(text: &str, start: usize, end: usize) -> String {
+    let start = (0..=start.min(text.len()))
+        .rev()
+        .find(|&i| text.is_char_boundary(i))
+        .unwrap_or(0);
+    let end = (end.min(text.len())..=text.len())
+        .find(|&i| text.is_char_boundary(i))
+        .unwrap_or(text.len());
+    let mut snippet = String::new();
+    if start > 0 {
+        snippet.push('…');
+    }
+    snippet.push_str(&text[start..end]);
+    if end < text.len() {
+        snippet.push('…');
+    }
+    snippet
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(serde::Deserialize, Debug)]
+    struct Response {
+        #[allow(dead_code)]
+        trip: Trip,
+    }
+    #[derive(serde::Deserialize, Debug)]
+    struct Trip {
+        #[allow(dead_code)]
+        legs: Vec<Leg>,
+    }
+    #[derive(serde::Deserialize, Debug)]
+    struct Leg {
+        #[allow(dead_code)]
+        length: f64,
+    }
+
+    #[test]
+    fn points_at_the_error() {
+        let padding = "x".repeat(100);
+        let text = format!(
+            r#"{{"padding": "{padding}", "trip": {{"legs": [{{"length": 1.5}}, {{"length": "far"}}]}}}}"#
+        );
+        let e = from_str::<Response>("route", 200, &text).unwrap_err();
+        assert_eq!(e.path, "trip.legs[1].length");
+        let snippet = e.snippet.as_deref().unwrap();
+        assert!(snippet.starts_with('…') && snippet.contains(r#"{"length": "far"}"#));
+        assert!(e.to_string().contains("at `trip.legs[1].length` near `…"));
+
+        let value: serde_json::Value = serde_json::from_str(&text).unwrap();
+        let e = from_value::<Response>("route", 200, &value).unwrap_err();
+        assert_eq!(e.path, "trip.legs[1].length");
+        assert_eq!(e.snippet.as_deref(), Some(r#""far""#));
+
+        let e = from_str::<Response>("route", 200, r#"{"trip": {}}"#).unwrap_err();
+        assert_eq!(e.path, "trip");
+        assert_eq!(e.snippet.as_deref(), Some(r#"{"trip": {}}"#));
+    }
+
+    #[test]
+    fn shortens_at_char_boundaries() {
+        assert_eq!(shorten("äöü", 3, 4), "…ö…");
+        assert_eq!(shorten("abc", 0, 10), "abc");
+    }
+}
@@ -4,11 +4,13 @@ use serde_json::Value;
 
 #[serde_with::skip_serializing_none]
 #[derive(Serialize, Default, Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct Manifest {
     id: Option<String>,
     height_precision: Option<HeightPrecision>,
     range: Option<bool>,
     resample_distance: Option<f64>,
+    #[serde(serialize_with = "crate::precision::serialize_shape_opt")]
     shape: Option<Vec<ShapePoint>>,
     encoded_polyline: Option<String>,
     shape_format: Option<ShapeFormat>,
@@ -17,6 +19,34 @@ impl Manifest {
     pub fn builder() -> Self {
         Default::default()
     }
+    /// Checks that exactly one of `shape` and `encoded_polyline` is set, and `shape_format` only
+    /// with the latter
+    ///
+    /// See [`crate::validation`].
+    pub fn validate(&self) -> Result<(), crate::ValidationError> {
+        self.validate_with(&Default::default())
+    }
+    /// [`Self::validate`], also checking against the limits of the server
+    pub fn validate_with(
+        &self,
+        limits: &crate::validation::ServiceLimits,
+    ) -> Result<(), crate::ValidationError> {
+        let mut validator = crate::validation::Validator::default();
+        validator.one_of(
+            ("shape", self.shape.is_some()),
+            ("encoded_polyline", self.encoded_polyline.is_some()),
+        );
+        validator.exclusive(
+            ("shape", self.shape.is_some()),
+            ("shape_format", self.shape_format.is_some()),
+        );
+        validator.at_most(
+            "max_shape",
+            self.shape.as_ref().map_or(0, Vec::len) as f64,
+            limits.max_shape.map(|max| max as f64),
+        );
+        validator.finish()
+    }
     /// Name your route request.
     ///
     /// If id is specified, the naming will be sent through to the response.
@@ -55,7 +85,6 @@ impl Manifest {
     ///
     /// Default: [`ShapeFormat::Polyline6`], meaning the encoded polyline is expected to be 6 digit precision.
     pub fn shape_format(mut self, shape_format: ShapeFormat) -> Self {
-        debug_assert!(self.shape.is_none(), "shape is set and setting the shape_format is requested. This combination does not make sense: shapes and encoded_polylines as input are mutually exclusive.");
         self.shape_format = Some(shape_format);
         self
     }
@@ -63,9 +92,9 @@ impl Manifest {
     ///
     /// The input coordinates can come from many input sources, such as a GPS location, a point or a click on a map, a geocoding service, and so on.
     /// The locations are visited in the order specified.
+    /// Accepts [`super::Coordinate`]s as well as geo-types geometry, e.g. a
+    /// [`geo_types::LineString`].
     pub fn shape(mut self, shape: impl IntoIterator<Item = impl Into<ShapePoint>>) -> Self {
-        debug_assert!(self.shape_format.is_none(), "shape_format is set and setting a shape is requested. This combination does not make sense: shapes and encoded_polylines as input are mutually exclusive.");
-        debug_assert!(self.encoded_polyline.is_none(), "encoded_polyline is set and setting a shape is requested. This combination does not make sense: shapes and encoded_polylines as input are mutually exclusive.");
         self.shape = Some(shape.into_iter().map(|s| s.into()).collect());
         self
     }
@@ -74,14 +103,39 @@ impl Manifest {
     /// Details on polyline encoding and decoding can be found [here](https://valhalla.github.io/valhalla/decoding/).
     /// See [`Self::shape_format`] to set the precision of the polyline.
     pub fn encoded_polyline(mut self, encoded_polyline: impl ToString) -> Self {
-        debug_assert!(self.shape.is_none(), "shape is set and setting the encoded_polyline is requested. This combination does not make sense: shapes and encoded_polylines as input are mutually exclusive.");
         self.encoded_polyline = Some(encoded_polyline.to_string());
         self
     }
+    /// Splits a [`Self::shape`] into manifests of at most `max_shape_points` points each
+    ///
+    /// Consecutive parts overlap by one point, so that [`Response::concat`] can continue the
+    /// ranges of the previous part.
+    /// Manifests using an [`Self::encoded_polyline`] or with a short enough shape are not split.
+    pub(crate) fn split(self, max_shape_points: usize) -> Vec<Self> {
+        let max_shape_points = max_shape_points.max(2);
+        let shape = match &self.shape {
+            Some(shape) if shape.len() > max_shape_points => shape,
+            _ => return vec![self],
+        };
+        let mut parts = Vec::new();
+        let mut start = 0;
+        loop {
+            let end = (start + max_shape_points).min(shape.len());
+            parts.push(Self {
+                shape: Some(shape[start..end].to_vec()),
+                ..self.clone()
+            });
+            if end == shape.len() {
+                return parts;
+            }
+            start = end - 1;
+        }
+    }
 }
 
 /// Specifies the precision (number of decimal places) of all returned height values.
 #[derive(serde_repr::Serialize_repr, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema_repr))]
 #[repr(u8)]
 pub enum HeightPrecision {
     /// Zero decimal places (="integer precision") of precision for all height values
@@ -100,6 +154,7 @@ pub enum HeightPrecision {
 }
 
 #[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub enum ShapeFormat {
     /// polyline is encoded with 6 digit precision
     #[serde(rename = "polyline6")]
@@ -148,6 +203,37 @@ pub struct Response {
     pub warnings: Vec<Value>,
 }
 
+impl Response {
+    /// Joins the responses to the parts of [`Manifest::split`] into one
+    ///
+    /// The first point of every part after the first one repeats the last point of the part
+    /// before it and is dropped.
+    /// The ranges of every part are shifted to continue where the part before it ended.
+    pub(crate) fn concat(parts: Vec<Self>) -> Option<Self> {
+        let mut parts = parts.into_iter();
+        let mut joined = parts.next()?;
+        for part in parts {
+            if let (Some(shape), Some(more)) = (&mut joined.shape, part.shape) {
+                shape.extend(more.into_iter().skip(1));
+            }
+            joined.height.extend(part.height.into_iter().skip(1));
+            let offset = joined
+                .range_height
+                .iter()
+                .rev()
+                .find_map(|range_height| range_height.map(|(range, _)| range))
+                .unwrap_or_default();
+            joined.range_height.extend(
+                part.range_height.into_iter().skip(1).map(|range_height| {
+                    range_height.map(|(range, height)| (range + offset, height))
+                }),
+            );
+            joined.warnings.extend(part.warnings);
+        }
+        Some(joined)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -173,4 +259,62 @@ mod tests {
             serde_json::json!({"id":"some_id","height_precision":1,"range":true,"encoded_polyline":"polyline","shape_format":"polyline6"})
         );
     }
+
+    #[test]
+    fn split_and_concat() {
+        let manifest = Manifest::builder()
+            .include_range()
+            .shape((0..5).map(|i| (i as f32, 0.0)));
+        let parts = manifest.clone().split(2);
+        assert_eq!(parts.len(), 4);
+        assert_eq!(
+            parts[1].shape.as_ref().unwrap()[0],
+            ShapePoint { lon: 1.0, lat: 0.0 }
+        );
+        assert_eq!(manifest.clone().split(5), [manifest]);
+
+        let part = |start: f64| Response {
+            id: None,
+            shape: Some(vec![
+                ShapePoint {
+                    lon: start,
+                    lat: 0.0,
+                },
+                ShapePoint {
+                    lon: start + 1.0,
+                    lat: 0.0,
+                },
+            ]),
+            encoded_polyline: None,
+            range_height: vec![Some((0.0, Some(start))), Some((100.0, None))],
+            x_coordinate: None,
+            y_coordinate: None,
+            height: vec![start as f32, start as f32 + 1.0],
+            warnings: vec![],
+        };
+        let joined = Response::concat((0..4).map(|i| part(i as f64)).collect()).unwrap();
+        assert_eq!(joined.shape.unwrap().len(), 5);
+        assert_eq!(joined.height, [0.0, 1.0, 2.0, 3.0, 4.0]);
+        assert_eq!(
+            joined.range_height,
+            [
+                Some((0.0, Some(0.0))),
+                Some((100.0, None)),
+                Some((200.0, None)),
+                Some((300.0, None)),
+                Some((400.0, None))
+            ]
+        );
+        assert!(Response::concat(vec![]).is_none());
+    }
+
+    #[test]
+    fn shape_from_line_string() {
+        let line: geo_types::LineString = vec![(4.9041, 52.3676), (5.1214, 52.0907)].into();
+        let manifest = Manifest::builder().shape(line);
+        assert_eq!(
+            serde_json::to_value(&manifest).unwrap(),
+            serde_json::json!({"shape": [{"lon": 4.9041, "lat": 52.3676}, {"lon": 5.1214, "lat": 52.0907}]})
+        );
+    }
 }
@@ -0,0 +1,415 @@
+//! Helpers for turn-by-turn guidance based on a [`Trip`]
+use crate::route::{Maneuver, Trip};
+use crate::shapes::{LocalProjection, ShapePoint};
+
+/// Which verbal instruction of a [`Maneuver`] to announce
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InstructionKind {
+    /// [`Maneuver::verbal_transition_alert_instruction`], well ahead of the maneuver
+    TransitionAlert,
+    /// [`Maneuver::verbal_pre_transition_instruction`], right before the maneuver
+    PreTransition,
+    /// [`Maneuver::verbal_post_transition_instruction`], once the maneuver is done
+    PostTransition,
+}
+impl InstructionKind {
+    /// The text of this instruction, if valhalla generated one for `maneuver`
+    pub fn instruction(self, maneuver: &Maneuver) -> Option<&str> {
+        match self {
+            Self::TransitionAlert => maneuver.verbal_transition_alert_instruction.as_deref(),
+            Self::PreTransition => maneuver.verbal_pre_transition_instruction.as_deref(),
+            Self::PostTransition => maneuver.verbal_post_transition_instruction.as_deref(),
+        }
+    }
+}
+
+/// When an instruction should be spoken, see [`AnnouncementTiming::schedule`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Announcement {
+    /// Index into [`Trip::legs`]
+    pub leg_index: usize,
+    /// Index into the maneuvers of the leg
+    pub maneuver_index: usize,
+    /// Distance from the start of the leg in meters at which to announce
+    pub distance_along_leg: f64,
+    pub kind: InstructionKind,
+}
+
+/// Configures how far ahead of a maneuver its instructions are announced
+///
+/// The distances scale with the speed on the approach to the maneuver, which reflects the road
+/// class: on a motorway the alert comes kilometres ahead, in a city a few hundred meters.
+///
+/// With the defaults, a maneuver approached at 100 KPH is alerted ~1.7 km and announced
+/// ~280 m ahead, at 50 KPH ~830 m and ~140 m ahead, and at walking speed 100 m and 25 m ahead.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AnnouncementTiming {
+    alert_secs: f64,
+    pre_transition_secs: f64,
+    min_alert_distance: f64,
+    min_pre_transition_distance: f64,
+}
+impl Default for AnnouncementTiming {
+    fn default() -> Self {
+        Self {
+            alert_secs: 60.0,
+            pre_transition_secs: 10.0,
+            min_alert_distance: 100.0,
+            min_pre_transition_distance: 25.0,
+        }
+    }
+}
+impl AnnouncementTiming {
+    pub fn builder() -> Self {
+        Self::default()
+    }
+    /// How many seconds ahead of the maneuver the transition alert is announced
+    ///
+    /// The alert is skipped if the previous maneuver is too short to fit it in.
+    ///
+    /// Default: `60` seconds
+    pub fn alert_secs(mut self, alert_secs: f64) -> Self {
+        self.alert_secs = alert_secs;
+        self
+    }
+    /// How many seconds ahead of the maneuver the pre-transition instruction is announced
+    ///
+    /// Default: `10` seconds
+    pub fn pre_transition_secs(mut self, pre_transition_secs: f64) -> Self {
+        self.pre_transition_secs = pre_transition_secs;
+        self
+    }
+    /// Lower bounds in meters for the alert and pre-transition distances, used at low speeds
+    ///
+    /// Default: `100` and `25` meters
+    pub fn min_distances(mut self, alert: f64, pre_transition: f64) -> Self {
+        self.min_alert_distance = alert;
+        self.min_pre_transition_distance = pre_transition;
+        self
+    }
+
+    /// Timeline of all announcements of `trip`, ordered by leg and distance
+    ///
+    /// Every maneuver is announced via its pre-transition instruction, most maneuvers
+    /// additionally get a transition alert before and a post-transition instruction after it.
+    /// Instructions valhalla did not generate (e.g. with [`crate::route::DirectionsType::Maneuvers`])
+    /// are skipped.
+    pub fn schedule(&self, trip: &Trip) -> Vec<Announcement> {
+        let mut announcements = Vec::new();
+        for (leg_index, leg) in trip.legs.iter().enumerate() {
+            let mut begin = 0.0;
+            let mut approach: Option<(f64, &Maneuver)> = None;
+            for (maneuver_index, maneuver) in leg.maneuvers.iter().enumerate() {
+                let mut announce = |distance_along_leg: f64, kind: InstructionKind| {
+                    if kind.instruction(maneuver).is_some() {
+                        announcements.push(Announcement {
+                            leg_index,
+                            maneuver_index,
+                            distance_along_leg,
+                            kind,
+                        });
+                    }
+                };
+                match approach {
+                    Some((approach_begin, previous)) => {
+                        let approach_length = begin - approach_begin;
+                        let speed = if previous.time > 0.0 {
+                            approach_length / previous.time
+                        } else {
+                            0.0
+                        };
+                        let alert = (speed * self.alert_secs).max(self.min_alert_distance);
+                        if alert < approach_length {
+                            announce(begin - alert, InstructionKind::TransitionAlert);
+                        }
+                        let pre_transition = (speed * self.pre_transition_secs)
+                            .max(self.min_pre_transition_distance)
+                            .min(approach_length);
+                        announce(begin - pre_transition, InstructionKind::PreTransition);
+                    }
+                    None => announce(begin, InstructionKind::PreTransition),
+                }
+                announce(begin, InstructionKind::PostTransition);
+                approach = Some((begin, maneuver));
+                begin += trip.units.to_meters(maneuver.length);
+            }
+        }
+        announcements.sort_by(|a, b| {
+            a.leg_index
+                .cmp(&b.leg_index)
+                .then(a.distance_along_leg.total_cmp(&b.distance_along_leg))
+        });
+        announcements
+    }
+}
+
+/// How fast a [`SimulatedDrive`] moves along the trip
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SpeedProfile {
+    /// A constant speed in meters per second
+    Constant(f64),
+    /// The average speed valhalla estimated for each maneuver
+    Maneuvers,
+}
+
+/// A synthetic GPS fix, see [`SimulatedDrive`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimulatedPosition {
+    /// Seconds since the start of the trip
+    pub time: f64,
+    /// Meters driven since the start of the trip
+    pub distance: f64,
+    /// The position, including noise
+    pub position: ShapePoint,
+}
+
+/// Iterator of synthetic GPS positions along the shape of a [`Trip`]
+///
+/// Yields a position every [`Self::interval_secs`], starting at the first shape point and ending
+/// with the last one.
+/// Useful for deterministic tests of guidance logic such as off-route detection or
+/// [`AnnouncementTiming::schedule`], inside and outside this crate.
+///
+/// # Example:
+/// ```rust,no_run
+/// use valhalla_client::Valhalla;
+/// use valhalla_client::guidance::{SimulatedDrive, SpeedProfile};
+/// use valhalla_client::costing::Costing;
+/// use valhalla_client::route::{Location, ManifestBuilder};
+///
+/// let manifest = ManifestBuilder::default()
+///   .locations([Location::new(4.9041, 52.3676), Location::new(5.1214, 52.0907)])
+///   .costing(Costing::default());
+/// let trip = Valhalla::default().route(manifest).unwrap();
+/// for fix in SimulatedDrive::new(&trip, SpeedProfile::Maneuvers).noise(5.0, 42) {
+///     println!("{:.0}s: {:?}", fix.time, fix.position);
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct SimulatedDrive {
+    /// Straight pieces of the shape with the speed to drive them at
+    segments: Vec<(ShapePoint, ShapePoint, f64)>,
+    interval_secs: f64,
+    noise_meters: f64,
+    rng: u64,
+    segment: usize,
+    /// Meters driven on the current segment
+    offset: f64,
+    time: f64,
+    distance: f64,
+    done: bool,
+}
+impl SimulatedDrive {
+    pub fn new(trip: &Trip, speed: SpeedProfile) -> Self {
+        let mut segments = Vec::new();
+        for leg in &trip.legs {
+            for maneuver in &leg.maneuvers {
+                let speed = match speed {
+                    SpeedProfile::Constant(speed) => speed,
+                    SpeedProfile::Maneuvers if maneuver.time > 0.0 => {
+                        trip.units.to_meters(maneuver.length) / maneuver.time
+                    }
+                    SpeedProfile::Maneuvers => 0.0,
+                };
+                segments.extend(
+                    leg.maneuver_shape(maneuver)
+                        .windows(2)
+                        .map(|w| (w[0].clone(), w[1].clone(), speed)),
+                );
+            }
+        }
+        Self {
+            done: segments.is_empty(),
+            segments,
+            interval_secs: 1.0,
+            noise_meters: 0.0,
+            rng: 0,
+            segment: 0,
+            offset: 0.0,
+            time: 0.0,
+            distance: 0.0,
+        }
+    }
+    /// Seconds between two positions
+    ///
+    /// Default: `1` second
+    ///
+    /// # Panics
+    ///
+    /// If `interval_secs` is not positive, as the drive would then never end
+    pub fn interval_secs(mut self, interval_secs: f64) -> Self {
+        assert!(
+            interval_secs > 0.0,
+            "interval_secs must be positive, not {interval_secs}"
+        );
+        self.interval_secs = interval_secs;
+        self
+    }
+    /// Adds normally distributed noise with a standard deviation of `meters` to each position
+    ///
+    /// The same `seed` always produces the same noise.
+    ///
+    /// Default: no noise
+    pub fn noise(mut self, meters: f64, seed: u64) -> Self {
+        self.noise_meters = meters;
+        // xorshift must not start at zero
+        self.rng = seed | 1;
+        self
+    }
+
+    fn next_random(&mut self) -> f64 {
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 7;
+        self.rng ^= self.rng << 17;
+        (self.rng >> 11) as f64 / (1u64 << 53) as f64
+    }
+    /// Pair of independent standard normal samples via the Box-Muller transform
+    fn next_gaussian(&mut self) -> (f64, f64) {
+        let radius = (-2.0 * (1.0 - self.next_random()).ln()).sqrt();
+        let angle = std::f64::consts::TAU * self.next_random();
+        (radius * angle.cos(), radius * angle.sin())
+    }
+    fn position(&mut self) -> ShapePoint {
+        let (segment, at_end) = match self.segments.get(self.segment) {
+            Some(segment) => (segment, false),
+            None => (self.segments.last().expect("checked in new"), true),
+        };
+        let projection = LocalProjection::new(&segment.0);
+        let (dx, dy) = projection.project(&segment.1);
+        let length = dx.hypot(dy);
+        let fraction = if at_end || length <= 0.0 {
+            1.0
+        } else {
+            (self.offset / length).min(1.0)
+        };
+        let (mut x, mut y) = (dx * fraction, dy * fraction);
+        if self.noise_meters > 0.0 {
+            let (nx, ny) = self.next_gaussian();
+            x += nx * self.noise_meters;
+            y += ny * self.noise_meters;
+        }
+        projection.unproject((x, y)).into()
+    }
+}
+impl Iterator for SimulatedDrive {
+    type Item = SimulatedPosition;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        self.done = self.segment >= self.segments.len();
+        let position = SimulatedPosition {
+            time: self.time,
+            distance: self.distance,
+            position: self.position(),
+        };
+        // advance by one interval, possibly across several segments
+        let mut remaining = self.interval_secs;
+        while let Some((start, end, speed)) = self.segments.get(self.segment) {
+            let left = crate::shapes::length(&[start.clone(), end.clone()]) - self.offset;
+            // valhalla estimated no time for e.g. the arrival maneuver, skip it
+            let needed = if *speed > 0.0 { left / speed } else { 0.0 };
+            if needed > remaining {
+                self.offset += remaining * speed;
+                self.distance += remaining * speed;
+                self.time += remaining;
+                break;
+            }
+            remaining -= needed;
+            self.time += needed;
+            self.distance += left;
+            self.segment += 1;
+            self.offset = 0.0;
+        }
+        Some(position)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::route::test::{summary, trip_with_legs};
+    #[test]
+    fn schedule_scales_with_speed() {
+        let maneuver = |length: f64, time: f64| {
+            serde_json::json!({
+                "type": 1, "instruction": "", "time": time, "length": length,
+                "begin_shape_index": 0, "end_shape_index": 0, "travel_mode": "drive",
+                "verbal_transition_alert_instruction": "alert",
+                "verbal_pre_transition_instruction": "now",
+                "verbal_post_transition_instruction": "continue"
+            })
+        };
+        let trip: Trip = serde_json::from_value(trip_with_legs([serde_json::json!({
+            "summary": summary(432.0, 11.0),
+            // 10 km at 100 KPH, 1 km at 50 KPH, arrival
+            "maneuvers": [maneuver(10.0, 360.0), maneuver(1.0, 72.0), maneuver(0.0, 0.0)],
+            "shape": ""
+        })]))
+        .unwrap();
+        let timeline: Vec<(usize, f64, InstructionKind)> = AnnouncementTiming::default()
+            .schedule(&trip)
+            .iter()
+            .map(|a| (a.maneuver_index, a.distance_along_leg.round(), a.kind))
+            .collect();
+        use InstructionKind::*;
+        assert_eq!(
+            timeline,
+            [
+                (0, 0.0, PreTransition),
+                (0, 0.0, PostTransition),
+                (1, 8333.0, TransitionAlert),
+                (1, 9722.0, PreTransition),
+                (1, 10000.0, PostTransition),
+                (2, 10167.0, TransitionAlert),
+                (2, 10861.0, PreTransition),
+                (2, 11000.0, PostTransition),
+            ]
+        );
+    }
+
+    #[test]
+    fn simulated_drive() {
+        let mut trip: Trip = serde_json::from_value(trip_with_legs([serde_json::json!({
+            "summary": summary(10.0, 0.1),
+            "maneuvers": [{
+                "type": 1, "instruction": "", "time": 10.0, "length": 0.1,
+                "begin_shape_index": 0, "end_shape_index": 1, "travel_mode": "drive"
+            }],
+            "shape": ""
+        })]))
+        .unwrap();
+        // ~111.2 m due north
+        trip.legs[0].shape = vec![
+            ShapePoint { lon: 0.0, lat: 0.0 },
+            ShapePoint {
+                lon: 0.0,
+                lat: 0.001,
+            },
+        ];
+        let length = crate::shapes::length(&trip.legs[0].shape);
+
+        let fixes: Vec<SimulatedPosition> =
+            SimulatedDrive::new(&trip, SpeedProfile::Constant(25.0))
+                .interval_secs(2.0)
+                .collect();
+        let times: Vec<f64> = fixes.iter().map(|f| f.time).collect();
+        assert_eq!(times[..3], [0.0, 2.0, 4.0]);
+        assert!((times[3] - length / 25.0).abs() < 1e-9);
+        assert!((fixes[1].position.lat - 0.001 * 50.0 / length).abs() < 1e-9);
+        let last = fixes.last().unwrap();
+        assert!((last.distance - length).abs() < 1e-9);
+        assert!((last.position.lat - 0.001).abs() < 1e-9);
+
+        let noisy = || {
+            SimulatedDrive::new(&trip, SpeedProfile::Maneuvers)
+                .noise(5.0, 7)
+                .collect::<Vec<_>>()
+        };
+        let (a, b) = (noisy(), noisy());
+        assert_eq!(a, b);
+        // 10 m/s: a fix every second for 11.1 s plus the end
+        assert_eq!(a.len(), 13);
+        assert!(a.iter().any(|f| f.position.lon != 0.0));
+    }
+}
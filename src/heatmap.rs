@@ -0,0 +1,177 @@
+use crate::costing;
+use crate::matrix::{DateTime, Location};
+
+/// Default number of grid cells sent to the time-distance matrix per request.
+///
+/// Valhalla limits the amount of source-target pairs per matrix request via
+/// `service_limits.<costing>.max_matrix_location_pairs`, which defaults to `2500`.
+pub const DEFAULT_MAX_LOCATIONS_PER_REQUEST: usize = 2500;
+
+/// Axis aligned area in which a [`TravelTimeGrid`] is computed
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoundingBox {
+    pub min_lon: f32,
+    pub min_lat: f32,
+    pub max_lon: f32,
+    pub max_lat: f32,
+}
+impl BoundingBox {
+    /// Creates a bounding box from its south-west and north-east corners
+    pub fn new(south_west: super::Coordinate, north_east: super::Coordinate) -> Self {
+        let (min_lon, min_lat) = south_west;
+        let (max_lon, max_lat) = north_east;
+        Self {
+            min_lon,
+            min_lat,
+            max_lon,
+            max_lat,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Manifest {
+    pub(crate) origin: Location,
+    pub(crate) bbox: BoundingBox,
+    pub(crate) rows: usize,
+    pub(crate) cols: usize,
+    pub(crate) costing: costing::Costing,
+    pub(crate) date_time: Option<DateTime>,
+    pub(crate) max_locations_per_request: usize,
+}
+impl Manifest {
+    /// Creates a grid of `rows` x `cols` cells over `bbox`, measured from `origin`
+    pub fn new(origin: Location, bbox: BoundingBox, rows: usize, cols: usize) -> Self {
+        Self {
+            origin,
+            bbox,
+            rows,
+            cols,
+            costing: Default::default(),
+            date_time: None,
+            max_locations_per_request: DEFAULT_MAX_LOCATIONS_PER_REQUEST,
+        }
+    }
+    /// Configures the costing model
+    ///
+    /// **Note:** multimodal costing is not supported for the time-distance matrix service at this time.
+    ///
+    /// Default: [`costing::Costing::Auto`]
+    pub fn costing(mut self, costing: costing::Costing) -> Self {
+        self.costing = costing;
+        self
+    }
+    /// Departure time from the origin
+    ///
+    /// See [`crate::matrix::Manifest::date_time`] for the limitations of time awareness.
+    pub fn date_time(mut self, date_time: DateTime) -> Self {
+        self.date_time = Some(date_time);
+        self
+    }
+    /// Upper bound of grid cells sent to the server in a single matrix request.
+    ///
+    /// Lower this if the server is configured with a smaller `max_matrix_location_pairs`.
+    ///
+    /// Default: [`DEFAULT_MAX_LOCATIONS_PER_REQUEST`]
+    pub fn max_locations_per_request(mut self, max_locations: usize) -> Self {
+        self.max_locations_per_request = max_locations;
+        self
+    }
+    /// Checks that `max_locations_per_request` is at least 1 and that the costing options are in
+    /// range
+    ///
+    /// See [`crate::validation`].
+    pub fn validate(&self) -> Result<(), crate::ValidationError> {
+        let mut validator = crate::validation::Validator::default();
+        validator.min_locations(
+            "max_locations_per_request",
+            self.max_locations_per_request,
+            1,
+        );
+        validator.costing(Some(&self.costing));
+        validator.finish()
+    }
+    /// Center of every cell in row-major order, starting at the north-west corner
+    pub(crate) fn cell_centers(&self) -> Vec<Location> {
+        (0..self.rows)
+            .flat_map(|row| (0..self.cols).map(move |col| (row, col)))
+            .map(|(row, col)| {
+                Location::from(cell_center(&self.bbox, self.rows, self.cols, row, col))
+            })
+            .collect()
+    }
+}
+
+fn cell_center(
+    bbox: &BoundingBox,
+    rows: usize,
+    cols: usize,
+    row: usize,
+    col: usize,
+) -> super::Coordinate {
+    let lon_step = (bbox.max_lon - bbox.min_lon) / cols as f32;
+    let lat_step = (bbox.max_lat - bbox.min_lat) / rows as f32;
+    (
+        bbox.min_lon + lon_step * (col as f32 + 0.5),
+        bbox.max_lat - lat_step * (row as f32 + 0.5),
+    )
+}
+
+/// Travel times from an origin to the centers of a regular grid of cells
+///
+/// Rows run from north to south and columns from west to east, which is the layout
+/// raster and heatmap libraries generally expect.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TravelTimeGrid {
+    pub bbox: BoundingBox,
+    pub rows: usize,
+    pub cols: usize,
+    /// Travel time in seconds for each cell in row-major order.
+    ///
+    /// `None` if the cell cannot be reached from the origin.
+    pub durations: Vec<Option<u32>>,
+}
+impl TravelTimeGrid {
+    /// Travel time in seconds to the cell at `row`/`col`
+    ///
+    /// `None` if the cell is unreachable or outside the grid.
+    pub fn get(&self, row: usize, col: usize) -> Option<u32> {
+        if row >= self.rows || col >= self.cols {
+            return None;
+        }
+        self.durations[row * self.cols + col]
+    }
+    /// Longitude/latitude of the center of the cell at `row`/`col`
+    pub fn cell_center(&self, row: usize, col: usize) -> super::Coordinate {
+        cell_center(&self.bbox, self.rows, self.cols, row, col)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn cell_centers_are_row_major_from_north_west() {
+        let bbox = BoundingBox::new((0.0, 0.0), (4.0, 2.0));
+        let manifest = Manifest::new(Location::new(2.0, 1.0), bbox, 2, 4);
+        let centers = manifest.cell_centers();
+        assert_eq!(centers.len(), 8);
+        assert_eq!(centers[0], Location::new(0.5, 1.5));
+        assert_eq!(centers[3], Location::new(3.5, 1.5));
+        assert_eq!(centers[4], Location::new(0.5, 0.5));
+    }
+
+    #[test]
+    fn get_outside_of_grid() {
+        let grid = TravelTimeGrid {
+            bbox: BoundingBox::new((0.0, 0.0), (1.0, 1.0)),
+            rows: 1,
+            cols: 2,
+            durations: vec![Some(10), None],
+        };
+        assert_eq!(grid.get(0, 0), Some(10));
+        assert_eq!(grid.get(0, 1), None);
+        assert_eq!(grid.get(1, 0), None);
+        assert_eq!(grid.cell_center(0, 1), (0.75, 0.5));
+    }
+}
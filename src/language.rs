@@ -0,0 +1,134 @@
+//! Languages of the narration instructions
+use serde::{Deserialize, Serialize};
+
+macro_rules! languages {
+    ($($(#[$meta:meta])* $variant:ident => $tag:literal,)*) => {
+        /// Language of the narration instructions
+        ///
+        /// Lists the [supported language tags](https://valhalla.github.io/valhalla/api/turn-by-turn/api-reference/#supported-language-tags).
+        /// Converting from a string matches the tags case-insensitively, so `"de-de"` becomes
+        /// [`Language::DeDe`].
+        /// Anything else is kept as [`Language::Other`], for which valhalla falls back to `en-US`.
+        #[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+        #[non_exhaustive]
+        pub enum Language {
+            $($(#[$meta])* #[doc = concat!("`", $tag, "`")] $variant,)*
+            /// A language tag this crate does not know about
+            Other(String),
+        }
+        impl Language {
+            /// All languages this crate knows about
+            pub const SUPPORTED: &[Language] = &[$(Language::$variant,)*];
+
+            /// The IETF BCP 47 language tag sent to valhalla
+            pub fn as_str(&self) -> &str {
+                match self {
+                    $(Self::$variant => $tag,)*
+                    Self::Other(tag) => tag,
+                }
+            }
+        }
+        impl From<&str> for Language {
+            fn from(tag: &str) -> Self {
+                $(if tag.eq_ignore_ascii_case($tag) {
+                    return Self::$variant;
+                })*
+                Self::Other(tag.to_string())
+            }
+        }
+    };
+}
+
+languages! {
+    BgBg => "bg-BG",
+    CaEs => "ca-ES",
+    CsCz => "cs-CZ",
+    DaDk => "da-DK",
+    DeDe => "de-DE",
+    ElGr => "el-GR",
+    EnGb => "en-GB",
+    EnUsXPirate => "en-US-x-pirate",
+    #[default]
+    EnUs => "en-US",
+    EsEs => "es-ES",
+    EtEe => "et-EE",
+    FiFi => "fi-FI",
+    FrFr => "fr-FR",
+    HiIn => "hi-IN",
+    HuHu => "hu-HU",
+    ItIt => "it-IT",
+    JaJp => "ja-JP",
+    NbNo => "nb-NO",
+    NlNl => "nl-NL",
+    PlPl => "pl-PL",
+    PtBr => "pt-BR",
+    PtPt => "pt-PT",
+    RoRo => "ro-RO",
+    RuRu => "ru-RU",
+    SkSk => "sk-SK",
+    SlSi => "sl-SI",
+    SvSe => "sv-SE",
+    TrTr => "tr-TR",
+    UkUa => "uk-UA",
+}
+
+impl Language {
+    /// Whether valhalla narrates in this language instead of falling back to `en-US`
+    pub fn is_supported(&self) -> bool {
+        !matches!(self, Self::Other(_))
+    }
+}
+impl From<String> for Language {
+    fn from(tag: String) -> Self {
+        Self::from(tag.as_str())
+    }
+}
+impl From<&String> for Language {
+    fn from(tag: &String) -> Self {
+        Self::from(tag.as_str())
+    }
+}
+impl std::str::FromStr for Language {
+    type Err = std::convert::Infallible;
+    fn from_str(tag: &str) -> Result<Self, Self::Err> {
+        Ok(Self::from(tag))
+    }
+}
+impl std::fmt::Display for Language {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+impl Serialize for Language {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+impl<'de> Deserialize<'de> for Language {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let tag = <std::borrow::Cow<'de, str>>::deserialize(deserializer)?;
+        Ok(Self::from(tag.as_ref()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    #[test]
+    fn parsing() {
+        assert_eq!(Language::from("de-De"), Language::DeDe);
+        assert_eq!(Language::from("EN-us-X-PIRATE"), Language::EnUsXPirate);
+        assert_eq!(
+            Language::from("xx-XX"),
+            Language::Other("xx-XX".to_string())
+        );
+        assert!(!Language::from("xx-XX").is_supported());
+        for language in Language::SUPPORTED {
+            assert_eq!(&Language::from(language.as_str()), language);
+        }
+        assert_eq!(
+            serde_json::to_value(Language::from("nl-nl")).unwrap(),
+            serde_json::json!("nl-NL")
+        );
+    }
+}
@@ -1,18 +1,57 @@
 #![forbid(unsafe_code)]
 #![doc = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/README.md"))]
 
+pub mod batch;
+mod capture;
+pub mod centroid;
+mod circuit_breaker;
+mod concurrency;
+pub mod config;
 pub mod costing;
+pub mod coverage;
+mod deserialize;
 pub mod elevation;
+pub mod guidance;
+pub mod heatmap;
+pub mod language;
 pub mod matrix;
+pub mod pool;
+mod precision;
+pub mod prelude;
+mod rate_limit;
 pub mod route;
 pub mod shapes;
+mod single_flight;
 pub mod status;
+pub mod transport;
+pub mod validation;
 
-use log::debug;
+pub use config::ClientConfig;
+pub use deserialize::DeserializeError;
+pub use language::Language;
+pub use pool::ValhallaPool;
+pub use validation::ValidationError;
+
+// Dependencies appearing in the public API, so that users can construct arguments with
+// exactly the versions this crate was built with
+pub use chrono;
+pub use geo_types;
+use log::{debug, warn};
+#[cfg(feature = "reqwest")]
+pub use reqwest;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Instant;
+#[cfg(feature = "ureq")]
+pub use ureq;
+pub use url;
 
 /// A longitude, latitude coordinate in degrees
 ///
+/// All conversions from a `Coordinate` in this crate read the longitude first, like
+/// [GeoJSON](https://datatracker.ietf.org/doc/html/rfc7946#section-3.1.1) and geo-types do.
+///
 /// See <https://en.wikipedia.org/wiki/Geographic_coordinate_system> for further context
 pub type Coordinate = (f32, f32);
 impl From<Coordinate> for shapes::ShapePoint {
@@ -24,23 +63,160 @@ impl From<Coordinate> for shapes::ShapePoint {
     }
 }
 
+/// A code with its human readable description
+///
+/// Not part of any response model: valhalla's verbose responses are modelled by typed
+/// structures such as [`status::VerboseStatus`] instead, and new endpoints follow that approach.
+#[deprecated(
+    note = "not returned by any endpoint of this crate; use the typed response models instead"
+)]
 #[derive(Deserialize, Debug, Clone)]
 pub struct CodedDescription {
     pub code: u64,
     pub description: String,
 }
 
+/// Blocking client for a valhalla server
+///
+/// # Use inside an async runtime
+///
+/// The default reqwest transport runs its own tokio runtime and panics if a request is sent
+/// from a thread which already drives a runtime.
+/// Either send requests via `tokio::task::spawn_blocking`, or use the ureq transport
+/// (feature `ureq`, see [`transport`]), which does not need a runtime at all.
+///
+/// # Cloning
+///
+/// Clones are cheap and share the transport (and thus its connection pool), the limits of
+/// [`Self::max_concurrent_requests`] and [`Self::rate_limit`] and the in-flight requests of
+/// [`Self::deduplicate_requests`].
+///
+/// # Dropping
+///
+/// `Valhalla` owns no runtime or background threads of its own, so there is nothing to close.
+/// The transport is dropped together with the last clone; requests running on other threads
+/// keep their clone alive until they return.
+/// The reqwest transport shuts its internal runtime down when dropped, which panics inside an
+/// async context just like sending a request does (see above).
+/// Use [`Self::with_deadline`] to bound how long in-flight requests can hold on to it.
+#[derive(Clone)]
 pub struct Valhalla {
-    client: reqwest::blocking::Client,
+    transport: Arc<dyn transport::HttpTransport>,
     base_url: url::Url,
+    concurrency: concurrency::Limiter,
+    rate_limit: Option<rate_limit::TokenBucket>,
+    circuit_breaker: Option<circuit_breaker::CircuitBreaker>,
+    request_method: RequestMethod,
+    response_adapter: Option<ResponseAdapter>,
+    single_flight: Option<Arc<single_flight::SingleFlight<transport::HttpResponse>>>,
+    auth: Option<Auth>,
+    deadline: Option<Instant>,
+    cancellation_token: Option<batch::CancellationToken>,
+    coordinate_precision: u8,
+    retries: u32,
+    #[cfg(feature = "gzip")]
+    compression_threshold: Option<usize>,
+    retry_backoff: std::time::Duration,
+    action_paths: std::collections::HashMap<String, String>,
+    capture: Option<Arc<capture::Capture>>,
+    last_rate_limit: Arc<std::sync::Mutex<Option<transport::RateLimit>>>,
+    service_limits: Arc<validation::ServiceLimits>,
+}
+
+type ResponseAdapter = Arc<dyn Fn(&str, serde_json::Value) -> serde_json::Value + Send + Sync>;
+
+/// Credentials sent with every request
+///
+/// Hosted valhalla instances and authenticating proxies in front of self-hosted ones expect
+/// different mechanisms.
+/// The `Debug` output does not contain the secrets.
+#[derive(Clone, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Auth {
+    /// `Authorization: Bearer <token>`
+    Bearer(String),
+    /// A custom header, e.g. `x-api-key: <key>`
+    Header { name: String, value: String },
+    /// A query parameter, e.g. `?api_key=<key>`
+    Query { name: String, value: String },
+}
+impl Auth {
+    /// The name of the header carrying the credentials, if sent as a header
+    fn header_name(&self) -> Option<&str> {
+        match self {
+            Self::Bearer(_) => Some("Authorization"),
+            Self::Header { name, .. } => Some(name),
+            Self::Query { .. } => None,
+        }
+    }
+}
+impl std::fmt::Debug for Auth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Bearer(_) => f.write_str("Bearer(<redacted>)"),
+            Self::Header { name, .. } => write!(f, "Header({name}: <redacted>)"),
+            Self::Query { name, .. } => write!(f, "Query({name}=<redacted>)"),
+        }
+    }
+}
+
+/// How requests are sent to the server
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum RequestMethod {
+    /// `POST /<action>` with the manifest as JSON body
+    #[default]
+    Post,
+    /// `GET /<action>?json=<urlencoded manifest>`
+    ///
+    /// Some reverse proxies and caching CDNs in front of Valhalla only allow or only cache GET requests.
+    /// Very large manifests may exceed the URL length limits of the server or proxies in between.
+    Get,
 }
 
+/// Everything which can go wrong talking to valhalla
+///
+/// New variants may be added in minor releases; prefer the classifications such as
+/// [`Self::is_retryable`] or [`Self::is_client_bug`] over matching on variants.
+/// [`Display`](std::fmt::Display) only describes this error; the underlying error, if any, is
+/// available via [`std::error::Error::source`], so error reporters do not repeat its message.
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum Error {
+    /// Sending the request or receiving the response failed, e.g. because the server is down
+    #[cfg(feature = "reqwest")]
     Reqwest(reqwest::Error),
+    /// Sending the request or receiving the response failed, e.g. because the server is down
+    #[cfg(feature = "ureq")]
+    Ureq(ureq::Error),
+    /// An url could not be parsed
     Url(url::ParseError),
+    /// The manifest could not be serialized, or the server explained an error response in an
+    /// unknown format
     Serde(serde_json::Error),
+    /// The response did not match the models of this crate
+    Deserialize(DeserializeError),
+    /// The server rejected the request or failed to process it, explaining why
     RemoteError(RemoteError),
+    /// Reading or writing a file failed
+    Io(std::io::Error),
+    /// The request or batch operation was cancelled via its [`batch::CancellationToken`]
+    Cancelled,
+    /// No response arrived before the deadline set via [`Valhalla::with_deadline`]
+    DeadlineExceeded,
+    /// The request was not sent, as the server failed too often, see [`Valhalla::circuit_breaker`]
+    CircuitOpen,
+    /// The server version does not match [`MIN_SUPPORTED_VALHALLA`], see [`Valhalla::check_version`]
+    UnsupportedVersion(semver::Version),
+    /// The server responded with an error status code, without explaining it via a [`RemoteError`]
+    HttpStatus(u16),
+    /// A setting of [`Valhalla::from_config`] or [`Valhalla::from_env`] is invalid
+    Config(String),
+    /// The manifest was not sent, as valhalla would reject it, see [`validation`]
+    Validation(ValidationError),
+    /// The server responded with HTTP 429, as too many requests were sent
+    ///
+    /// Contains the limits the server reported, see [`Self::retry_after`].
+    RateLimited(transport::RateLimit),
 }
 
 /// valhalla needs `date_time` fields to be in the `YYYY-MM-DDTHH:MM` format
@@ -68,7 +244,57 @@ where
     serializer.serialize_str(&value.format("%Y-%m-%dT%H:%M").to_string())
 }
 
+/// Parses `date_time` strings as returned by valhalla
+///
+/// Accepts `YYYY-MM-DDTHH:MM` with optional seconds (and fractions thereof) and an optional
+/// `Z` or `±hh:mm` suffix.
+/// An offset is dropped, keeping the local time at the location, like the times valhalla expects as input.
+pub(crate) fn parse_lenient_naive_date_time(
+    value: &str,
+) -> Result<chrono::NaiveDateTime, chrono::ParseError> {
+    let bytes = value.as_bytes();
+    let local = if let Some(local) = value.strip_suffix('Z') {
+        local
+    } else if bytes.len() > 6
+        && matches!(bytes[bytes.len() - 6], b'+' | b'-')
+        && bytes[bytes.len() - 3] == b':'
+    {
+        &value[..value.len() - 6]
+    } else {
+        value
+    };
+    chrono::NaiveDateTime::parse_from_str(local, "%Y-%m-%dT%H:%M:%S%.f")
+        .or_else(|_| chrono::NaiveDateTime::parse_from_str(local, "%Y-%m-%dT%H:%M"))
+}
+
+/// Deserializes `date_time` fields via [`parse_lenient_naive_date_time`]
+pub(crate) fn deserialize_naive_date_time<'de, D>(
+    deserializer: D,
+) -> Result<chrono::NaiveDateTime, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let value = <std::borrow::Cow<'de, str>>::deserialize(deserializer)?;
+    parse_lenient_naive_date_time(&value).map_err(serde::de::Error::custom)
+}
+
+/// Deserializes optional `date_time` fields via [`parse_lenient_naive_date_time`]
+pub(crate) fn deserialize_naive_date_time_opt<'de, D>(
+    deserializer: D,
+) -> Result<Option<chrono::NaiveDateTime>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    match <Option<std::borrow::Cow<'de, str>>>::deserialize(deserializer)? {
+        None => Ok(None),
+        Some(value) => parse_lenient_naive_date_time(&value)
+            .map(Some)
+            .map_err(serde::de::Error::custom),
+    }
+}
+
 #[derive(Serialize, Deserialize, Default, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub enum Units {
     #[default]
     #[serde(rename = "kilometers")]
@@ -77,21 +303,220 @@ pub enum Units {
     #[serde(rename = "miles")]
     Imperial,
 }
+impl Units {
+    /// Converts a length in these units, e.g. [`route::Maneuver::length`], to meters
+    pub fn to_meters(self, length: f64) -> f64 {
+        match self {
+            Self::Metric => length * 1000.0,
+            Self::Imperial => length * 1609.344,
+        }
+    }
+}
 
 impl std::fmt::Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
-            Self::Reqwest(e) => write!(f, "reqwest error: {e}"),
-            Self::Url(e) => write!(f, "url error: {e}"),
-            Self::Serde(e) => write!(f, "serde error: {e}"),
-            Self::RemoteError(e) => write!(f, "remote error: {e:?}"),
+            #[cfg(feature = "reqwest")]
+            Self::Reqwest(_) => write!(f, "the request failed"),
+            #[cfg(feature = "ureq")]
+            Self::Ureq(_) => write!(f, "the request failed"),
+            Self::Url(_) => write!(f, "invalid url"),
+            Self::Serde(_) => write!(f, "cannot convert from or to JSON"),
+            Self::Deserialize(e) => write!(
+                f,
+                "cannot parse the {} response (HTTP {})",
+                e.action, e.status
+            ),
+            Self::RemoteError(_) => write!(f, "the server returned an error"),
+            Self::Io(_) => write!(f, "io error"),
+            Self::Cancelled => write!(f, "operation was cancelled"),
+            Self::DeadlineExceeded => write!(f, "deadline exceeded"),
+            Self::CircuitOpen => write!(f, "circuit breaker is open after repeated failures"),
+            Self::HttpStatus(status @ 301..=303) => write!(
+                f,
+                "server responded with HTTP {status}, which is not followed for POST requests as it loses the request body"
+            ),
+            Self::HttpStatus(status) => write!(f, "server responded with HTTP {status}"),
+            Self::Config(e) => write!(f, "invalid configuration: {e}"),
+            Self::Validation(_) => write!(f, "invalid request"),
+            Self::RateLimited(transport::RateLimit {
+                retry_after: Some(retry_after),
+                ..
+            }) => write!(f, "rate limited by the server, retry after {retry_after:?}"),
+            Self::RateLimited(_) => write!(f, "rate limited by the server"),
+            Self::UnsupportedVersion(version) => write!(
+                f,
+                "valhalla {version} is not supported, {} is required",
+                *MIN_SUPPORTED_VALHALLA
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            #[cfg(feature = "reqwest")]
+            Self::Reqwest(e) => Some(e),
+            #[cfg(feature = "ureq")]
+            Self::Ureq(e) => Some(e),
+            Self::Url(e) => Some(e),
+            Self::Serde(e) => Some(e),
+            Self::Deserialize(e) => Some(e),
+            Self::RemoteError(e) => Some(e),
+            Self::Io(e) => Some(e),
+            Self::Validation(e) => Some(e),
+            Self::Cancelled
+            | Self::DeadlineExceeded
+            | Self::CircuitOpen
+            | Self::UnsupportedVersion(_)
+            | Self::HttpStatus(_)
+            | Self::Config(_)
+            | Self::RateLimited(_) => None,
+        }
+    }
+}
+
+/// Joins the message of `error` and of its sources, for logs and captures
+pub(crate) fn error_chain(error: &dyn std::error::Error) -> String {
+    let mut message = error.to_string();
+    let mut source = error.source();
+    while let Some(error) = source {
+        message.push_str(": ");
+        message.push_str(&error.to_string());
+        source = error.source();
+    }
+    message
+}
+
+impl Error {
+    /// Whether sending the same request again may succeed
+    ///
+    /// True for failures to reach the server, for overloaded or unavailable servers
+    /// (HTTP 429, 502, 503 and 504) and for requests which timed out on the server (HTTP 408).
+    /// This is what [`Valhalla::retries`] retries.
+    pub fn is_retryable(&self) -> bool {
+        self.is_connection_failure() || matches!(self.status(), Some(408 | 429 | 502 | 503 | 504))
+    }
+    /// Whether the request was invalid or the response did not match the models of this crate
+    ///
+    /// Retrying will not help, the request (or this crate) needs to be fixed.
+    /// Note that [`ErrorCode::NO_SUITABLE_EDGES`] and [`ErrorCode::NO_PATH`] are not bugs but
+    /// answers: the locations cannot be routed between.
+    pub fn is_client_bug(&self) -> bool {
+        match self {
+            Self::Url(_) | Self::Serde(_) | Self::Deserialize(_) | Self::Validation(_) => true,
+            Self::RemoteError(e) => e.is_invalid_input(),
+            Self::HttpStatus(status) => (400..500).contains(status) && !self.is_retryable(),
+            _ => false,
+        }
+    }
+    /// Whether the server found no route between the locations, see [`RemoteError::is_no_route`]
+    ///
+    /// Unlike other errors, this is an answer: retrying with a different costing (or other
+    /// locations) may find a route.
+    ///
+    /// # Example:
+    /// ```rust,no_run
+    /// use valhalla_client::Valhalla;
+    /// use valhalla_client::costing::{Costing, PedestrianCostingOptions};
+    /// use valhalla_client::route::{Location, ManifestBuilder};
+    ///
+    /// let valhalla = Valhalla::default();
+    /// let locations = [Location::new(4.9041, 52.3676), Location::new(5.1214, 52.0907)];
+    /// let by = |costing| ManifestBuilder::default().locations(locations.clone()).costing(costing);
+    /// let route = match valhalla.route(by(Costing::default())) {
+    ///   Err(e) if e.is_no_route() => {
+    ///     valhalla.route(by(Costing::Pedestrian(PedestrianCostingOptions::builder())))
+    ///   }
+    ///   result => result,
+    /// };
+    /// ```
+    pub fn is_no_route(&self) -> bool {
+        matches!(self, Self::RemoteError(e) if e.is_no_route())
+    }
+    /// Whether a location is too far from any road usable with the costing, see
+    /// [`ErrorCode::NO_SUITABLE_EDGES`]
+    ///
+    /// Retrying with a larger [`route::Location::radius`] may help.
+    pub fn is_no_segment(&self) -> bool {
+        matches!(self, Self::RemoteError(e) if e.is_no_segment())
+    }
+    /// Whether the server rejected the request, i.e. responded with HTTP 4xx
+    ///
+    /// Includes answers such as [`Self::is_no_route`] and throttling (HTTP 429); see
+    /// [`Self::is_client_bug`] and [`Self::is_retryable`] to tell them apart.
+    pub fn is_client_error(&self) -> bool {
+        self.status()
+            .is_some_and(|status| (400..500).contains(&status))
+    }
+    /// Whether the server failed to process the request, i.e. responded with HTTP 5xx
+    pub fn is_server_error(&self) -> bool {
+        self.status().is_some_and(|status| status >= 500)
+    }
+    /// Same as [`Self::is_server_error`], named after the other side of [`Self::is_client_bug`]
+    pub fn is_server_side(&self) -> bool {
+        self.is_server_error()
+    }
+    /// HTTP status code of the response the error was read from
+    ///
+    /// `None` if there was no response, e.g. because the server could not be reached.
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            Self::RemoteError(e) => Some(e.status_code),
+            Self::HttpStatus(status) => Some(*status),
+            Self::Deserialize(e) => Some(e.status),
+            Self::RateLimited(_) => Some(429),
+            _ => None,
+        }
+    }
+    /// How long the server asked to wait before sending the next request, via `Retry-After`
+    ///
+    /// [`Valhalla::retries`] waits at least this long.
+    pub fn retry_after(&self) -> Option<std::time::Duration> {
+        match self {
+            Self::RateLimited(rate_limit) => rate_limit.retry_after,
+            _ => None,
+        }
+    }
+    /// Whether the server could not be reached at all, e.g. because the connection was refused
+    pub(crate) fn is_connection_failure(&self) -> bool {
+        match self {
+            #[cfg(feature = "reqwest")]
+            Self::Reqwest(e) => e.is_connect() || e.is_timeout(),
+            #[cfg(feature = "ureq")]
+            Self::Ureq(e) => matches!(
+                e,
+                ureq::Error::ConnectionFailed
+                    | ureq::Error::HostNotFound
+                    | ureq::Error::Timeout(_)
+                    | ureq::Error::Io(_)
+            ),
+            _ => false,
         }
     }
 }
 
-impl std::error::Error for Error {}
+/// The valhalla versions the request and response models of this crate are written against,
+/// `>=3.1.4`
+///
+/// Older servers may lack fields the models expect, which surfaces as confusing
+/// deserialization errors or silently missing data.
+/// Pre-releases such as `3.5.1-dev` count as the release they precede.
+/// See [`Valhalla::check_version`].
+pub static MIN_SUPPORTED_VALHALLA: std::sync::LazyLock<semver::VersionReq> =
+    std::sync::LazyLock::new(|| semver::VersionReq::parse(">=3.1.4").unwrap());
+
+/// Redirects followed per request, like reqwest and ureq by default
+const MAX_REDIRECTS: usize = 10;
 
+/// See [`Valhalla::shared`]
+#[cfg(any(feature = "reqwest", feature = "ureq"))]
+static SHARED: std::sync::OnceLock<Valhalla> = std::sync::OnceLock::new();
+
+#[cfg(any(feature = "reqwest", feature = "ureq"))]
 const VALHALLA_PUBLIC_API_URL: &str = "https://valhalla1.openstreetmap.de/";
+#[cfg(any(feature = "reqwest", feature = "ureq"))]
 impl Default for Valhalla {
     fn default() -> Self {
         Self::new(
@@ -101,213 +526,2478 @@ impl Default for Valhalla {
     }
 }
 
-#[derive(Debug, Deserialize)]
+/// Error reported by the valhalla server
+///
+/// See <https://valhalla.github.io/valhalla/api/turn-by-turn/api-reference/#http-status-codes-and-conditions>
+#[derive(Debug, Clone, Deserialize)]
 pub struct RemoteError {
-    pub error_code: isize,
+    /// Valhalla specific error code
+    pub error_code: ErrorCode,
+    /// Human readable error message
+    ///
+    /// Example: `"No suitable edges near location"`
     pub error: String,
-    pub status_code: isize,
+    /// HTTP status code of the response
+    pub status_code: u16,
+    /// HTTP status text of the response
+    ///
+    /// Example: `"Bad Request"`
     pub status: String,
 }
+impl RemoteError {
+    /// See [`ErrorCode::is_invalid_input`]
+    pub fn is_invalid_input(&self) -> bool {
+        self.error_code.is_invalid_input()
+    }
+    /// See [`ErrorCode::NO_SUITABLE_EDGES`]
+    pub fn is_no_segment(&self) -> bool {
+        self.error_code == ErrorCode::NO_SUITABLE_EDGES
+    }
+    /// Whether no route exists between the locations with the requested costing
+    ///
+    /// See [`ErrorCode::NO_PATH`] and [`ErrorCode::UNCONNECTED_REGIONS`].
+    pub fn is_no_route(&self) -> bool {
+        matches!(
+            self.error_code,
+            ErrorCode::NO_PATH | ErrorCode::UNCONNECTED_REGIONS
+        )
+    }
+}
+impl std::error::Error for RemoteError {}
+impl std::fmt::Display for RemoteError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{} (error code {}, HTTP {} {})",
+            self.error, self.error_code, self.status_code, self.status
+        )
+    }
+}
+
+/// Valhalla specific error code of a [`RemoteError`]
+///
+/// The hundreds digit identifies the part of valhalla which failed, e.g. `1xx` for the
+/// correlation of the input locations to the road network and `4xx` for the path finding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Deserialize)]
+#[serde(transparent)]
+pub struct ErrorCode(pub u16);
+impl ErrorCode {
+    /// `Failed to parse json request`
+    pub const INVALID_JSON: Self = Self(100);
+    /// `Locations are in unconnected regions. Go check/edit the map at osm.org`
+    pub const UNCONNECTED_REGIONS: Self = Self(170);
+    /// `No suitable edges near location`
+    ///
+    /// None of the roads near a location can be used with the requested costing.
+    pub const NO_SUITABLE_EDGES: Self = Self(171);
+    /// `No path could be found for input`
+    pub const NO_PATH: Self = Self(442);
+
+    /// Whether the request itself was rejected, e.g. because it is malformed, misses required
+    /// parameters or exceeds the configured service limits
+    ///
+    /// These are the `1xx` codes below `170`; retrying the same request will not help.
+    pub fn is_invalid_input(self) -> bool {
+        (100..170).contains(&self.0)
+    }
+}
+impl std::fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
 
 impl Valhalla {
+    /// Creates a client for the server at `base_url`
+    ///
+    /// Sends requests via reqwest, or via ureq if only the `ureq` feature is enabled.
+    /// Use [`Self::with_transport`] to pick the HTTP client explicitly.
+    ///
+    /// `base_url` may contain a path prefix, with or without a trailing slash: actions are
+    /// appended to it, e.g. `https://example.com/valhalla` sends routes to
+    /// `https://example.com/valhalla/route`.
+    ///
+    /// Up to 10 redirects are followed, with 307 and 308 for any request.
+    /// 301, 302 and 303 are only followed for [`RequestMethod::Get`], as HTTP clients turn a
+    /// redirected POST into a GET without the manifest; a POST fails with [`Error::HttpStatus`]
+    /// instead, use the url it points to.
+    /// The credentials of [`Auth::Bearer`] and [`Auth::Header`] are not sent to other origins.
+    ///
+    /// # Panics
+    ///
+    /// If the TLS backend cannot be initialized, like [`reqwest::blocking::Client::new`]
+    #[cfg(any(feature = "reqwest", feature = "ureq"))]
     pub fn new(base_url: url::Url) -> Self {
-        Self {
-            client: reqwest::blocking::Client::new(),
-            base_url,
-        }
+        Self::with_transport_config(base_url, &Default::default())
+            .expect("cannot initialize the TLS backend")
     }
 
-    /// Make a turn-by-turn routing request
+    /// Creates a client with custom TLS or connection pool settings
     ///
-    /// See <https://valhalla.github.io/valhalla/api/turn-by-turn/api-reference> for details
-    pub fn route(&self, manifest: route::Manifest) -> Result<route::Trip, Error> {
-        debug!(
-            "Sending routing request: {}",
-            serde_json::to_string(&manifest).unwrap()
+    /// E.g. for a server whose certificate is not signed by a public CA, or for high request
+    /// rates which need more pooled connections.
+    /// Sends requests via reqwest, or via ureq if only the `ureq` feature is enabled, like
+    /// [`Self::new`].
+    /// Fails if a certificate of `config` cannot be parsed.
+    /// Redirects are followed as described in [`Self::new`].
+    ///
+    /// # Example:
+    /// ```rust,no_run
+    /// use std::time::Duration;
+    /// use valhalla_client::Valhalla;
+    /// use valhalla_client::transport::TransportConfig;
+    ///
+    /// let config = TransportConfig::builder()
+    ///   .add_root_certificate_pem(std::fs::read("corporate-ca.pem").unwrap())
+    ///   .pool_max_idle_per_host(64)
+    ///   .pool_idle_timeout(Duration::from_secs(30));
+    /// let valhalla = Valhalla::with_transport_config(
+    ///   url::Url::parse("https://valhalla.internal/").unwrap(),
+    ///   &config,
+    /// )
+    /// .unwrap()
+    /// .max_concurrent_requests(64);
+    /// ```
+    #[cfg(any(feature = "reqwest", feature = "ureq"))]
+    pub fn with_transport_config(
+        base_url: url::Url,
+        config: &transport::TransportConfig,
+    ) -> Result<Self, Error> {
+        #[cfg(feature = "reqwest")]
+        let transport = config.reqwest_client()?;
+        #[cfg(not(feature = "reqwest"))]
+        let transport = config.ureq_agent()?;
+        Ok(Self::with_transport(transport, base_url))
+    }
+
+    /// Creates a client from a [`ClientConfig`], e.g. loaded from a configuration file
+    ///
+    /// See [`config`] for an example.
+    /// Fails with [`Error::Config`] if a setting is out of range or the base url is not an
+    /// http(s) url, and with [`Error::Url`] if the base url cannot be parsed.
+    #[cfg(any(feature = "reqwest", feature = "ureq"))]
+    pub fn from_config(config: &ClientConfig) -> Result<Self, Error> {
+        let base_url = config
+            .base_url
+            .as_deref()
+            .unwrap_or(VALHALLA_PUBLIC_API_URL);
+        let base_url = url::Url::parse(base_url).map_err(Error::Url)?;
+        // e.g. `localhost:8002` parses, with `localhost` as the scheme
+        if base_url.cannot_be_a_base() || !matches!(base_url.scheme(), "http" | "https") {
+            return Err(Error::Config(format!(
+                "base_url {base_url} is not an http or https url"
+            )));
+        }
+        let mut transport = transport::TransportConfig::builder();
+        if let Some(timeout) = config.timeout_secs {
+            transport = transport.timeout(config::seconds("timeout_secs", timeout)?);
+        }
+        if let Some(max) = config.pool_max_idle_per_host {
+            transport = transport.pool_max_idle_per_host(max);
+        }
+        if let Some(timeout) = config.pool_idle_timeout_secs {
+            transport =
+                transport.pool_idle_timeout(config::seconds("pool_idle_timeout_secs", timeout)?);
+        }
+        let mut valhalla = Self::with_transport_config(base_url, &transport)?.retries(
+            config.retries,
+            std::time::Duration::from_millis(config.retry_backoff_ms),
         );
-        let mut url = self.base_url.clone();
-        url.path_segments_mut()
-            .expect("base_url is not a valid base url")
-            .push("route");
-        let response = self
-            .client
-            .post(url)
-            .json(&manifest)
-            .send()
-            .map_err(Error::Reqwest)?;
-        if response.status().is_client_error() {
-            return Err(Error::RemoteError(response.json().map_err(Error::Reqwest)?));
-        }
-        response.error_for_status_ref().map_err(Error::Reqwest)?;
-        let text = response.text().map_err(Error::Reqwest)?;
-        let response: route::Response = serde_json::from_str(&text).map_err(Error::Serde)?;
-        Ok(response.trip)
+        if let Some(auth) = &config.auth {
+            valhalla = valhalla.auth(auth.clone());
+        }
+        match config.max_concurrent_requests {
+            Some(0) => {
+                return Err(Error::Config(
+                    "max_concurrent_requests must be positive".into(),
+                ))
+            }
+            Some(max) => valhalla = valhalla.max_concurrent_requests(max),
+            None => {}
+        }
+        if let Some(limit) = config.rate_limit {
+            if limit.requests_per_second.is_nan() || limit.requests_per_second <= 0.0 {
+                return Err(Error::Config(
+                    "rate_limit.requests_per_second must be positive".into(),
+                ));
+            }
+            valhalla = valhalla.rate_limit(limit.requests_per_second, limit.burst);
+        }
+        if let Some(breaker) = config.circuit_breaker {
+            let open_for = config::seconds("circuit_breaker.open_for_secs", breaker.open_for_secs)?;
+            valhalla = valhalla.circuit_breaker(breaker.failures, open_for);
+        }
+        Ok(valhalla)
     }
-    /// Make a time-distance matrix routing request
+
+    /// Creates a client configured via environment variables
     ///
-    /// See <https://valhalla.github.io/valhalla/api/matrix/api-reference> for details
+    /// Allows running the same binary against different routers, e.g. for development,
+    /// staging and production.
+    /// All variables are optional:
+    ///
+    /// | Variable | Meaning |
+    /// |----------|---------|
+    /// | `VALHALLA_URL` | base url of the server, see [`Self::new`]; defaults to the public FOSSGIS instance |
+    /// | `VALHALLA_API_KEY` | sent as [`Auth::Bearer`] token |
+    /// | `VALHALLA_API_KEY_HEADER` | send `VALHALLA_API_KEY` in this header instead, e.g. `x-api-key` ([`Auth::Header`]) |
+    /// | `VALHALLA_TIMEOUT_SECS` | [`transport::TransportConfig::timeout`], fractions are allowed |
+    /// | `VALHALLA_RETRIES` | number of [`Self::retries`] |
+    /// | `VALHALLA_RETRY_BACKOFF_MS` | backoff before the first retry, defaults to 200 |
+    ///
+    /// Empty variables are treated like unset ones.
+    /// Fails like [`Self::from_config`], or with [`Error::Config`] if a number cannot be parsed.
+    ///
+    /// # Example:
+    /// ```rust,no_run
+    /// // VALHALLA_URL=https://valhalla.internal/ VALHALLA_RETRIES=3 ./my-fleet-planner
+    /// let valhalla = valhalla_client::Valhalla::from_env().unwrap();
+    /// ```
+    #[cfg(any(feature = "reqwest", feature = "ureq"))]
+    pub fn from_env() -> Result<Self, Error> {
+        Self::from_vars(|name| std::env::var(name).ok())
+    }
+
+    /// [`Self::from_env`], reading variables via `var`
+    #[cfg(any(feature = "reqwest", feature = "ureq"))]
+    fn from_vars(var: impl Fn(&str) -> Option<String>) -> Result<Self, Error> {
+        let var = |name: &str| var(name).filter(|value| !value.is_empty());
+        fn parse<T: std::str::FromStr>(name: &str, value: String) -> Result<T, Error>
+        where
+            T::Err: std::fmt::Display,
+        {
+            value
+                .parse()
+                .map_err(|e| Error::Config(format!("{name}={value:?}: {e}")))
+        }
+        let mut config = ClientConfig {
+            base_url: var("VALHALLA_URL"),
+            auth: var("VALHALLA_API_KEY").map(|key| match var("VALHALLA_API_KEY_HEADER") {
+                Some(name) => Auth::Header { name, value: key },
+                None => Auth::Bearer(key),
+            }),
+            ..Default::default()
+        };
+        if let Some(timeout) = var("VALHALLA_TIMEOUT_SECS") {
+            config.timeout_secs = Some(parse("VALHALLA_TIMEOUT_SECS", timeout)?);
+        }
+        if let Some(retries) = var("VALHALLA_RETRIES") {
+            config.retries = parse("VALHALLA_RETRIES", retries)?;
+        }
+        if let Some(backoff) = var("VALHALLA_RETRY_BACKOFF_MS") {
+            config.retry_backoff_ms = parse("VALHALLA_RETRY_BACKOFF_MS", backoff)?;
+        }
+        Self::from_config(&config)
+    }
+
+    /// The client shared by the whole process
+    ///
+    /// Creating a client per request (e.g. in the handler of a web server) creates a new
+    /// connection pool each time, so every request pays for a new connection and TLS handshake.
+    /// Clients are cheap to clone and share their connection pool, limits and circuit breaker,
+    /// but passing one around is not always convenient.
+    ///
+    /// Configure the shared client once at startup via [`Self::set_shared`]; if that did not
+    /// happen before the first call, it is [`Self::default`].
     ///
     /// # Example:
     /// ```rust,no_run
-    /// use chrono::Local;
     /// use valhalla_client::Valhalla;
-    /// use valhalla_client::matrix::{DateTime, Location, Manifest,};
-    /// use valhalla_client::costing::Costing;
     ///
-    /// let amsterdam = Location::new(4.9041, 52.3676);
-    /// let utrecht = Location::new(5.1214, 52.0907);
-    /// let rotterdam = Location::new(4.4775302894411, 51.92485867761482);
-    /// let den_haag = Location::new(4.324908478055228, 52.07934071633195);
+    /// // at startup
+    /// Valhalla::set_shared(Valhalla::from_env().unwrap()).ok();
+    /// // in request handlers
+    /// let status = Valhalla::shared().status(Default::default()).unwrap();
+    /// ```
+    #[cfg(any(feature = "reqwest", feature = "ureq"))]
+    pub fn shared() -> &'static Self {
+        SHARED.get_or_init(Self::default)
+    }
+
+    /// Configures the client returned by [`Self::shared`]
     ///
-    /// let manifest = Manifest::builder()
-    ///   .verbose_output(true)
-    ///   .sources_to_targets([utrecht],[amsterdam,rotterdam,den_haag])
-    ///   .date_time(DateTime::from_departure_time(Local::now().naive_local()))
-    ///   .costing(Costing::Auto(Default::default()));
+    /// Fails with [`Error::Config`] if the shared client was already configured or used.
+    #[cfg(any(feature = "reqwest", feature = "ureq"))]
+    pub fn set_shared(valhalla: Self) -> Result<(), Error> {
+        SHARED
+            .set(valhalla)
+            .map_err(|_| Error::Config("the shared client is already in use".into()))
+    }
+
+    /// Uses a pre-configured HTTP client
     ///
-    /// let response = Valhalla::default()
-    ///   .matrix(manifest)
+    /// Allows configuring proxies, default headers, timeouts or anything else
+    /// [`transport::TransportConfig`] does not cover.
+    ///
+    /// # Example:
+    /// ```rust
+    /// use valhalla_client::Valhalla;
+    ///
+    /// let client = reqwest::blocking::Client::builder()
+    ///   .timeout(std::time::Duration::from_secs(10))
+    ///   .user_agent("my-fleet-planner/1.0")
+    ///   .build()
     ///   .unwrap();
-    /// # use valhalla_client::matrix::Response;
-    /// # if let Response::Verbose(r) = response{
-    /// #   assert!(r.warnings.is_empty());
-    /// #   assert_eq!(r.sources.len(),1);
-    /// #   assert_eq!(r.targets.len(),3);
-    /// # };
+    /// let valhalla = Valhalla::with_client(client, url::Url::parse("http://localhost:8002/").unwrap());
     /// ```
-    pub fn matrix(&self, manifest: matrix::Manifest) -> Result<matrix::Response, Error> {
-        debug_assert_ne!(
-            manifest.targets.len(),
-            0,
-            "a matrix route needs at least one target specified"
-        );
-        debug_assert_ne!(
-            manifest.sources.len(),
-            0,
-            "a matrix route needs at least one source specified"
-        );
+    #[cfg(feature = "reqwest")]
+    pub fn with_client(client: reqwest::blocking::Client, base_url: url::Url) -> Self {
+        Self::with_transport(client, base_url)
+    }
 
-        debug!(
-            "Sending routing request: {}",
-            serde_json::to_string(&manifest).unwrap()
-        );
-        let mut url = self.base_url.clone();
-        url.path_segments_mut()
-            .expect("base_url is not a valid base url")
-            .push("sources_to_targets");
-        let response = self
-            .client
-            .post(url)
-            .json(&manifest)
-            .send()
-            .map_err(Error::Reqwest)?;
-        if response.status().is_client_error() {
-            return Err(Error::RemoteError(response.json().map_err(Error::Reqwest)?));
-        }
-        response.error_for_status_ref().map_err(Error::Reqwest)?;
-        let text = response.text().map_err(Error::Reqwest)?;
-        let response: matrix::Response = serde_json::from_str(&text).map_err(Error::Serde)?;
-        Ok(response)
+    /// Sends requests via another HTTP client than reqwest, or a test double
+    ///
+    /// See [`transport`] for details.
+    pub fn with_transport(
+        transport: impl transport::HttpTransport + 'static,
+        base_url: url::Url,
+    ) -> Self {
+        Self {
+            transport: Arc::new(transport),
+            base_url,
+            concurrency: Default::default(),
+            rate_limit: None,
+            circuit_breaker: None,
+            response_adapter: None,
+            request_method: Default::default(),
+            single_flight: None,
+            auth: None,
+            deadline: None,
+            cancellation_token: None,
+            coordinate_precision: precision::DEFAULT_DECIMALS,
+            retries: 0,
+            #[cfg(feature = "gzip")]
+            compression_threshold: None,
+            retry_backoff: std::time::Duration::ZERO,
+            action_paths: Default::default(),
+            capture: None,
+            last_rate_limit: Default::default(),
+            service_limits: Default::default(),
+        }
     }
-    /// Make an elevation request
+
+    /// Number of decimals coordinates in manifests are rounded to
     ///
-    /// Valhalla's elevation lookup service provides digital elevation model (DEM) data as the result of a query.
-    /// The elevation service data has many applications when combined with other routing and navigation data, including computing the steepness of roads and paths or generating an elevation profile chart along a route.
+    /// Rounding keeps requests small and their JSON stable, regardless of how the coordinates
+    /// were computed.
+    /// Manifests serialized outside of this client (e.g. via `serde_json::to_string`) use the
+    /// default.
     ///
-    /// For example, you can get elevation data for a point, a trail, or a trip.
-    /// You might use the results to consider hills for your bicycle trip, or when estimating battery usage for trips in electric vehicles.
+    /// Default: `6`, about 0.1m
+    pub fn coordinate_precision(mut self, decimals: u8) -> Self {
+        self.coordinate_precision = decimals;
+        self
+    }
+    /// Sends requests for `action` (e.g. `"route"`) to `path` instead
     ///
-    /// See <https://valhalla.github.io/valhalla/api/elevation/api-reference/> for details
+    /// `path` is relative to the base url and may consist of several segments, for deployments
+    /// which rename actions or expose them under a versioned path.
+    /// Prefixes shared by all actions are best put into the base url instead.
+    ///
+    /// Default: every action is sent to the path of the same name
     ///
     /// # Example:
+    /// ```rust
+    /// use valhalla_client::Valhalla;
     ///
-    /// ```rust,no_run
+    /// let valhalla = Valhalla::default()
+    ///   .action_path("route", "v1/directions")
+    ///   .action_path("sources_to_targets", "v1/matrix");
+    /// ```
+    pub fn action_path(mut self, action: &str, path: impl Into<String>) -> Self {
+        self.action_paths.insert(action.to_string(), path.into());
+        self
+    }
+    /// Configures whether manifests are sent as POST body or as `json` query parameter of a GET request
+    ///
+    /// Default: [`RequestMethod::Post`]
+    pub fn request_method(mut self, request_method: RequestMethod) -> Self {
+        self.request_method = request_method;
+        self
+    }
+
+    /// Rewrites every successful response before it is deserialized
+    ///
+    /// The adapter receives the action (e.g. `"route"`) and the response as parsed JSON.
+    /// Allows talking to forks of valhalla which rename fields or wrap responses in an envelope,
+    /// without forking the response models.
+    ///
+    /// # Example:
+    /// ```rust
     /// use valhalla_client::Valhalla;
-    /// use valhalla_client::elevation::Manifest;
     ///
-    /// let request = Manifest::builder()
-    ///   .shape([
-    ///     (40.712431, -76.504916),
-    ///     (40.712275, -76.605259),
-    ///     (40.712122, -76.805694),
-    ///     (40.722431, -76.884916),
-    ///     (40.812275, -76.905259),
-    ///     (40.912122, -76.965694),
-    ///   ])
-    ///   .include_range();
-    /// let response = Valhalla::default()
-    ///   .elevation(request).unwrap();
-    /// # assert!(response.height.is_empty());
-    /// # assert_eq!(response.range_height.len(), 6);
-    /// # assert!(response.encoded_polyline.is_none());
-    /// # assert!(response.warnings.is_empty());
-    /// # assert_eq!(response.x_coordinate, None);
-    /// # assert_eq!(response.y_coordinate, None);
-    /// # assert_eq!(response.shape.map(|s|s.len()),Some(6));
+    /// // the server wraps every response as {"data": ...}
+    /// let valhalla = Valhalla::default().response_adapter(|_action, mut response| {
+    ///   response["data"].take()
+    /// });
     /// ```
-    pub fn elevation(&self, manifest: elevation::Manifest) -> Result<elevation::Response, Error> {
-        println!(
-            "Sending routing request: {}",
-            serde_json::to_string(&manifest).unwrap()
+    pub fn response_adapter(
+        mut self,
+        adapter: impl Fn(&str, serde_json::Value) -> serde_json::Value + Send + Sync + 'static,
+    ) -> Self {
+        self.response_adapter = Some(Arc::new(adapter));
+        self
+    }
+
+    /// Limits the number of requests this client has in flight at the same time.
+    ///
+    /// `Valhalla` can be shared between threads (e.g. via [`std::sync::Arc`]).
+    /// Once the limit is reached, further requests block until a running request finishes.
+    /// This keeps applications issuing thousands of requests from exhausting sockets or
+    /// overwhelming a small self-hosted instance.
+    /// Waiting requests are counted by [`Self::queued_requests`]; use [`Self::with_deadline`]
+    /// to bound how long they wait.
+    ///
+    /// Default: unlimited
+    ///
+    /// # Panics
+    ///
+    /// If `max_concurrent_requests` is `0`, as no request could ever be sent
+    pub fn max_concurrent_requests(mut self, max_concurrent_requests: usize) -> Self {
+        assert!(
+            max_concurrent_requests > 0,
+            "max_concurrent_requests must be positive"
         );
-        let mut url = self.base_url.clone();
-        url.path_segments_mut()
-            .expect("base_url is not a valid base url")
-            .push("height");
-        let response = self
-            .client
-            .post(url)
-            .json(&manifest)
-            .send()
-            .map_err(Error::Reqwest)?;
-        if response.status().is_client_error() {
-            return Err(Error::RemoteError(response.json().map_err(Error::Reqwest)?));
-        }
-        response.error_for_status_ref().map_err(Error::Reqwest)?;
-        let text = response.text().map_err(Error::Reqwest)?;
-        let response: elevation::Response = serde_json::from_str(&text).map_err(Error::Serde)?;
-        Ok(response)
+        self.concurrency = concurrency::Limiter::new(max_concurrent_requests);
+        self
     }
-    /// Make a time-distance matrix routing request
+    /// Limits the rate at which this client sends requests
     ///
-    /// This can be used as a health endpoint for the HTTP API or to toggle features in a frontend.
+    /// Up to `burst` requests are sent immediately, after which requests are spaced out to
+    /// `requests_per_second`; requests beyond that block until they may be sent.
+    /// The limit applies to all threads sharing this client.
+    /// Helps staying within the fair-use policy of the public OSM instance.
     ///
-    /// See <https://valhalla.github.io/valhalla/api/status/api-reference/> for details
+    /// Default: unlimited
+    ///
+    /// # Panics
+    ///
+    /// If `requests_per_second` is not positive
+    pub fn rate_limit(mut self, requests_per_second: f64, burst: u32) -> Self {
+        assert!(
+            requests_per_second > 0.0,
+            "requests_per_second must be positive, not {requests_per_second}"
+        );
+        self.rate_limit = Some(rate_limit::TokenBucket::new(requests_per_second, burst));
+        self
+    }
+    /// Fails requests with [`Error::CircuitOpen`] without sending them while the server is down
+    ///
+    /// After `failures` consecutive requests failed with a [retryable](Error::is_retryable) or
+    /// [server side](Error::is_server_error) error, requests fail fast for `open_for`.
+    /// Then a single request is let through to probe the server: if it succeeds, requests are
+    /// sent again, otherwise they keep failing fast for another `open_for`.
+    /// This keeps request handling threads from piling up on timeouts of a dead backend.
+    /// The state is shared by all clones of this client.
+    ///
+    /// Default: no circuit breaker
+    pub fn circuit_breaker(mut self, failures: u32, open_for: std::time::Duration) -> Self {
+        self.circuit_breaker = Some(circuit_breaker::CircuitBreaker::new(failures, open_for));
+        self
+    }
+    /// Writes every request and its response to `directory`, one JSON file per request
+    ///
+    /// Each file records the action, the manifest sent, the status code, the response (or the
+    /// error if there was none) and the latency in milliseconds.
+    /// Retries are recorded as separate files.
+    /// Meant for reproducing deserialization bugs and attaching payloads to bug reports; the
+    /// credentials of [`Self::auth`] are not written, but the manifests and responses may
+    /// contain personal data such as locations.
+    /// The directory is created if it does not exist; failing to write to it is logged, but
+    /// does not fail the request.
+    ///
+    /// Default: nothing is captured
     ///
     /// # Example:
     /// ```rust,no_run
     /// use valhalla_client::Valhalla;
-    /// use valhalla_client::status::Manifest;
     ///
-    /// let request = Manifest::builder()
-    ///   .verbose_output(false);
-    /// let response = Valhalla::default()
-    ///   .status(request).unwrap();
+    /// let valhalla = Valhalla::default().capture("valhalla-capture");
+    /// ```
+    pub fn capture(mut self, directory: impl Into<std::path::PathBuf>) -> Self {
+        self.capture = Some(Arc::new(capture::Capture::new(directory.into())));
+        self
+    }
+    /// Rejects manifests exceeding the limits of the server before sending them
+    ///
+    /// Requests exceeding them fail with [`Error::Validation`] naming the limit, instead of
+    /// the error of valhalla.
+    /// Applies to [`Self::route`], [`Self::matrix`] and [`Self::elevation`] and the methods
+    /// built on them.
+    ///
+    /// Default: no limits are checked
+    ///
+    /// # Example:
+    /// ```rust,no_run
+    /// use valhalla_client::Valhalla;
+    /// use valhalla_client::validation::ServiceLimits;
+    ///
+    /// let valhalla = Valhalla::default().service_limits(ServiceLimits {
+    ///   max_locations: Some(20),
+    ///   max_matrix_location_pairs: Some(2500),
+    ///   ..Default::default()
+    /// });
+    /// ```
+    pub fn service_limits(mut self, limits: validation::ServiceLimits) -> Self {
+        self.service_limits = Arc::new(limits);
+        self
+    }
+    /// Gzip-compresses request bodies larger than `bytes`, sending them with
+    /// `Content-Encoding: gzip`
+    ///
+    /// Manifests with large exclusion polygons or long shapes can run to megabytes.
+    /// The server, or a proxy in front of it, has to support compressed requests; valhalla
+    /// itself does not.
+    /// Only applies to [`RequestMethod::Post`].
+    ///
+    /// Default: requests are not compressed
+    #[cfg(feature = "gzip")]
+    pub fn compress_requests_over(mut self, bytes: usize) -> Self {
+        self.compression_threshold = Some(bytes);
+        self
+    }
+    /// Retries requests failing with a [retryable](Error::is_retryable) error up to `retries` times
+    ///
+    /// The first retry happens after `backoff`, every further one waits twice as long as the
+    /// one before.
+    /// Retries respect [`Self::with_deadline`] and [`Self::with_cancellation_token`]: once the
+    /// next attempt would start after the deadline, the last error is returned instead.
+    ///
+    /// If the server asks to wait longer via `Retry-After`, see [`Error::retry_after`], the retry
+    /// waits as long as asked.
+    ///
+    /// Default: no retries
+    pub fn retries(mut self, retries: u32, backoff: std::time::Duration) -> Self {
+        self.retries = retries;
+        self.retry_backoff = backoff;
+        self
+    }
+    /// The rate limit reported by the most recent response which reported one
+    ///
+    /// Allows pacing requests to hosted providers before they respond with HTTP 429, e.g. by
+    /// slowing down once [`transport::RateLimit::remaining`] runs low.
+    /// Shared by all clones of this client.
+    /// `None` until a response reported a rate limit via its headers.
+    pub fn last_rate_limit(&self) -> Option<transport::RateLimit> {
+        *self.last_rate_limit.lock().unwrap()
+    }
+    /// Sends `auth` as credentials with every request
+    ///
+    /// Default: no credentials
+    pub fn auth(mut self, auth: Auth) -> Self {
+        self.auth = Some(auth);
+        self
+    }
+    /// A client which sends `auth` instead of the credentials configured via [`Self::auth`]
+    ///
+    /// Everything else, including the connection pool and the limits, is shared with `self`.
+    /// This allows one client to serve many tenants which each have their own API key.
+    ///
+    /// # Example:
+    /// ```rust
+    /// use valhalla_client::{Auth, Valhalla};
+    ///
+    /// let valhalla = Valhalla::default().max_concurrent_requests(16);
+    /// let tenant = valhalla.with_auth(Auth::Header {
+    ///     name: "x-api-key".to_string(),
+    ///     value: "key of the tenant".to_string(),
+    /// });
+    /// ```
+    pub fn with_auth(&self, auth: Auth) -> Self {
+        self.clone().auth(auth)
+    }
+    /// A client whose requests fail with [`Error::DeadlineExceeded`] once `deadline` has passed
+    ///
+    /// The deadline bounds the whole request: waiting for the rate limit and for a free slot
+    /// of [`Self::max_concurrent_requests`], connecting and reading the response.
+    /// Requests which are still in flight at the deadline are aborted by the HTTP transport.
+    ///
+    /// # Example:
+    /// ```rust,no_run
+    /// use std::time::{Duration, Instant};
+    /// use valhalla_client::{status, Valhalla};
+    ///
+    /// let valhalla = Valhalla::default();
+    /// let deadline = Instant::now() + Duration::from_secs(2);
+    /// let status = valhalla
+    ///     .with_deadline(deadline)
+    ///     .status(status::Manifest::builder());
+    /// ```
+    pub fn with_deadline(&self, deadline: Instant) -> Self {
+        let mut valhalla = self.clone();
+        valhalla.deadline = Some(deadline);
+        valhalla
+    }
+    /// A client whose requests fail with [`Error::Cancelled`] once `token` is cancelled
+    ///
+    /// The token is checked before a request is sent, including after waiting for the rate
+    /// limit or a free slot of [`Self::max_concurrent_requests`].
+    /// A blocking HTTP call cannot be interrupted once it has been sent, so combine this with
+    /// [`Self::with_deadline`] to bound how long cancelling may take.
+    pub fn with_cancellation_token(&self, token: batch::CancellationToken) -> Self {
+        let mut valhalla = self.clone();
+        valhalla.cancellation_token = Some(token);
+        valhalla
+    }
+    /// Coalesces identical requests which are in flight at the same time into a single one
+    ///
+    /// Requests are identical if they have the same action and serialize to the same manifest.
+    /// A request which arrives while an identical one is awaiting its response waits for it
+    /// and receives a copy of the same response instead of sending its own.
+    /// Useful for UIs which fire the same route request on every keystroke.
+    /// Failed requests are not shared: each waiting request then retries by itself.
+    /// Waiting requests still fail once their [deadline](Self::with_deadline) has passed or
+    /// they are [cancelled](Self::with_cancellation_token).
+    ///
+    /// Default: `false`
+    pub fn deduplicate_requests(mut self, deduplicate: bool) -> Self {
+        self.single_flight = deduplicate.then(Default::default);
+        self
+    }
+    /// Number of requests currently being sent or awaiting a response
+    pub fn in_flight_requests(&self) -> usize {
+        self.concurrency.in_flight()
+    }
+    /// Number of requests waiting for [`Self::max_concurrent_requests`] to allow them to be sent
+    pub fn queued_requests(&self) -> usize {
+        self.concurrency.queued()
+    }
+
+    /// Make a turn-by-turn routing request
+    ///
+    /// See <https://valhalla.github.io/valhalla/api/turn-by-turn/api-reference> for details
+    pub fn route(&self, manifest: route::Manifest) -> Result<route::Trip, Error> {
+        manifest
+            .validate_with(&self.service_limits)
+            .map_err(Error::Validation)?;
+        let response = self.exchange("route", &manifest)?;
+        self.parse_route_response(response)
+    }
+    /// Make a turn-by-turn routing request, keeping the legs which can be parsed
+    ///
+    /// Unlike [`Self::route`], a leg which cannot be parsed (e.g. because a proxy mangled its
+    /// polyline) does not fail the whole trip.
+    /// It is left out of [`route::Trip::legs`] instead and the reason is added to
+    /// [`route::Trip::parse_warnings`], so that apps displaying the route can degrade gracefully.
+    pub fn route_lenient(&self, manifest: route::Manifest) -> Result<route::Trip, Error> {
+        manifest
+            .validate_with(&self.service_limits)
+            .map_err(Error::Validation)?;
+        let response: route::LenientResponse = self.do_request("route", &manifest)?;
+        route::Trip::from_value_lenient(response.trip).map_err(Error::Serde)
+    }
+    /// Make many turn-by-turn routing requests, up to `max_concurrency` of them at a time
+    ///
+    /// Returns one result per manifest, in the order of `manifests`.
+    /// A failing request does not affect the others.
+    /// The requests are sent from scoped threads, [`Self::max_concurrent_requests`] and
+    /// [`Self::rate_limit`] still apply to them.
+    ///
+    /// # Example:
+    /// ```rust,no_run
+    /// use valhalla_client::Valhalla;
+    /// use valhalla_client::costing::Costing;
+    /// use valhalla_client::route::{Location, ManifestBuilder};
+    ///
+    /// let depot = Location::new(4.9041, 52.3676);
+    /// let customers = [(5.1214, 52.0907), (4.4777, 51.9244), (6.5665, 53.2194)];
+    /// let manifests = customers.into_iter().map(|(lon, lat)| {
+    ///   ManifestBuilder::default()
+    ///     .locations([depot.clone(), Location::new(lon, lat)])
+    ///     .costing(Costing::default())
+    /// });
+    /// let trips = Valhalla::default().route_many(manifests, 4);
+    /// # assert_eq!(trips.len(), 3);
+    /// ```
+    pub fn route_many(
+        &self,
+        manifests: impl IntoIterator<Item = route::Manifest>,
+        max_concurrency: usize,
+    ) -> Vec<Result<route::Trip, Error>> {
+        concurrency::map_concurrently(
+            manifests.into_iter().collect(),
+            max_concurrency,
+            |manifest| self.route(manifest),
+        )
+    }
+    /// Make a turn-by-turn routing request, keeping a summary of the request next to the trip
+    ///
+    /// See [`route::RoutedTrip`] for details.
+    pub fn route_with_request(
+        &self,
+        manifest: route::Manifest,
+    ) -> Result<route::RoutedTrip, Error> {
+        let request = manifest.summary();
+        let trip = self.route(manifest)?;
+        if trip.id != request.id {
+            warn!(
+                "route response id {:?} does not match the request id {:?}",
+                trip.id, request.id
+            );
+        }
+        Ok(route::RoutedTrip { trip, request })
+    }
+    /// Make a turn-by-turn routing request, returning the alternate routes as well
+    ///
+    /// The first trip is the one [`Self::route`] would return, followed by up to
+    /// [`route::Manifest::alternates`] alternate trips.
+    ///
+    /// # Example:
+    /// ```rust,no_run
+    /// use valhalla_client::Valhalla;
+    /// use valhalla_client::costing::Costing;
+    /// use valhalla_client::route::{Location, ManifestBuilder};
+    ///
+    /// let amsterdam = Location::new(4.9041, 52.3676);
+    /// let utrecht = Location::new(5.1214, 52.0907);
+    /// let manifest = ManifestBuilder::default()
+    ///   .locations([amsterdam, utrecht])
+    ///   .costing(Costing::default())
+    ///   .alternates(2);
+    ///
+    /// let trips = Valhalla::default()
+    ///   .route_with_alternates(manifest)
+    ///   .unwrap();
+    /// # assert!(!trips.is_empty() && trips.len() <= 3);
+    /// ```
+    pub fn route_with_alternates(
+        &self,
+        manifest: route::Manifest,
+    ) -> Result<Vec<route::Trip>, Error> {
+        manifest
+            .validate_with(&self.service_limits)
+            .map_err(Error::Validation)?;
+        let response: route::Response = self.do_request("route", &manifest)?;
+        let mut trips = Vec::with_capacity(1 + response.alternates.len());
+        trips.push(response.trip);
+        trips.extend(response.alternates.into_iter().map(|a| a.trip));
+        Ok(trips)
+    }
+    /// Search for several routes which differ noticeably from each other
+    ///
+    /// Valhalla's own [`route::Manifest::alternates`] are not supported for multipoint or time
+    /// dependent routes and often return few or very similar routes.
+    /// This instead routes repeatedly, each time excluding a corridor around the previously found
+    /// routes via [`route::Manifest::exclude_polygon`].
+    /// Candidates which overlap too much with an already accepted route are rejected.
+    ///
+    /// The first route is the one [`Self::route`] would return.
+    /// Fewer routes than requested are returned once no further route can be found or
+    /// [`route::DiversityOptions::max_attempts`] is reached.
+    ///
+    /// # Example:
+    /// ```rust,no_run
+    /// use valhalla_client::Valhalla;
+    /// use valhalla_client::costing::Costing;
+    /// use valhalla_client::route::{DiversityOptions, Location, ManifestBuilder};
+    ///
+    /// let amsterdam = Location::new(4.9041, 52.3676);
+    /// let utrecht = Location::new(5.1214, 52.0907);
+    /// let manifest = ManifestBuilder::default()
+    ///   .locations([amsterdam, utrecht])
+    ///   .costing(Costing::default());
+    ///
+    /// let trips = Valhalla::default()
+    ///   .diverse_routes(manifest, DiversityOptions::new(3))
+    ///   .unwrap();
+    /// # assert!(!trips.is_empty());
+    /// ```
+    pub fn diverse_routes(
+        &self,
+        mut manifest: route::Manifest,
+        options: route::DiversityOptions,
+    ) -> Result<Vec<route::Trip>, Error> {
+        options.validate().map_err(Error::Validation)?;
+        let mut trips: Vec<route::Trip> = Vec::with_capacity(options.count);
+        for _ in 0..options.max_attempts {
+            if trips.len() >= options.count {
+                break;
+            }
+            let trip = match self.route(manifest.clone()) {
+                Ok(trip) => trip,
+                // the exclusions leave no path, which is only an error for the first route
+                Err(Error::RemoteError(_)) if !trips.is_empty() => break,
+                Err(e) => return Err(e),
+            };
+            for corridor in options.exclusion_corridors(&trip) {
+                manifest = manifest.exclude_polygon(corridor);
+            }
+            if options.is_distinct(&trip, &trips) {
+                trips.push(trip);
+            }
+        }
+        Ok(trips)
+    }
+    /// Find the least-cost convergence point of multiple locations
+    ///
+    /// The response contains one route per input location, each ending at the centroid.
+    ///
+    /// See <https://valhalla.github.io/valhalla/api/centroid/api-reference/> for details
+    ///
+    /// # Example:
+    /// ```rust,no_run
+    /// use valhalla_client::Valhalla;
+    /// use valhalla_client::centroid::Manifest;
+    /// use valhalla_client::route::Location;
+    ///
+    /// let manifest = Manifest::builder().locations([
+    ///   Location::new(4.9041, 52.3676),
+    ///   Location::new(5.1214, 52.0907),
+    ///   Location::new(4.4775302894411, 51.92485867761482),
+    /// ]);
+    /// let response = Valhalla::default().centroid(manifest).unwrap();
+    /// # assert_eq!(response.routes.len(), 3);
+    /// # assert!(response.centroid().is_some());
+    /// ```
+    pub fn centroid(&self, manifest: centroid::Manifest) -> Result<centroid::Response, Error> {
+        manifest.validate().map_err(Error::Validation)?;
+        let response = self.exchange("centroid", &manifest)?;
+        self.parse_centroid_response(response)
+    }
+    /// Make a time-distance matrix routing request
+    ///
+    /// See <https://valhalla.github.io/valhalla/api/matrix/api-reference> for details
+    ///
+    /// # Example:
+    /// ```rust,no_run
+    /// use chrono::Local;
+    /// use valhalla_client::Valhalla;
+    /// use valhalla_client::matrix::{DateTime, Location, Manifest,};
+    /// use valhalla_client::costing::Costing;
+    ///
+    /// let amsterdam = Location::new(4.9041, 52.3676);
+    /// let utrecht = Location::new(5.1214, 52.0907);
+    /// let rotterdam = Location::new(4.4775302894411, 51.92485867761482);
+    /// let den_haag = Location::new(4.324908478055228, 52.07934071633195);
+    ///
+    /// let manifest = Manifest::builder()
+    ///   .verbose_output(true)
+    ///   .sources_to_targets([utrecht],[amsterdam,rotterdam,den_haag])
+    ///   .date_time(DateTime::from_departure_time(Local::now().naive_local()))
+    ///   .costing(Costing::Auto(Default::default()));
+    ///
+    /// let response = Valhalla::default()
+    ///   .matrix(manifest)
+    ///   .unwrap();
+    /// # use valhalla_client::matrix::Response;
+    /// # if let Response::Verbose(r) = response{
+    /// #   assert!(r.warnings.is_empty());
+    /// #   assert_eq!(r.sources.len(),1);
+    /// #   assert_eq!(r.targets.len(),3);
+    /// # };
+    /// ```
+    pub fn matrix(&self, manifest: matrix::Manifest) -> Result<matrix::Response, Error> {
+        manifest
+            .validate_with(&self.service_limits)
+            .map_err(Error::Validation)?;
+        let response = self.exchange("sources_to_targets", &manifest)?;
+        self.parse_matrix_response(&manifest, response)
+    }
+    /// Compute travel times from an origin to every cell of a grid, e.g. for a heatmap
+    ///
+    /// The cells are sent to the time-distance matrix in chunks of at most
+    /// [`heatmap::Manifest::max_locations_per_request`] locations.
+    /// Cells which cannot be reached are reported as `None`.
+    /// Progress is reported and cancellation checked once per request via `control`.
+    ///
+    /// # Example:
+    /// ```rust,no_run
+    /// use valhalla_client::Valhalla;
+    /// use valhalla_client::heatmap::{BoundingBox, Manifest};
+    /// use valhalla_client::matrix::Location;
+    ///
+    /// let amsterdam = Location::new(4.9041, 52.3676);
+    /// let around_amsterdam = BoundingBox::new((4.80, 52.30), (5.00, 52.42));
+    /// let manifest = Manifest::new(amsterdam, around_amsterdam, 20, 20);
+    ///
+    /// let grid = Valhalla::default()
+    ///   .travel_time_grid(manifest, &Default::default())
+    ///   .unwrap();
+    /// # assert_eq!(grid.durations.len(), 400);
+    /// ```
+    pub fn travel_time_grid(
+        &self,
+        manifest: heatmap::Manifest,
+        control: &batch::Control,
+    ) -> Result<heatmap::TravelTimeGrid, Error> {
+        manifest.validate().map_err(Error::Validation)?;
+        let cells = manifest.cell_centers();
+        let total = cells.len().div_ceil(manifest.max_locations_per_request);
+        let mut durations = Vec::with_capacity(cells.len());
+        for (i, chunk) in cells.chunks(manifest.max_locations_per_request).enumerate() {
+            control.check_cancelled()?;
+            let mut request = matrix::Manifest::builder()
+                .sources_to_targets([manifest.origin], chunk.iter().copied())
+                .costing(manifest.costing.clone())
+                .verbose_output(false);
+            if let Some(date_time) = manifest.date_time {
+                request = request.date_time(date_time);
+            }
+            let chunk = control.adjust(matrix::Chunk {
+                source_offset: 0,
+                target_offset: i * manifest.max_locations_per_request,
+                manifest: request,
+            });
+            match self.matrix(chunk.manifest)? {
+                matrix::Response::Concise(r) => durations.extend(
+                    r.sources_to_targets
+                        .durations
+                        .into_iter()
+                        .next()
+                        .unwrap_or_default(),
+                ),
+                matrix::Response::Verbose(r) => durations.extend(
+                    r.sources_to_targets
+                        .into_iter()
+                        .next()
+                        .unwrap_or_default()
+                        .into_iter()
+                        .map(|s| s.time),
+                ),
+            }
+            control.report(i + 1, total);
+        }
+        // cells missing from a partial result are unreachable as well
+        durations.resize(cells.len(), None);
+        Ok(heatmap::TravelTimeGrid {
+            bbox: manifest.bbox,
+            rows: manifest.rows,
+            cols: manifest.cols,
+            durations,
+        })
+    }
+    /// Compute a huge time-distance matrix, writing results to `sink` as they arrive
+    ///
+    /// The matrix is split into requests of at most `max_location_pairs` source-target pairs
+    /// (see `service_limits.<costing>.max_matrix_location_pairs`, which defaults to `2500`).
+    /// Requests are sent one after another and each result is handed to the sink before the
+    /// next request is made, keeping memory usage flat even for matrices with millions of pairs.
+    /// Progress is reported and cancellation checked once per request via `control`.
+    ///
+    /// # Example:
+    /// ```rust,no_run
+    /// use valhalla_client::Valhalla;
+    /// use valhalla_client::matrix::{CsvSink, Location, Manifest};
+    ///
+    /// let locations: Vec<Location> = (0..100)
+    ///   .map(|i| Location::new(4.8 + i as f32 * 0.002, 52.3676))
+    ///   .collect();
+    /// let manifest = Manifest::builder().sources_to_targets(locations.clone(), locations);
+    ///
+    /// let file = std::fs::File::create("matrix.csv").unwrap();
+    /// let mut sink = CsvSink::new(std::io::BufWriter::new(file));
+    /// Valhalla::default()
+    ///   .matrix_batched(manifest, 2500, &mut sink, &Default::default())
+    ///   .unwrap();
+    /// ```
+    pub fn matrix_batched(
+        &self,
+        manifest: matrix::Manifest,
+        max_location_pairs: usize,
+        sink: &mut impl matrix::MatrixSink,
+        control: &batch::Control,
+    ) -> Result<(), Error> {
+        let total = manifest.chunk_count(max_location_pairs);
+        for (i, chunk) in manifest.chunks(max_location_pairs).enumerate() {
+            control.check_cancelled()?;
+            let chunk = control.adjust(chunk);
+            let entries = self
+                .matrix(chunk.manifest)?
+                .into_entries(chunk.source_offset, chunk.target_offset);
+            sink.write_entries(&entries).map_err(Error::Io)?;
+            control.report(i + 1, total);
+        }
+        Ok(())
+    }
+    /// Compute a time-distance matrix which exceeds the service limits of the server
+    ///
+    /// The matrix is split into requests of at most `max_location_pairs` source-target pairs
+    /// (see `service_limits.<costing>.max_matrix_location_pairs`, which defaults to `2500`),
+    /// up to `max_concurrency` of which are sent at a time.
+    /// Their results are stitched into a single response with the indices of `manifest`.
+    /// Fails if any of the requests fails.
+    /// Progress is reported and cancellation checked once per request via `control`.
+    ///
+    /// Unlike [`Self::matrix_batched`], the whole matrix is kept in memory.
+    ///
+    /// # Example:
+    /// ```rust,no_run
+    /// use valhalla_client::Valhalla;
+    /// use valhalla_client::matrix::{Location, Manifest};
+    ///
+    /// let locations: Vec<Location> = (0..100)
+    ///   .map(|i| Location::new(4.8 + i as f32 * 0.002, 52.3676))
+    ///   .collect();
+    /// let manifest = Manifest::builder().sources_to_targets(locations.clone(), locations);
+    ///
+    /// let matrix = Valhalla::default()
+    ///   .matrix_chunked(manifest, 2500, 4, &Default::default())
+    ///   .unwrap();
+    /// # assert_eq!(matrix.sources_to_targets.durations.len(), 100);
+    /// ```
+    pub fn matrix_chunked(
+        &self,
+        manifest: matrix::Manifest,
+        max_location_pairs: usize,
+        max_concurrency: usize,
+        control: &batch::Control,
+    ) -> Result<matrix::ConciseResponse, Error> {
+        let (sources, targets) = (manifest.sources.len(), manifest.targets.len());
+        let chunks: Vec<_> = manifest.chunks(max_location_pairs).collect();
+        if chunks.is_empty() {
+            // without sources or targets there is nothing to split, let the server explain why
+            let response = self.matrix(manifest.verbose_output(false))?;
+            return Ok(matrix::stitch(sources, targets, vec![(0, 0, response)]));
+        }
+        let total = chunks.len();
+        let completed = std::sync::atomic::AtomicUsize::new(0);
+        let parts = concurrency::map_concurrently(chunks, max_concurrency, |chunk| {
+            control.check_cancelled()?;
+            let chunk = control.adjust(chunk);
+            let response = self.matrix(chunk.manifest)?;
+            let completed = completed.fetch_add(1, std::sync::atomic::Ordering::Relaxed) + 1;
+            control.report(completed, total);
+            Ok::<_, Error>((chunk.source_offset, chunk.target_offset, response))
+        });
+        let parts = parts.into_iter().collect::<Result<Vec<_>, _>>()?;
+        Ok(matrix::stitch(sources, targets, parts))
+    }
+    /// Make an elevation request
+    ///
+    /// Valhalla's elevation lookup service provides digital elevation model (DEM) data as the result of a query.
+    /// The elevation service data has many applications when combined with other routing and navigation data, including computing the steepness of roads and paths or generating an elevation profile chart along a route.
+    ///
+    /// For example, you can get elevation data for a point, a trail, or a trip.
+    /// You might use the results to consider hills for your bicycle trip, or when estimating battery usage for trips in electric vehicles.
+    ///
+    /// See <https://valhalla.github.io/valhalla/api/elevation/api-reference/> for details
+    ///
+    /// # Example:
+    ///
+    /// ```rust,no_run
+    /// use valhalla_client::Valhalla;
+    /// use valhalla_client::elevation::Manifest;
+    ///
+    /// let request = Manifest::builder()
+    ///   .shape([
+    ///     (-76.504916, 40.712431),
+    ///     (-76.605259, 40.712275),
+    ///     (-76.805694, 40.712122),
+    ///     (-76.884916, 40.722431),
+    ///     (-76.905259, 40.812275),
+    ///     (-76.965694, 40.912122),
+    ///   ])
+    ///   .include_range();
+    /// let response = Valhalla::default()
+    ///   .elevation(request).unwrap();
+    /// # assert!(response.height.is_empty());
+    /// # assert_eq!(response.range_height.len(), 6);
+    /// # assert!(response.encoded_polyline.is_none());
+    /// # assert!(response.warnings.is_empty());
+    /// # assert_eq!(response.x_coordinate, None);
+    /// # assert_eq!(response.y_coordinate, None);
+    /// # assert_eq!(response.shape.map(|s|s.len()),Some(6));
+    /// ```
+    pub fn elevation(&self, manifest: elevation::Manifest) -> Result<elevation::Response, Error> {
+        manifest
+            .validate_with(&self.service_limits)
+            .map_err(Error::Validation)?;
+        self.do_request("height", &manifest)
+    }
+    /// Make elevation requests for a shape which is too long for a single request
+    ///
+    /// The shape is split into requests of at most `max_shape_points` points
+    /// (see `service_limits.skadi.max_shape`), which are sent one after another.
+    /// Their heights are concatenated, with the ranges of [`elevation::Manifest::include_range`]
+    /// accumulated along the whole shape.
+    /// Manifests using an [`elevation::Manifest::encoded_polyline`] are sent as they are.
+    pub fn elevation_chunked(
+        &self,
+        manifest: elevation::Manifest,
+        max_shape_points: usize,
+    ) -> Result<elevation::Response, Error> {
+        let parts = manifest
+            .split(max_shape_points)
+            .into_iter()
+            .map(|part| self.elevation(part))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(elevation::Response::concat(parts).expect("a manifest is split into at least one part"))
+    }
+    /// Make a time-distance matrix routing request
+    ///
+    /// This can be used as a health endpoint for the HTTP API or to toggle features in a frontend.
+    ///
+    /// See <https://valhalla.github.io/valhalla/api/status/api-reference/> for details
+    ///
+    /// # Example:
+    /// ```rust,no_run
+    /// use valhalla_client::Valhalla;
+    /// use valhalla_client::status::Manifest;
+    ///
+    /// let request = Manifest::builder()
+    ///   .verbose_output(false);
+    /// let response = Valhalla::default()
+    ///   .status(request).unwrap();
     /// # assert!(response.version >= semver::Version::parse("3.1.4").unwrap());
     /// # assert!(response.tileset_last_modified.timestamp() > 0);
     /// # assert!(response.verbose.is_none());
     /// ```
     pub fn status(&self, manifest: status::Manifest) -> Result<status::Response, Error> {
-        debug!(
-            "Sending routing request: {}",
-            serde_json::to_string(&manifest).unwrap()
-        );
+        self.do_request("status", &manifest)
+    }
+    /// Checks that the server version matches [`MIN_SUPPORTED_VALHALLA`]
+    ///
+    /// Call this once after connecting to a server you do not control, to fail early instead of
+    /// running into deserialization gaps later.
+    /// Returns the servers status on success.
+    ///
+    /// # Example:
+    /// ```rust,no_run
+    /// use valhalla_client::{Error, Valhalla};
+    ///
+    /// match Valhalla::default().check_version() {
+    ///   Ok(status) => println!("connected to valhalla {}", status.version),
+    ///   Err(Error::UnsupportedVersion(version)) => eprintln!("valhalla {version} is too old"),
+    ///   Err(e) => eprintln!("cannot reach valhalla: {e}"),
+    /// }
+    /// ```
+    pub fn check_version(&self) -> Result<status::Response, Error> {
+        let status = self.status(status::Manifest::builder())?;
+        if !status.is_supported_version() {
+            return Err(Error::UnsupportedVersion(status.version));
+        }
+        Ok(status)
+    }
+    /// Send an arbitrary action to the server
+    ///
+    /// Escape hatch for actions or fields this crate does not model yet.
+    /// `manifest` is sent as JSON and the response is deserialized into `Resp`, with the same
+    /// error handling as the typed methods such as [`Self::route`].
+    ///
+    /// # Example:
+    /// ```rust,no_run
+    /// use valhalla_client::Valhalla;
+    ///
+    /// let response: serde_json::Value = Valhalla::default()
+    ///   .call("locate", serde_json::json!({
+    ///     "locations": [{"lat": 52.3676, "lon": 4.9041}],
+    ///     "costing": "auto",
+    ///   }))
+    ///   .unwrap();
+    /// ```
+    pub fn call<Req: Serialize, Resp: DeserializeOwned>(
+        &self,
+        action: &str,
+        manifest: Req,
+    ) -> Result<Resp, Error> {
+        self.do_request(action, &manifest)
+    }
+    /// Send an arbitrary action to the server, with untyped JSON on both ends
+    ///
+    /// Shorthand for [`Self::call`] for when neither the request nor the response are modelled.
+    /// Credentials, limits and error handling are the same as for the typed methods.
+    pub fn send_raw(
+        &self,
+        action: &str,
+        json: serde_json::Value,
+    ) -> Result<serde_json::Value, Error> {
+        self.call(action, json)
+    }
+
+    /// The request [`Self::call`] would send, without sending it
+    ///
+    /// Meant for debugging costing issues and for filing bug reports with valhalla: url and
+    /// body are exactly what the server would receive, after applying e.g.
+    /// [`Self::coordinate_precision`], [`Self::request_method`] and [`Self::action_path`].
+    /// The typed methods send the actions `"route"`, `"sources_to_targets"` ([`Self::matrix`]),
+    /// `"height"` ([`Self::elevation`]), `"centroid"` and `"status"`.
+    ///
+    /// **Note:** The credentials of [`Self::auth`] are part of the request; remove them
+    /// before sharing it.
+    ///
+    /// # Example:
+    /// ```rust
+    /// use valhalla_client::Valhalla;
+    /// use valhalla_client::costing::Costing;
+    /// use valhalla_client::route::{Location, ManifestBuilder};
+    ///
+    /// let manifest = ManifestBuilder::default()
+    ///   .locations([Location::new(4.9041, 52.3676), Location::new(5.1214, 52.0907)])
+    ///   .costing(Costing::default());
+    /// let request = Valhalla::default().dry_run("route", &manifest).unwrap();
+    /// assert_eq!(request.url.as_str(), "https://valhalla1.openstreetmap.de/route");
+    /// let body = String::from_utf8(request.body.unwrap()).unwrap();
+    /// println!("curl -d '{body}' {}", request.url);
+    /// ```
+    pub fn dry_run<Req: Serialize>(
+        &self,
+        action: &str,
+        manifest: &Req,
+    ) -> Result<transport::HttpRequest, Error> {
+        let body = precision::with_decimals(self.coordinate_precision, || {
+            serde_json::to_string(manifest)
+        })
+        .map_err(Error::Serde)?;
+        Ok(self.build_request(action, body))
+    }
+
+    fn do_request<Req: Serialize, Resp: DeserializeOwned>(
+        &self,
+        action: &str,
+        manifest: &Req,
+    ) -> Result<Resp, Error> {
+        let response = self.exchange(action, manifest)?;
+        self.parse_response(action, response)
+    }
+
+    /// Sends the manifest and returns the successful response
+    fn exchange<Req: Serialize>(
+        &self,
+        action: &str,
+        manifest: &Req,
+    ) -> Result<transport::HttpResponse, Error> {
+        let request = self.dry_run(action, manifest)?;
+        if let Some(body) = &request.body {
+            debug!(
+                "Sending {action} request: {}",
+                String::from_utf8_lossy(body)
+            );
+        } else {
+            let url = self.url_without_credentials(&request.url);
+            debug!("Sending {action} request: {url}");
+        }
+        match &self.single_flight {
+            None => self.fetch(action, request),
+            Some(single_flight) => {
+                // hashes the credentials, so responses are never shared between tenants
+                let key = request.fingerprint();
+                single_flight.run(key, self.deadline, self.cancellation_token.as_ref(), || {
+                    self.fetch(action, request)
+                })
+            }
+        }
+    }
+
+    /// Sends the request, retrying as configured, and returns the successful response
+    fn fetch(
+        &self,
+        action: &str,
+        request: transport::HttpRequest,
+    ) -> Result<transport::HttpResponse, Error> {
+        let mut backoff = self.retry_backoff;
+        for _ in 0..self.retries {
+            match self.fetch_once(action, request.clone()) {
+                Err(e) if e.is_retryable() => {
+                    let wait = e.retry_after().map_or(backoff, |after| after.max(backoff));
+                    let retry_at = Instant::now() + wait;
+                    if self.deadline.is_some_and(|deadline| retry_at >= deadline) {
+                        return Err(e);
+                    }
+                    warn!(
+                        "retrying in {wait:?} after {}",
+                        self.redact(&error_chain(&e))
+                    );
+                    std::thread::sleep(wait);
+                    backoff = backoff.saturating_mul(2);
+                }
+                result => return result,
+            }
+        }
+        self.fetch_once(action, request)
+    }
+
+    /// Sends the request once and returns the successful response
+    fn fetch_once(
+        &self,
+        action: &str,
+        mut request: transport::HttpRequest,
+    ) -> Result<transport::HttpResponse, Error> {
+        self.check_cancelled()?;
+        if let Some(rate_limit) = &self.rate_limit {
+            if !rate_limit.acquire(self.deadline) {
+                return Err(Error::DeadlineExceeded);
+            }
+        }
+        let _permit = self
+            .concurrency
+            .acquire_until(self.deadline)
+            .ok_or(Error::DeadlineExceeded)?;
+        self.check_cancelled()?;
+        if let Some(deadline) = self.deadline {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Err(Error::DeadlineExceeded);
+            }
+            request.timeout = Some(remaining);
+        }
+        let Some(breaker) = &self.circuit_breaker else {
+            return self.send(action, request);
+        };
+        if !breaker.try_acquire(Instant::now()) {
+            return Err(Error::CircuitOpen);
+        }
+        let result = self.send(action, request);
+        let failed = result
+            .as_ref()
+            .is_err_and(|e| e.is_retryable() || e.is_server_error());
+        breaker.record(!failed, Instant::now());
+        result
+    }
+
+    /// Sends the request, following redirects, and turns error responses into [`Error`]s
+    fn send(
+        &self,
+        action: &str,
+        mut request: transport::HttpRequest,
+    ) -> Result<transport::HttpResponse, Error> {
+        for _ in 0..MAX_REDIRECTS {
+            let response = self.send_once(action, request.clone())?;
+            let Some(url) = response.redirect(&request) else {
+                return response.error_for_status();
+            };
+            debug!(
+                "following HTTP {} to {}",
+                response.status,
+                self.url_without_credentials(&url)
+            );
+            if url.origin() != request.url.origin() {
+                // like HTTP clients, keep the credentials from other servers
+                if let Some(name) = self.auth.as_ref().and_then(Auth::header_name) {
+                    request
+                        .headers
+                        .retain(|(n, _)| !n.eq_ignore_ascii_case(name));
+                }
+            }
+            request.url = url;
+        }
+        self.send_once(action, request)?.error_for_status()
+    }
+
+    /// Sends the request via the transport once, without looking at the status
+    fn send_once(
+        &self,
+        action: &str,
+        request: transport::HttpRequest,
+    ) -> Result<transport::HttpResponse, Error> {
+        let manifest = self.capture.as_ref().and_then(|_| match &request.body {
+            Some(body) => Some(String::from_utf8_lossy(body).into_owned()),
+            None => request
+                .url
+                .query_pairs()
+                .find(|(name, _)| name == "json")
+                .map(|(_, json)| json.into_owned()),
+        });
+        #[cfg(feature = "gzip")]
+        let request = self.compress(request)?;
+        let started = Instant::now();
+        let response = self.transport.send(request);
+        if let Some(capture) = &self.capture {
+            // transport errors may contain the url, including the query credentials
+            let outcome = response.as_ref().map_err(|e| self.redact(&error_chain(e)));
+            capture.record(action, manifest.as_deref(), outcome, started.elapsed());
+        }
+        if let Some(rate_limit) = response.as_ref().ok().and_then(|r| r.rate_limit()) {
+            *self.last_rate_limit.lock().unwrap() = Some(rate_limit);
+        }
+        match response {
+            Err(_)
+                if self
+                    .deadline
+                    .is_some_and(|deadline| Instant::now() >= deadline) =>
+            {
+                Err(Error::DeadlineExceeded)
+            }
+            response => response,
+        }
+    }
+
+    /// Compresses the body if it exceeds [`Self::compress_requests_over`]
+    #[cfg(feature = "gzip")]
+    fn compress(
+        &self,
+        mut request: transport::HttpRequest,
+    ) -> Result<transport::HttpRequest, Error> {
+        use std::io::Write;
+        let (Some(threshold), Some(body)) = (self.compression_threshold, &request.body) else {
+            return Ok(request);
+        };
+        if body.len() <= threshold {
+            return Ok(request);
+        }
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::fast());
+        encoder.write_all(body).map_err(Error::Io)?;
+        request.body = Some(encoder.finish().map_err(Error::Io)?);
+        request
+            .headers
+            .push(("Content-Encoding".to_string(), "gzip".to_string()));
+        Ok(request)
+    }
+
+    fn check_cancelled(&self) -> Result<(), Error> {
+        match &self.cancellation_token {
+            Some(token) if token.is_cancelled() => Err(Error::Cancelled),
+            _ => Ok(()),
+        }
+    }
+
+    /// Reads the response to a request built by [`Self::dry_run`]
+    ///
+    /// Together with [`Self::dry_run`], this allows speaking the protocol without sending
+    /// anything, e.g. over an async HTTP client or a message queue: error responses become
+    /// [`Error`]s and the body is deserialized, exactly as by the typed methods.
+    /// [`Self::response_adapter`] is applied; retries, limits and the like are up to the caller.
+    ///
+    /// Use [`Self::parse_route_response`], [`Self::parse_centroid_response`] and
+    /// [`Self::parse_matrix_response`] for the actions whose response is converted further.
+    ///
+    /// # Example:
+    /// ```rust
+    /// use valhalla_client::{status, transport::HttpResponse, Valhalla};
+    ///
+    /// let valhalla = Valhalla::default();
+    /// let request = valhalla.dry_run("status", &status::Manifest::builder()).unwrap();
+    /// // send `request` by any means, then
+    /// let response = HttpResponse {
+    ///   status: 200,
+    ///   headers: Vec::new(),
+    ///   body: br#"{"version": "3.5.1", "tileset_last_modified": 0, "available_actions": []}"#.to_vec(),
+    /// };
+    /// let status: status::Response = valhalla.parse_response("status", response).unwrap();
+    /// assert_eq!(status.version.to_string(), "3.5.1");
+    /// ```
+    pub fn parse_response<Resp: DeserializeOwned>(
+        &self,
+        action: &str,
+        response: transport::HttpResponse,
+    ) -> Result<Resp, Error> {
+        let response = response.error_for_status()?;
+        let text = String::from_utf8_lossy(&response.body);
+        self.deserialize_body(action, response.status, &text)
+    }
+
+    /// Reads the response to a `route` request, like [`Self::route`]
+    ///
+    /// See [`Self::parse_response`].
+    pub fn parse_route_response(
+        &self,
+        response: transport::HttpResponse,
+    ) -> Result<route::Trip, Error> {
+        let response: route::Response = self.parse_response("route", response)?;
+        Ok(response.trip)
+    }
+    /// Reads the response to a `centroid` request, like [`Self::centroid`]
+    ///
+    /// See [`Self::parse_response`].
+    pub fn parse_centroid_response(
+        &self,
+        response: transport::HttpResponse,
+    ) -> Result<centroid::Response, Error> {
+        let response: centroid::RawResponse = self.parse_response("centroid", response)?;
+        Ok(response.into())
+    }
+    /// Reads the response to the matrix request `manifest`, like [`Self::matrix`]
+    ///
+    /// The manifest determines whether the response is verbose.
+    /// See [`Self::parse_response`].
+    pub fn parse_matrix_response(
+        &self,
+        manifest: &matrix::Manifest,
+        response: transport::HttpResponse,
+    ) -> Result<matrix::Response, Error> {
+        // deserializing the variant directly keeps the path of errors, which the untagged
+        // `matrix::Response` loses
+        if manifest.verbose == Some(false) {
+            self.parse_response("sources_to_targets", response)
+                .map(matrix::Response::Concise)
+        } else {
+            self.parse_response("sources_to_targets", response)
+                .map(matrix::Response::Verbose)
+        }
+    }
+
+    fn deserialize_body<Resp: DeserializeOwned>(
+        &self,
+        action: &str,
+        status: u16,
+        text: &str,
+    ) -> Result<Resp, Error> {
+        match &self.response_adapter {
+            None => deserialize::from_str(action, status, text).map_err(Error::Deserialize),
+            Some(adapter) => {
+                let value = serde_json::from_str(text).map_err(Error::Serde)?;
+                deserialize::from_value(action, status, &adapter(action, value))
+                    .map_err(Error::Deserialize)
+            }
+        }
+    }
+
+    /// `url` without the query parameter of [`Auth::Query`], for logging
+    fn url_without_credentials(&self, url: &url::Url) -> url::Url {
+        let Some(Auth::Query { name, .. }) = &self.auth else {
+            return url.clone();
+        };
+        let mut url = url.clone();
+        let pairs: Vec<(String, String)> = url
+            .query_pairs()
+            .filter(|(key, _)| key != name)
+            .map(|(key, value)| (key.into_owned(), value.into_owned()))
+            .collect();
+        url.set_query(None);
+        if !pairs.is_empty() {
+            url.query_pairs_mut().extend_pairs(pairs);
+        }
+        url
+    }
+    /// `text` with the credentials of [`Auth::Query`] replaced by `<redacted>`
+    ///
+    /// The other kinds of [`Auth`] are sent as headers, which do not end up in error messages.
+    fn redact(&self, text: &str) -> String {
+        match &self.auth {
+            Some(Auth::Query { value, .. }) if !value.is_empty() => {
+                let encoded: String =
+                    url::form_urlencoded::byte_serialize(value.as_bytes()).collect();
+                text.replace(value.as_str(), "<redacted>")
+                    .replace(&encoded, "<redacted>")
+            }
+            _ => text.to_string(),
+        }
+    }
+
+    fn build_request(&self, action: &str, body: String) -> transport::HttpRequest {
         let mut url = self.base_url.clone();
-        url.path_segments_mut()
-            .expect("base_url is not a valid base url")
-            .push("status");
-        let response = self
-            .client
-            .post(url)
-            .json(&manifest)
-            .send()
-            .map_err(Error::Reqwest)?;
-        if response.status().is_client_error() {
-            return Err(Error::RemoteError(response.json().map_err(Error::Reqwest)?));
-        }
-        response.error_for_status_ref().map_err(Error::Reqwest)?;
-        let text = response.text().map_err(Error::Reqwest)?;
-        let response: status::Response = serde_json::from_str(&text).map_err(Error::Serde)?;
-        Ok(response)
+        {
+            let mut segments = url
+                .path_segments_mut()
+                .expect("base_url is not a valid base url");
+            // the empty segment after a trailing slash
+            segments.pop_if_empty();
+            match self.action_paths.get(action) {
+                None => segments.push(action),
+                Some(path) => segments.extend(path.split('/').filter(|s| !s.is_empty())),
+            };
+        }
+        let body = match self.request_method {
+            RequestMethod::Post => Some(body.into_bytes()),
+            RequestMethod::Get => {
+                url.query_pairs_mut().append_pair("json", &body);
+                None
+            }
+        };
+        let mut headers = Vec::new();
+        match &self.auth {
+            None => {}
+            Some(Auth::Bearer(token)) => {
+                headers.push(("Authorization".to_string(), format!("Bearer {token}")));
+            }
+            Some(Auth::Header { name, value }) => headers.push((name.clone(), value.clone())),
+            Some(Auth::Query { name, value }) => {
+                url.query_pairs_mut().append_pair(name, value);
+            }
+        }
+        transport::HttpRequest {
+            method: self.request_method,
+            url,
+            headers,
+            body,
+            timeout: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(any(feature = "reqwest", feature = "ureq"))]
+    #[test]
+    fn response_adapter() {
+        let valhalla = Valhalla::default().response_adapter(|action, mut response| {
+            assert_eq!(action, "status");
+            let mut status = response["result"].take();
+            status["available_actions"] = response["actions"].take();
+            status
+        });
+        let status: status::Response = valhalla
+            .deserialize_body(
+                "status",
+                200,
+                r#"{"result": {"version": "3.5.1", "tileset_last_modified": 0}, "actions": ["route"]}"#,
+            )
+            .unwrap();
+        assert_eq!(status.available_actions.len(), 1);
+    }
+
+    #[test]
+    fn remote_error() {
+        let error: RemoteError = serde_json::from_value(serde_json::json!({
+            "error_code": 171,
+            "error": "No suitable edges near location",
+            "status_code": 400,
+            "status": "Bad Request"
+        }))
+        .unwrap();
+        assert!(error.is_no_segment());
+        assert!(!error.is_invalid_input());
+        assert_eq!(
+            error_chain(&Error::RemoteError(error)),
+            "the server returned an error: No suitable edges near location (error code 171, HTTP 400 Bad Request)"
+        );
+        assert!(ErrorCode(154).is_invalid_input());
+        assert!(!ErrorCode::NO_PATH.is_invalid_input());
+    }
+
+    #[test]
+    fn deserialize_error() {
+        let (valhalla, _) = with_canned(200, r#"{"version": 3, "available_actions": []}"#);
+        let error = valhalla.status(Default::default()).err().unwrap();
+        let Error::Deserialize(e) = &error else {
+            panic!("unexpected error {error:?}");
+        };
+        assert_eq!(
+            (e.action.as_str(), e.status, e.path.as_str()),
+            ("status", 200, "version")
+        );
+        assert_eq!(
+            error.to_string(),
+            "cannot parse the status response (HTTP 200)"
+        );
+        assert!(error_chain(&error)
+            .starts_with("cannot parse the status response (HTTP 200): invalid type: integer `3`"));
+        assert_eq!(error.status(), Some(200));
+        let source = std::error::Error::source(&error).unwrap();
+        assert!(source.is::<DeserializeError>());
+        assert!(source.source().unwrap().is::<serde_json::Error>());
+    }
+
+    #[test]
+    fn matrix_error_path() {
+        let body = r#"{"algorithm": "costmatrix", "units": "kilometers", "sources": [], "targets": [], "sources_to_targets": [[{"from_index": 0, "to_index": 0, "time": 60, "distance": "far"}]]}"#;
+        let (valhalla, _) = with_canned(200, body);
+        let location = matrix::Location::new(4.9, 52.4);
+        let manifest = matrix::Manifest::builder().sources_to_targets([location], [location]);
+        let error = valhalla.matrix(manifest.clone()).err().unwrap();
+        let Error::Deserialize(e) = &error else {
+            panic!("unexpected error {error:?}");
+        };
+        assert_eq!(e.path, "sources_to_targets[0][0].distance");
+        let response = transport::HttpResponse {
+            status: 200,
+            headers: Vec::new(),
+            body: body.as_bytes().to_vec(),
+        };
+        let error = valhalla
+            .parse_matrix_response(&manifest, response)
+            .err()
+            .unwrap();
+        assert!(
+            matches!(error, Error::Deserialize(e) if e.path == "sources_to_targets[0][0].distance")
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "max_concurrent_requests must be positive")]
+    fn max_concurrent_requests_zero() {
+        let (valhalla, _) = with_canned(200, "{}");
+        let _ = valhalla.max_concurrent_requests(0);
+    }
+
+    #[test]
+    #[should_panic(expected = "requests_per_second must be positive, not NaN")]
+    fn rate_limit_nan() {
+        let (valhalla, _) = with_canned(200, "{}");
+        let _ = valhalla.rate_limit(f64::NAN, 1);
+    }
+
+    #[test]
+    fn rate_limits() {
+        use std::time::Duration;
+        let body = r#"{"version": "3.5.1", "tileset_last_modified": 0, "available_actions": []}"#;
+        let headers = [
+            ("X-RateLimit-Limit", "100"),
+            ("x-ratelimit-remaining", " 7"),
+            ("X-RateLimit-Reset", "30"),
+        ];
+        let (valhalla, _) = with_canned_headers(200, &headers, body);
+        assert_eq!(valhalla.last_rate_limit(), None);
+        valhalla.status(Default::default()).unwrap();
+        assert_eq!(
+            valhalla.last_rate_limit(),
+            Some(transport::RateLimit {
+                limit: Some(100),
+                remaining: Some(7),
+                reset: Some(Duration::from_secs(30)),
+                retry_after: None,
+            })
+        );
+
+        let (valhalla, transport) =
+            with_canned_headers(429, &[("Retry-After", "0")], "Too Many Requests");
+        let error = valhalla
+            .retries(1, Duration::ZERO)
+            .status(Default::default())
+            .unwrap_err();
+        assert_eq!(error.retry_after(), Some(Duration::ZERO));
+        assert_eq!(
+            error.to_string(),
+            "rate limited by the server, retry after 0ns"
+        );
+        assert_eq!(transport.requests.lock().unwrap().len(), 2);
+
+        // points in time which already passed
+        let response = transport::HttpResponse {
+            status: 429,
+            headers: vec![
+                ("RateLimit-Reset".into(), "1000000001".into()),
+                ("Retry-After".into(), "Wed, 21 Oct 2015 07:28:00 GMT".into()),
+            ],
+            body: Vec::new(),
+        };
+        let rate_limit = response.rate_limit().unwrap();
+        assert_eq!(rate_limit.reset, Some(Duration::ZERO));
+        assert_eq!(rate_limit.retry_after, Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn validation() {
+        let (valhalla, transport) = with_canned(200, "{}");
+        let manifest = route::Manifest::default()
+            .locations([route::Location::new(4.9, 52.4)])
+            .costing(costing::Costing::Truck(
+                costing::TruckCostingOptions::builder().top_speed(300.0),
+            ));
+        let error = valhalla.route(manifest).err().unwrap();
+        assert!(error.is_client_bug());
+        assert_eq!(
+            error_chain(&error),
+            "invalid request: locations has 1 locations, but needs at least 2; top_speed is 300, but must be between 10 and 252"
+        );
+        let error = valhalla
+            .elevation(elevation::Manifest::builder())
+            .err()
+            .unwrap();
+        let Error::Validation(e) = error else {
+            panic!("unexpected error {error:?}");
+        };
+        assert_eq!(
+            e.violations,
+            [validation::Violation::Missing("shape", "encoded_polyline")]
+        );
+        let grid = heatmap::Manifest::new(
+            matrix::Location::new(4.9, 52.4),
+            heatmap::BoundingBox::new((4.8, 52.3), (5.0, 52.5)),
+            2,
+            2,
+        )
+        .max_locations_per_request(0);
+        let error = valhalla
+            .travel_time_grid(grid, &Default::default())
+            .err()
+            .unwrap();
+        assert_eq!(
+            error_chain(&error),
+            "invalid request: max_locations_per_request has 0 locations, but needs at least 1"
+        );
+        let route = route::Manifest::default().locations([
+            route::Location::new(4.9, 52.4),
+            route::Location::new(5.1, 52.1),
+        ]);
+        let options = route::DiversityOptions::new(2).min_distinct_fraction(1.5);
+        let error = valhalla.diverse_routes(route, options).err().unwrap();
+        assert_eq!(
+            error_chain(&error),
+            "invalid request: min_distinct_fraction is 1.5, but must be between 0 and 1"
+        );
+        assert!(transport.requests.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn service_limits() {
+        let (valhalla, transport) = with_canned(200, "{}");
+        let valhalla = valhalla.service_limits(validation::ServiceLimits {
+            max_locations: Some(2),
+            max_distance: Some(500_000.0),
+            max_matrix_location_pairs: Some(3),
+            max_matrix_distance: Some(500_000.0),
+            ..Default::default()
+        });
+        let amsterdam = route::Location::new(4.9, 52.37);
+        let munich = route::Location::new(11.58, 48.14);
+        let manifest = route::Manifest::default().locations([amsterdam.clone(), munich, amsterdam]);
+        let error = valhalla.route(manifest).err().unwrap();
+        assert_eq!(
+            error_chain(&error),
+            "invalid request: 3 exceeds the max_locations of 2; 1335784 exceeds the max_distance of 500000"
+        );
+        let (amsterdam, munich) = (
+            matrix::Location::new(4.9, 52.37),
+            matrix::Location::new(11.58, 48.14),
+        );
+        let manifest = matrix::Manifest::builder()
+            .sources_to_targets([amsterdam, munich], [amsterdam, munich]);
+        let error = valhalla.matrix(manifest.clone()).err().unwrap();
+        let Error::Validation(e) = error else {
+            panic!("unexpected error {error:?}");
+        };
+        assert_eq!(e.violations.len(), 2);
+        assert!(manifest.validate().is_ok());
+        assert!(transport.requests.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn no_route() {
+        let (valhalla, _) = with_canned(
+            400,
+            r#"{"error_code": 442, "error": "No path could be found for input", "status_code": 400, "status": "Bad Request"}"#,
+        );
+        let error = valhalla.status(Default::default()).err().unwrap();
+        assert!(error.is_no_route());
+        assert!(!error.is_no_segment() && !error.is_client_bug() && !error.is_retryable());
+        assert!(!Error::HttpStatus(400).is_no_route());
+        assert_eq!(error.status(), Some(400));
+        let source = std::error::Error::source(&error).unwrap();
+        assert!(source
+            .downcast_ref::<RemoteError>()
+            .is_some_and(RemoteError::is_no_route));
+    }
+
+    #[test]
+    fn lenient_date_time_parsing() {
+        let expected = chrono::NaiveDate::from_ymd_opt(2024, 11, 7)
+            .unwrap()
+            .and_hms_opt(15, 26, 0)
+            .unwrap();
+        for value in [
+            "2024-11-07T15:26",
+            "2024-11-07T15:26:00",
+            "2024-11-07T15:26:00.000",
+            "2024-11-07T15:26+01:00",
+            "2024-11-07T15:26:00-05:30",
+            "2024-11-07T15:26Z",
+        ] {
+            assert_eq!(
+                parse_lenient_naive_date_time(value),
+                Ok(expected),
+                "{value}"
+            );
+        }
+        assert!(parse_lenient_naive_date_time("2024-11-07").is_err());
+        assert!(parse_lenient_naive_date_time("2024-11-07T15:26+0100").is_err());
+    }
+
+    #[cfg(any(feature = "reqwest", feature = "ureq"))]
+    #[test]
+    fn get_requests_carry_the_manifest_in_the_query() {
+        let valhalla = Valhalla::new(url::Url::parse("http://localhost:8002/").unwrap())
+            .request_method(RequestMethod::Get);
+        let request = valhalla.build_request("status", r#"{"verbose":true}"#.to_string());
+        assert_eq!(request.method, RequestMethod::Get);
+        assert_eq!(
+            request.url.as_str(),
+            "http://localhost:8002/status?json=%7B%22verbose%22%3Atrue%7D"
+        );
+        assert!(request.body.is_none());
+
+        let request = valhalla
+            .request_method(RequestMethod::Post)
+            .build_request("status", r#"{"verbose":true}"#.to_string());
+        assert_eq!(request.method, RequestMethod::Post);
+        assert_eq!(request.url.as_str(), "http://localhost:8002/status");
+        assert_eq!(
+            request.body.as_deref(),
+            Some(br#"{"verbose":true}"#.as_slice())
+        );
+    }
+
+    #[cfg(any(feature = "reqwest", feature = "ureq"))]
+    #[test]
+    fn base_urls_with_paths() {
+        for base_url in [
+            "http://localhost:8002",
+            "http://localhost:8002/",
+            "http://localhost:8002/valhalla",
+            "http://localhost:8002/valhalla/",
+        ] {
+            let valhalla = Valhalla::new(url::Url::parse(base_url).unwrap());
+            let request = valhalla.build_request("route", "{}".to_string());
+            let expected = format!("{}/route", base_url.trim_end_matches('/'));
+            assert_eq!(request.url.as_str(), expected);
+        }
+    }
+
+    #[cfg(any(feature = "reqwest", feature = "ureq"))]
+    #[test]
+    fn action_paths() {
+        let valhalla = Valhalla::new(url::Url::parse("http://localhost:8002/").unwrap())
+            .action_path("route", "/valhalla/v1/route/");
+        let request = valhalla.build_request("route", "{}".to_string());
+        assert_eq!(
+            request.url.as_str(),
+            "http://localhost:8002/valhalla/v1/route"
+        );
+        let request = valhalla.build_request("status", "{}".to_string());
+        assert_eq!(request.url.as_str(), "http://localhost:8002/status");
+    }
+
+    /// Answers every request with `status` and `body`, remembering the requests
+    struct Canned {
+        status: u16,
+        headers: Vec<(String, String)>,
+        body: &'static str,
+        requests: std::sync::Mutex<Vec<transport::HttpRequest>>,
+    }
+    impl transport::HttpTransport for Arc<Canned> {
+        fn send(&self, request: transport::HttpRequest) -> Result<transport::HttpResponse, Error> {
+            self.requests.lock().unwrap().push(request);
+            Ok(transport::HttpResponse {
+                status: self.status,
+                headers: self.headers.clone(),
+                body: self.body.as_bytes().to_vec(),
+            })
+        }
+    }
+    fn with_canned(status: u16, body: &'static str) -> (Valhalla, Arc<Canned>) {
+        with_canned_headers(status, &[], body)
+    }
+    fn with_canned_headers(
+        status: u16,
+        headers: &[(&str, &str)],
+        body: &'static str,
+    ) -> (Valhalla, Arc<Canned>) {
+        let canned = Arc::new(Canned {
+            status,
+            headers: headers
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+            body,
+            requests: Default::default(),
+        });
+        let url = url::Url::parse("http://localhost:8002/").unwrap();
+        (Valhalla::with_transport(canned.clone(), url), canned)
+    }
+
+    #[test]
+    fn chunk_costing() {
+        let body = r#"{"algorithm": "costmatrix", "units": "kilometers", "warnings": [], "sources_to_targets": {"durations": [[60]], "distances": [[1.0]]}}"#;
+        let (valhalla, transport) = with_canned(200, body);
+        let locations = [
+            matrix::Location::new(4.9, 52.4),
+            matrix::Location::new(11.6, 48.1),
+        ];
+        let manifest =
+            matrix::Manifest::builder().sources_to_targets(locations, locations[..1].to_vec());
+        let control = batch::Control::builder().chunk_costing(|chunk| {
+            let east = chunk.sources().iter().all(|s| s.coordinate().0 > 10.0);
+            east.then(|| costing::Costing::Pedestrian(Default::default()))
+        });
+        let matrix = valhalla.matrix_chunked(manifest, 1, 1, &control).unwrap();
+        assert_eq!(
+            matrix.sources_to_targets.durations,
+            [[Some(60)], [Some(60)]]
+        );
+        let costings: Vec<_> = transport
+            .requests
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|r| {
+                serde_json::from_slice::<serde_json::Value>(r.body.as_deref().unwrap()).unwrap()
+                    ["costing"]
+                    .clone()
+            })
+            .collect();
+        assert_eq!(costings, ["auto", "pedestrian"]);
+    }
+
+    #[test]
+    fn dry_run() {
+        let (valhalla, transport) = with_canned(500, "");
+        let valhalla = valhalla
+            .request_method(RequestMethod::Get)
+            .auth(Auth::Query {
+                name: "api_key".into(),
+                value: "secret".into(),
+            });
+        let request = valhalla
+            .dry_run("status", &status::Manifest::builder().verbose_output(true))
+            .unwrap();
+        assert_eq!(request.method, RequestMethod::Get);
+        assert_eq!(
+            request.url.as_str(),
+            "http://localhost:8002/status?json=%7B%22verbose%22%3Atrue%7D&api_key=secret"
+        );
+        assert_eq!(
+            valhalla.url_without_credentials(&request.url).as_str(),
+            "http://localhost:8002/status?json=%7B%22verbose%22%3Atrue%7D"
+        );
+        assert!(transport.requests.lock().unwrap().is_empty());
+    }
+
+    /// Fails every request with the body of the request as error message
+    struct Echo;
+    impl transport::HttpTransport for Echo {
+        fn send(&self, request: transport::HttpRequest) -> Result<transport::HttpResponse, Error> {
+            let body = serde_json::json!({
+                "error_code": 171,
+                "error": String::from_utf8(request.body.unwrap_or_default()).unwrap(),
+                "status_code": 400,
+                "status": "Bad Request"
+            });
+            Ok(transport::HttpResponse {
+                status: 400,
+                headers: Vec::new(),
+                body: body.to_string().into_bytes(),
+            })
+        }
+    }
+
+    #[test]
+    fn route_many_keeps_the_input_order() {
+        let url = url::Url::parse("http://localhost:8002/").unwrap();
+        let valhalla = Valhalla::with_transport(Echo, url);
+        let manifests = (0..20).map(|i| {
+            route::ManifestBuilder::default()
+                .locations([
+                    route::Location::new(0.0, 0.0),
+                    route::Location::new(1.0, 1.0),
+                ])
+                .costing(costing::Costing::default())
+                .id(i)
+        });
+        let results = valhalla.route_many(manifests, 4);
+        assert_eq!(results.len(), 20);
+        for (i, result) in results.into_iter().enumerate() {
+            let Err(Error::RemoteError(e)) = result else {
+                panic!("expected the echoed request, got {result:?}");
+            };
+            assert!(e.error.contains(&format!(r#""id":"{i}""#)), "{}", e.error);
+        }
+        assert!(valhalla.route_many([], 4).is_empty());
+    }
+
+    #[test]
+    fn custom_transport() {
+        let (valhalla, transport) = with_canned(
+            200,
+            r#"{"version": "3.5.1", "tileset_last_modified": 0, "available_actions": ["route"]}"#,
+        );
+        let status = valhalla.status(status::Manifest::builder()).unwrap();
+        assert_eq!(status.available_actions.len(), 1);
+        let requests = transport.requests.lock().unwrap();
+        assert_eq!(requests[0].url.as_str(), "http://localhost:8002/status");
+        assert_eq!(requests[0].body.as_deref(), Some(b"{}".as_slice()));
+
+        let (valhalla, _) = with_canned(
+            400,
+            r#"{"error_code": 171, "error": "No suitable edges near location", "status_code": 400, "status": "Bad Request"}"#,
+        );
+        let error = valhalla.status(status::Manifest::builder()).unwrap_err();
+        assert!(matches!(error, Error::RemoteError(e) if e.is_no_segment()));
+
+        let (valhalla, _) = with_canned(502, "<html>Bad Gateway</html>");
+        let error = valhalla.status(status::Manifest::builder()).unwrap_err();
+        assert!(matches!(error, Error::HttpStatus(502)));
+    }
+
+    #[test]
+    fn redirects() {
+        /// Redirects `/status` to `location` with `status`, answering anything else
+        struct Redirecting {
+            status: u16,
+            location: &'static str,
+            requests: std::sync::Mutex<Vec<transport::HttpRequest>>,
+        }
+        impl transport::HttpTransport for Arc<Redirecting> {
+            fn send(
+                &self,
+                request: transport::HttpRequest,
+            ) -> Result<transport::HttpResponse, Error> {
+                let redirect = request.url.path() == "/status";
+                self.requests.lock().unwrap().push(request);
+                Ok(match redirect {
+                    true => transport::HttpResponse {
+                        status: self.status,
+                        headers: vec![("Location".to_string(), self.location.to_string())],
+                        body: Vec::new(),
+                    },
+                    false => transport::HttpResponse {
+                        status: 200,
+                        headers: Vec::new(),
+                        body: br#"{"version": "3.5.1", "tileset_last_modified": 0, "available_actions": []}"#.to_vec(),
+                    },
+                })
+            }
+        }
+        let redirecting = |status, location| {
+            let transport = Arc::new(Redirecting {
+                status,
+                location,
+                requests: Default::default(),
+            });
+            let url = url::Url::parse("http://localhost:8002/").unwrap();
+            let valhalla = Valhalla::with_transport(transport.clone(), url)
+                .auth(Auth::Bearer("secret".to_string()));
+            (valhalla, transport)
+        };
+
+        let (valhalla, transport) = redirecting(308, "/v2/status");
+        valhalla.status(status::Manifest::builder()).unwrap();
+        let requests = transport.requests.lock().unwrap();
+        assert_eq!(requests[1].url.as_str(), "http://localhost:8002/v2/status");
+        assert_eq!(
+            (requests[1].method, &requests[1].body, &requests[1].headers),
+            (RequestMethod::Post, &requests[0].body, &requests[0].headers)
+        );
+
+        let (valhalla, transport) = redirecting(307, "https://example.com/v2/status");
+        valhalla.status(status::Manifest::builder()).unwrap();
+        let requests = transport.requests.lock().unwrap();
+        assert_eq!(requests[1].url.as_str(), "https://example.com/v2/status");
+        assert!(requests[1].headers.is_empty());
+
+        let (valhalla, transport) = redirecting(301, "/v2/status");
+        let error = valhalla.status(status::Manifest::builder()).unwrap_err();
+        assert!(matches!(error, Error::HttpStatus(301)));
+        assert!(error.to_string().contains("not followed for POST requests"));
+        assert_eq!(transport.requests.lock().unwrap().len(), 1);
+
+        let (valhalla, transport) = redirecting(302, "/v2/status");
+        let valhalla = valhalla.request_method(RequestMethod::Get);
+        valhalla.status(status::Manifest::builder()).unwrap();
+        let requests = transport.requests.lock().unwrap();
+        assert_eq!(requests[1].method, RequestMethod::Get);
+        assert_eq!(requests[1].url.path(), "/v2/status");
+
+        let (valhalla, transport) = redirecting(307, "/status");
+        let error = valhalla.status(status::Manifest::builder()).unwrap_err();
+        assert!(matches!(error, Error::HttpStatus(307)));
+        assert_eq!(transport.requests.lock().unwrap().len(), MAX_REDIRECTS + 1);
+    }
+
+    #[test]
+    fn retries() {
+        let (valhalla, transport) = with_canned(503, "<html>Service Unavailable</html>");
+        let error = valhalla
+            .retries(2, std::time::Duration::from_millis(1))
+            .status(status::Manifest::builder())
+            .unwrap_err();
+        assert!(matches!(error, Error::HttpStatus(503)));
+        assert!(error.is_retryable() && error.is_server_error() && !error.is_client_error());
+        assert!(error.is_server_side());
+        assert!(!error.is_client_bug());
+        assert_eq!(transport.requests.lock().unwrap().len(), 3);
+
+        let (valhalla, transport) = with_canned(
+            400,
+            r#"{"error_code": 154, "error": "Path distance exceeds the max distance limit", "status_code": 400, "status": "Bad Request"}"#,
+        );
+        let error = valhalla
+            .retries(2, std::time::Duration::ZERO)
+            .status(status::Manifest::builder())
+            .unwrap_err();
+        assert!(!error.is_retryable() && !error.is_server_error() && error.is_client_error());
+        assert!(error.is_client_bug());
+        assert_eq!(transport.requests.lock().unwrap().len(), 1);
+
+        let (valhalla, _) = with_canned(429, "Too Many Requests");
+        let error = valhalla.status(status::Manifest::builder()).unwrap_err();
+        assert!(matches!(error, Error::RateLimited(_)));
+        assert!(error.is_retryable() && error.is_client_error() && !error.is_client_bug());
+        assert!(!Error::DeadlineExceeded.is_client_error());
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn compressed_requests() {
+        use std::io::Read;
+        let body = r#"{"version": "3.5.1", "tileset_last_modified": 0, "available_actions": []}"#;
+        let (valhalla, transport) = with_canned(200, body);
+        let valhalla = valhalla.compress_requests_over(10);
+        valhalla.status(status::Manifest::builder()).unwrap();
+        valhalla
+            .status(status::Manifest::builder().verbose_output(true))
+            .unwrap();
+        let requests = transport.requests.lock().unwrap();
+        assert!(requests[0].headers.is_empty());
+        assert_eq!(
+            requests[1].headers,
+            [("Content-Encoding".to_string(), "gzip".to_string())]
+        );
+        let compressed = requests[1].body.as_deref().unwrap();
+        let mut decompressed = String::new();
+        flate2::read::GzDecoder::new(compressed)
+            .read_to_string(&mut decompressed)
+            .unwrap();
+        assert_eq!(decompressed, r#"{"verbose":true}"#);
+    }
+
+    #[test]
+    fn capture() {
+        let directory =
+            std::env::temp_dir().join(format!("valhalla-capture-{}", std::process::id()));
+        let (valhalla, _) = with_canned(
+            400,
+            r#"{"error_code": 171, "error": "No suitable edges near location", "status_code": 400, "status": "Bad Request"}"#,
+        );
+        let valhalla = valhalla
+            .auth(Auth::Query {
+                name: "api_key".into(),
+                value: "secret".into(),
+            })
+            .request_method(RequestMethod::Get)
+            .capture(&directory);
+        let _ = valhalla.send_raw("locate", serde_json::json!({"costing": "auto"}));
+        let files: Vec<_> = std::fs::read_dir(&directory)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect();
+        let text = std::fs::read_to_string(&files[0]).unwrap();
+        std::fs::remove_dir_all(&directory).unwrap();
+        assert_eq!(files.len(), 1);
+        assert!(files[0].to_str().unwrap().ends_with("-000000-locate.json"));
+        assert!(!text.contains("secret"));
+        let mut capture: serde_json::Value = serde_json::from_str(&text).unwrap();
+        assert!(capture["latency_ms"].take().is_f64());
+        assert_eq!(
+            capture,
+            serde_json::json!({
+                "action": "locate",
+                "request": {"costing": "auto"},
+                "status": 400,
+                "response": {"error_code": 171, "error": "No suitable edges near location", "status_code": 400, "status": "Bad Request"},
+                "latency_ms": null,
+            })
+        );
+
+        let valhalla = Valhalla::with_transport(Unreachable, valhalla.base_url.clone())
+            .auth(Auth::Query {
+                name: "api_key".into(),
+                value: "s3cret/key".into(),
+            })
+            .capture(&directory);
+        let _ = valhalla.status(Default::default());
+        let files: Vec<_> = std::fs::read_dir(&directory)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect();
+        let text = std::fs::read_to_string(&files[0]).unwrap();
+        std::fs::remove_dir_all(&directory).unwrap();
+        let capture: serde_json::Value = serde_json::from_str(&text).unwrap();
+        assert_eq!(
+            capture["error"],
+            "io error: cannot connect to http://localhost:8002/status?api_key=<redacted>"
+        );
+    }
+
+    /// Fails every request like a transport which cannot reach the server
+    struct Unreachable;
+    impl transport::HttpTransport for Unreachable {
+        fn send(&self, request: transport::HttpRequest) -> Result<transport::HttpResponse, Error> {
+            let message = format!("cannot connect to {}", request.url);
+            Err(Error::Io(std::io::Error::other(message)))
+        }
+    }
+
+    #[test]
+    #[cfg(any(feature = "reqwest", feature = "ureq"))]
+    fn shared() {
+        let body = r#"{"version": "3.5.1", "tileset_last_modified": 0, "available_actions": []}"#;
+        let (valhalla, transport) = with_canned(200, body);
+        assert!(Valhalla::set_shared(valhalla).is_ok());
+        Valhalla::shared().status(Default::default()).unwrap();
+        assert_eq!(transport.requests.lock().unwrap().len(), 1);
+        assert!(Valhalla::set_shared(Valhalla::default()).is_err());
+    }
+
+    #[cfg(any(feature = "reqwest", feature = "ureq"))]
+    #[test]
+    fn from_env() {
+        let vars = |vars: &'static [(&'static str, &'static str)]| {
+            move |name: &str| {
+                vars.iter()
+                    .find(|(n, _)| *n == name)
+                    .map(|(_, value)| value.to_string())
+            }
+        };
+        let valhalla = Valhalla::from_vars(vars(&[
+            ("VALHALLA_URL", "https://valhalla.internal/api"),
+            ("VALHALLA_API_KEY", "secret"),
+            ("VALHALLA_API_KEY_HEADER", "x-api-key"),
+            ("VALHALLA_TIMEOUT_SECS", "2.5"),
+            ("VALHALLA_RETRIES", "3"),
+            ("VALHALLA_RETRY_BACKOFF_MS", ""),
+        ]))
+        .unwrap();
+        assert_eq!(valhalla.base_url.as_str(), "https://valhalla.internal/api");
+        assert_eq!(
+            valhalla.auth,
+            Some(Auth::Header {
+                name: "x-api-key".into(),
+                value: "secret".into()
+            })
+        );
+        assert_eq!(valhalla.retries, 3);
+        assert_eq!(
+            valhalla.retry_backoff,
+            std::time::Duration::from_millis(200)
+        );
+
+        let valhalla = Valhalla::from_vars(vars(&[])).unwrap();
+        assert_eq!(valhalla.base_url.as_str(), VALHALLA_PUBLIC_API_URL);
+        assert_eq!(valhalla.auth, None);
+        assert_eq!(valhalla.retries, 0);
+
+        let error = Valhalla::from_vars(vars(&[("VALHALLA_RETRIES", "many")]))
+            .err()
+            .unwrap();
+        assert_eq!(
+            error.to_string(),
+            r#"invalid configuration: VALHALLA_RETRIES="many": invalid digit found in string"#
+        );
+        let error = Valhalla::from_vars(vars(&[("VALHALLA_TIMEOUT_SECS", "-1")]))
+            .err()
+            .unwrap();
+        assert!(matches!(error, Error::Config(_)));
+        let error = Valhalla::from_vars(vars(&[("VALHALLA_URL", "localhost:8002")]))
+            .err()
+            .unwrap();
+        assert_eq!(
+            error.to_string(),
+            "invalid configuration: base_url localhost:8002 is not an http or https url"
+        );
+    }
+
+    #[test]
+    fn send_raw() {
+        let (valhalla, transport) = with_canned(200, r#"[{"input_lat": 52.3676}]"#);
+        let response = valhalla
+            .auth(Auth::Bearer("secret".into()))
+            .send_raw("locate", serde_json::json!({"costing": "auto"}))
+            .unwrap();
+        assert_eq!(response[0]["input_lat"], 52.3676);
+        let requests = transport.requests.lock().unwrap();
+        assert_eq!(requests[0].url.as_str(), "http://localhost:8002/locate");
+        assert_eq!(
+            requests[0].body.as_deref(),
+            Some(br#"{"costing":"auto"}"#.as_slice())
+        );
+        assert_eq!(requests[0].headers.len(), 1);
+    }
+
+    #[test]
+    fn auth() {
+        let body = r#"{"version": "3.5.1", "tileset_last_modified": 0, "available_actions": []}"#;
+        let (valhalla, transport) = with_canned(200, body);
+        let valhalla = valhalla.auth(Auth::Bearer("secret".into()));
+        let tenant = valhalla.with_auth(Auth::Query {
+            name: "api_key".into(),
+            value: "tenant secret".into(),
+        });
+        valhalla.status(status::Manifest::builder()).unwrap();
+        tenant.status(status::Manifest::builder()).unwrap();
+        let requests = transport.requests.lock().unwrap();
+        assert_eq!(
+            requests[0].headers,
+            [("Authorization".to_string(), "Bearer secret".to_string())]
+        );
+        assert!(requests[1].headers.is_empty());
+        assert_eq!(
+            requests[1].url.as_str(),
+            "http://localhost:8002/status?api_key=tenant+secret"
+        );
+        for request in requests.iter() {
+            assert!(!format!("{request:?}").contains("secret"), "{request:?}");
+            assert!(!request.fingerprint().contains("secret"));
+        }
+        assert!(format!("{:?}", requests[1]).contains("api_key=<redacted>"));
+        let other = valhalla
+            .with_auth(Auth::Bearer("other secret".into()))
+            .build_request("status", "{}".to_string());
+        assert_eq!(format!("{other:?}"), format!("{:?}", requests[0]));
+        assert_ne!(other.fingerprint(), requests[0].fingerprint());
+
+        let debug = format!(
+            "{:?}",
+            Auth::Header {
+                name: "x-api-key".into(),
+                value: "secret".into(),
+            }
+        );
+        assert_eq!(debug, "Header(x-api-key: <redacted>)");
+    }
+
+    #[test]
+    fn deadline_and_cancellation() {
+        let body = r#"{"version": "3.5.1", "tileset_last_modified": 0, "available_actions": []}"#;
+        let (valhalla, transport) = with_canned(200, body);
+
+        let expired = valhalla.with_deadline(Instant::now());
+        let error = expired.status(status::Manifest::builder()).unwrap_err();
+        assert!(matches!(error, Error::DeadlineExceeded));
+
+        let token = batch::CancellationToken::new();
+        let cancellable = valhalla.with_cancellation_token(token.clone());
+        token.cancel();
+        let error = cancellable.status(status::Manifest::builder()).unwrap_err();
+        assert!(matches!(error, Error::Cancelled));
+        assert!(transport.requests.lock().unwrap().is_empty());
+
+        let deadline = Instant::now() + std::time::Duration::from_secs(60);
+        let bounded = valhalla.with_deadline(deadline);
+        bounded.status(status::Manifest::builder()).unwrap();
+        let timeout = transport.requests.lock().unwrap()[0].timeout.unwrap();
+        assert!(timeout <= std::time::Duration::from_secs(60));
+    }
+}
+
+#[cfg(all(test, feature = "schemars"))]
+mod schema_tests {
+    use super::*;
+
+    fn assert_conforms<T: Serialize + schemars::JsonSchema>(manifest: &T) {
+        let schema = serde_json::to_value(schemars::schema_for!(T)).unwrap();
+        let instance = serde_json::to_value(manifest).unwrap();
+        let validator = jsonschema::validator_for(&schema).unwrap();
+        let errors: Vec<String> = validator
+            .iter_errors(&instance)
+            .map(|e| format!("{} at {}", e, e.instance_path()))
+            .collect();
+        assert!(errors.is_empty(), "{instance} does not conform: {errors:?}");
+    }
+
+    #[test]
+    fn manifests_conform_to_their_schema() {
+        let amsterdam = route::Location::new(4.9041, 52.3676);
+        let utrecht = route::Location::new(5.1214, 52.0907);
+        assert_conforms(
+            &route::ManifestBuilder::default()
+                .locations([amsterdam.clone(), utrecht.clone()])
+                .costing(costing::Costing::Bicycle(Default::default()))
+                .exclude_polygon([(4.99, 52.25), (4.84, 52.24), (4.85, 52.18)]),
+        );
+        assert_conforms(
+            &centroid::Manifest::builder()
+                .locations([amsterdam, utrecht])
+                .units(Units::Imperial),
+        );
+        let departure = chrono::NaiveDate::from_ymd_opt(2024, 11, 7)
+            .unwrap()
+            .and_hms_opt(15, 26, 0)
+            .unwrap();
+        assert_conforms(
+            &matrix::Manifest::builder()
+                .sources_to_targets(
+                    [matrix::Location::new(4.9041, 52.3676).date_time(departure)],
+                    [matrix::Location::new(5.1214, 52.0907)],
+                )
+                .date_time(matrix::DateTime::from_departure_time(departure))
+                .costing(costing::Costing::Truck(Default::default())),
+        );
+        assert_conforms(
+            &elevation::Manifest::builder()
+                .shape([(4.9041, 52.3676), (5.1214, 52.0907)])
+                .height_precision(elevation::HeightPrecision::TwoDecimalPlaces)
+                .include_range(),
+        );
+        assert_conforms(&status::Manifest::builder().verbose_output(true));
     }
 }
@@ -4,7 +4,8 @@ use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
 #[serde_with::skip_serializing_none]
-#[derive(Serialize, Default, Debug)]
+#[derive(Serialize, Default, Debug, Clone)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct Manifest {
     pub(crate) targets: Vec<Location>,
     pub(crate) sources: Vec<Location>,
@@ -13,13 +14,48 @@ pub struct Manifest {
     id: Option<String>,
     matrix_locations: Option<u32>,
     date_time: Option<DateTime>,
-    verbose: Option<bool>,
+    pub(crate) verbose: Option<bool>,
     shape_format: Option<ShapeFormat>,
 }
 impl Manifest {
     pub fn builder() -> Self {
         Default::default()
     }
+    /// Checks that there is at least one source and one target and that the costing options are
+    /// in range
+    ///
+    /// See [`crate::validation`].
+    pub fn validate(&self) -> Result<(), crate::ValidationError> {
+        self.validate_with(&Default::default())
+    }
+    /// [`Self::validate`], also checking against the limits of the server
+    pub fn validate_with(
+        &self,
+        limits: &crate::validation::ServiceLimits,
+    ) -> Result<(), crate::ValidationError> {
+        let mut validator = crate::validation::Validator::default();
+        validator.min_locations("sources", self.sources.len(), 1);
+        validator.min_locations("targets", self.targets.len(), 1);
+        validator.costing(Some(&self.costing));
+        validator.at_most(
+            "max_matrix_location_pairs",
+            (self.sources.len() * self.targets.len()) as f64,
+            limits.max_matrix_location_pairs.map(|max| max as f64),
+        );
+        if limits.max_matrix_distance.is_some() {
+            let distance = self
+                .sources
+                .iter()
+                .flat_map(|s| {
+                    self.targets
+                        .iter()
+                        .map(|t| crate::shapes::crow_fly_distance(s.coordinate(), t.coordinate()))
+                })
+                .fold(0.0, f64::max);
+            validator.at_most("max_matrix_distance", distance, limits.max_matrix_distance);
+        }
+        validator.finish()
+    }
     /// Sets the source and targets of the matrix
     pub fn sources_to_targets(
         mut self,
@@ -37,6 +73,11 @@ impl Manifest {
     ///
     /// **Note:** multimodal costing is not supported for the time-distance matrix service at this time.
     ///
+    /// **Note:** The limits on the size of a matrix (`service_limits.<costing>.max_matrix_distance`
+    /// and the number of locations) are part of the server configuration and cannot be set per request.
+    /// To bound the cost of a single request, use [`Self::minimum_matrix_locations_count`]
+    /// and [`Location::search_cutoff`].
+    ///
     /// Default: [`costing::Costing::Auto`]
     pub fn costing(mut self, costing: costing::Costing) -> Self {
         self.costing = costing;
@@ -97,13 +138,153 @@ impl Manifest {
         self.shape_format = Some(shape_format);
         self
     }
+    /// Splits the matrix into sub-matrices of at most `max_location_pairs` source-target pairs
+    ///
+    /// Chunks are created lazily to keep memory flat for huge matrices.
+    pub(crate) fn chunks(&self, max_location_pairs: usize) -> impl Iterator<Item = Chunk> + '_ {
+        let (sources_per_chunk, targets_per_chunk) = self.chunk_dimensions(max_location_pairs);
+        self.sources
+            .chunks(sources_per_chunk)
+            .enumerate()
+            .flat_map(move |(s, sources)| {
+                self.targets
+                    .chunks(targets_per_chunk)
+                    .enumerate()
+                    .map(move |(t, targets)| Chunk {
+                        source_offset: s * sources_per_chunk,
+                        target_offset: t * targets_per_chunk,
+                        manifest: Self {
+                            sources: sources.to_vec(),
+                            targets: targets.to_vec(),
+                            verbose: Some(false),
+                            ..self.clone_options()
+                        },
+                    })
+            })
+    }
+    /// Number of chunks [`Self::chunks`] yields
+    pub(crate) fn chunk_count(&self, max_location_pairs: usize) -> usize {
+        let (sources_per_chunk, targets_per_chunk) = self.chunk_dimensions(max_location_pairs);
+        self.sources.len().div_ceil(sources_per_chunk)
+            * self.targets.len().div_ceil(targets_per_chunk)
+    }
+    fn chunk_dimensions(&self, max_location_pairs: usize) -> (usize, usize) {
+        let targets_per_chunk = self.targets.len().clamp(1, max_location_pairs.max(1));
+        let sources_per_chunk = (max_location_pairs / targets_per_chunk).max(1);
+        (sources_per_chunk, targets_per_chunk)
+    }
+    /// Clone of all options, without sources and targets
+    fn clone_options(&self) -> Self {
+        Self {
+            targets: Vec::new(),
+            sources: Vec::new(),
+            costing: self.costing.clone(),
+            id: self.id.clone(),
+            matrix_locations: self.matrix_locations,
+            date_time: self.date_time,
+            verbose: self.verbose,
+            shape_format: self.shape_format,
+        }
+    }
+}
+
+/// Part of a matrix which is sent as a request of its own
+///
+/// See [`crate::batch::Control::chunk_costing`].
+#[derive(Debug, Clone)]
+pub struct Chunk {
+    /// Index of the first source of this chunk in the original matrix
+    pub(crate) source_offset: usize,
+    /// Index of the first target of this chunk in the original matrix
+    pub(crate) target_offset: usize,
+    pub(crate) manifest: Manifest,
+}
+impl Chunk {
+    /// Index of the first source of this chunk in the original matrix
+    pub fn source_offset(&self) -> usize {
+        self.source_offset
+    }
+    /// Index of the first target of this chunk in the original matrix
+    pub fn target_offset(&self) -> usize {
+        self.target_offset
+    }
+    /// The sources of this chunk
+    pub fn sources(&self) -> &[Location] {
+        &self.manifest.sources
+    }
+    /// The targets of this chunk
+    pub fn targets(&self) -> &[Location] {
+        &self.manifest.targets
+    }
+}
+
+/// Time and distance between one source and one target of a matrix
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MatrixEntry {
+    /// Index into the sources of the [`Manifest`]
+    pub from_index: usize,
+    /// Index into the targets of the [`Manifest`]
+    pub to_index: usize,
+    /// The computed time in seconds, `None` if the target cannot be reached
+    pub time: Option<u32>,
+    /// The computed distance in [`super::Units`], `None` if the target cannot be reached
+    pub distance: Option<f64>,
+}
+
+/// Destination for matrix results which are written as they arrive
+///
+/// See [`crate::Valhalla::matrix_batched`].
+/// Implement this to write to other formats such as Arrow IPC or a database.
+pub trait MatrixSink {
+    /// Called once per completed chunk of the matrix
+    fn write_entries(&mut self, entries: &[MatrixEntry]) -> std::io::Result<()>;
+}
+
+/// Writes matrix entries as CSV with the header `from_index,to_index,time,distance`
+///
+/// Unreachable pairs have empty `time` and `distance` columns.
+pub struct CsvSink<W: std::io::Write> {
+    writer: W,
+    header_written: bool,
+}
+impl<W: std::io::Write> CsvSink<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            header_written: false,
+        }
+    }
+    /// Returns the underlying writer
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+impl<W: std::io::Write> MatrixSink for CsvSink<W> {
+    fn write_entries(&mut self, entries: &[MatrixEntry]) -> std::io::Result<()> {
+        if !self.header_written {
+            writeln!(self.writer, "from_index,to_index,time,distance")?;
+            self.header_written = true;
+        }
+        for entry in entries {
+            let time = entry.time.map(|t| t.to_string()).unwrap_or_default();
+            let distance = entry.distance.map(|d| d.to_string()).unwrap_or_default();
+            writeln!(
+                self.writer,
+                "{},{},{time},{distance}",
+                entry.from_index, entry.to_index
+            )?;
+        }
+        self.writer.flush()
+    }
 }
 
 /// The local date and time at the location
-#[derive(Serialize, Debug)]
+#[derive(Serialize, Debug, Clone, Copy)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct DateTime {
     r#type: MatrixDateTimeType,
     #[serde(serialize_with = "super::serialize_naive_date_time")]
+    #[cfg_attr(feature = "schemars", schemars(with = "String"))]
     value: chrono::NaiveDateTime,
 }
 impl DateTime {
@@ -131,6 +312,7 @@ impl DateTime {
 }
 
 #[derive(serde_repr::Serialize_repr, Debug, Clone, Copy)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema_repr))]
 #[repr(u8)]
 enum MatrixDateTimeType {
     CurrentDeparture = 0,
@@ -138,20 +320,33 @@ enum MatrixDateTimeType {
     SpecifiedArrival,
 }
 
+#[serde_with::skip_serializing_none]
 #[derive(Serialize, Default, Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct Location {
+    #[serde(serialize_with = "crate::precision::serialize_f32")]
     lat: f32,
+    #[serde(serialize_with = "crate::precision::serialize_f32")]
     lon: f32,
     #[serde(serialize_with = "super::serialize_naive_date_time_opt")]
+    #[cfg_attr(feature = "schemars", schemars(with = "Option<String>"))]
     #[serde(skip_serializing_if = "Option::is_none")]
     date_time: Option<chrono::NaiveDateTime>,
+    preferred_side: Option<crate::route::Side>,
+    heading: Option<u32>,
+    heading_tolerance: Option<u32>,
+    radius: Option<i32>,
+    street_side_tolerance: Option<f32>,
+    street_side_max_distance: Option<f32>,
+    minimum_reachability: Option<i32>,
+    search_cutoff: Option<f32>,
 }
 impl From<super::Coordinate> for Location {
     fn from((longitude, latitude): super::Coordinate) -> Self {
         Self {
             lat: latitude,
             lon: longitude,
-            date_time: None,
+            ..Default::default()
         }
     }
 }
@@ -160,6 +355,10 @@ impl Location {
     pub fn new(longitude: f32, latitude: f32) -> Self {
         Self::from((longitude, latitude))
     }
+    /// Longitude and latitude of the location
+    pub fn coordinate(&self) -> super::Coordinate {
+        (self.lon, self.lat)
+    }
     /// Expected date/time for the user to be at the location in the local time zone of departure or arrival.
     ///
     /// Offers more granularity over setting time than the global [`Manifest::date_time`].
@@ -185,6 +384,78 @@ impl Location {
         self.date_time = Some(date_time);
         self
     }
+    /// Which side of the road the location should be departed from or arrived at
+    ///
+    /// E.g. to make a delivery van stop on the side of the street the customer lives on.
+    /// The location is correlated to the road network the same way as for routes, so the
+    /// durations and distances honour the approach.
+    ///
+    /// See [`crate::route::Location::preferred_side`] for details.
+    pub fn preferred_side(mut self, preferred_side: crate::route::Side) -> Self {
+        self.preferred_side = Some(preferred_side);
+        self
+    }
+    /// Preferred direction of travel in degrees from north for departing from the location
+    ///
+    /// See [`crate::route::Location::heading`] for details.
+    pub fn heading(mut self, heading: u32) -> Self {
+        self.heading = Some(heading);
+        self
+    }
+    /// How close in degrees a street's heading must be to [`Self::heading`]
+    ///
+    /// Default: `60` degrees
+    pub fn heading_tolerance(mut self, heading_tolerance: u32) -> Self {
+        self.heading_tolerance = Some(heading_tolerance);
+        self
+    }
+    /// The number of meters about this location within which roads are considered as candidates
+    ///
+    /// See [`crate::route::Location::radius`] for details.
+    ///
+    /// Default: `0` meters
+    pub fn radius(mut self, radius: i32) -> Self {
+        self.radius = Some(radius);
+        self
+    }
+    /// Below this distance in meters from the road centerline, the side of street is ignored
+    ///
+    /// See [`crate::route::Location::street_side_tolerance`] for details.
+    ///
+    /// Default: `5 meters`
+    pub fn street_side_tolerance(mut self, street_side_tolerance: f32) -> Self {
+        self.street_side_tolerance = Some(street_side_tolerance);
+        self
+    }
+    /// Beyond this distance in meters from the road centerline, the side of street is ignored
+    ///
+    /// See [`crate::route::Location::street_side_max_distance`] for details.
+    ///
+    /// Default: `1000 meters`
+    pub fn street_side_max_distance(mut self, street_side_max_distance: f32) -> Self {
+        self.street_side_max_distance = Some(street_side_max_distance);
+        self
+    }
+    /// Minimum number of intersections reachable from a candidate road for it to not be
+    /// considered a disconnected island
+    ///
+    /// See [`crate::route::Location::minimum_reachability`] for details.
+    ///
+    /// Default: `50` reachable nodes.
+    pub fn minimum_reachability(mut self, minimum_reachability: i32) -> Self {
+        self.minimum_reachability = Some(minimum_reachability);
+        self
+    }
+    /// Distance in meters beyond which this location is not correlated to the road network
+    ///
+    /// Lowering this bounds the work spent on locations far away from any road:
+    /// instead of searching up to the server default, the matrix reports them as unreachable early.
+    ///
+    /// Default: `35 km`
+    pub fn search_cutoff(mut self, search_cutoff: f32) -> Self {
+        self.search_cutoff = Some(search_cutoff);
+        self
+    }
 }
 
 /// [`Location`] which was configured in the input
@@ -197,6 +468,7 @@ pub struct VerboseLocation {
     /// Longitude as defined in [`super::Coordinate`]
     pub lon: f32,
     /// time configured via [`Location::date_time`]
+    #[serde(default, deserialize_with = "super::deserialize_naive_date_time_opt")]
     pub date_time: Option<chrono::NaiveDateTime>,
 }
 
@@ -216,6 +488,7 @@ impl From<VerboseLocation> for Location {
             lat: value.lat,
             lon: value.lon,
             date_time: value.date_time,
+            ..Default::default()
         }
     }
 }
@@ -232,6 +505,92 @@ pub enum Response {
     /// Verbosity can be set via [`Manifest::verbose_output`]
     Concise(ConciseResponse),
 }
+impl Response {
+    /// Flattens the response into entries, adding the offsets to their indices
+    pub(crate) fn into_entries(
+        self,
+        source_offset: usize,
+        target_offset: usize,
+    ) -> Vec<MatrixEntry> {
+        match self {
+            Self::Concise(r) => r
+                .sources_to_targets
+                .durations
+                .into_iter()
+                .zip(r.sources_to_targets.distances)
+                .enumerate()
+                .flat_map(|(from, (times, distances))| {
+                    times.into_iter().zip(distances).enumerate().map(
+                        move |(to, (time, distance))| MatrixEntry {
+                            from_index: source_offset + from,
+                            to_index: target_offset + to,
+                            time,
+                            distance,
+                        },
+                    )
+                })
+                .collect(),
+            Self::Verbose(r) => r
+                .sources_to_targets
+                .into_iter()
+                .flatten()
+                .map(|s| MatrixEntry {
+                    from_index: source_offset + s.from_index,
+                    to_index: target_offset + s.to_index,
+                    time: s.time,
+                    distance: s.distance,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Assembles the responses to the chunks of a `sources`×`targets` matrix into one response
+///
+/// `parts` are the responses with the source and target offset of their chunk.
+/// The `id`, `algorithm` and `units` are taken from the first part, `warnings` are collected
+/// from all of them.
+/// Pairs not covered by any part are `None`, as if they were unreachable.
+///
+/// # Panics
+///
+/// If `parts` is empty
+pub(crate) fn stitch(
+    sources: usize,
+    targets: usize,
+    parts: Vec<(usize, usize, Response)>,
+) -> ConciseResponse {
+    let mut durations = vec![vec![None; targets]; sources];
+    let mut distances = vec![vec![None; targets]; sources];
+    let mut first = None;
+    let mut warnings = Vec::new();
+    for (source_offset, target_offset, response) in parts {
+        let (id, algorithm, units, part_warnings) = match &response {
+            Response::Concise(r) => (&r.id, &r.algorithm, r.units, &r.warnings),
+            Response::Verbose(r) => (&r.id, &r.algorithm, r.units, &r.warnings),
+        };
+        first.get_or_insert_with(|| (id.clone(), algorithm.clone(), units));
+        warnings.extend(part_warnings.iter().cloned());
+        for entry in response.into_entries(source_offset, target_offset) {
+            if entry.from_index < sources && entry.to_index < targets {
+                durations[entry.from_index][entry.to_index] = entry.time;
+                distances[entry.from_index][entry.to_index] = entry.distance;
+            }
+        }
+    }
+    let (id, algorithm, units) = first.expect("a matrix consists of at least one part");
+    ConciseResponse {
+        id,
+        algorithm,
+        units,
+        warnings,
+        sources_to_targets: ConciseSourceToTargets {
+            durations,
+            distances,
+        },
+    }
+}
+
 #[derive(Deserialize, Debug, Clone)]
 pub struct VerboseResponse {
     /// Name of the route request.
@@ -295,14 +654,21 @@ pub struct ConciseSourceToTargets {
     /// - the first element of the time-distance array for one_to_many,
     /// - the last element in a many_to_one, and
     /// - the first and last elements of a many_to_many
-    pub durations: Vec<Vec<u32>>,
+    ///
+    /// `None` if the target cannot be reached from the source.
+    pub durations: Vec<Vec<Option<u32>>>,
     /// The computed distance between each set of points.
     ///
     /// Distance will always be `0.00` for
     /// - the first element of the time-distance array for one_to_many,
     /// - the last element in a many_to_one, and
     /// - the first and last elements of a many_to_many.
-    pub distances: Vec<Vec<f32>>,
+    ///
+    /// `None` if the target cannot be reached from the source.
+    ///
+    /// Double precision like [`crate::route::Summary::length`], so that sums over thousands of
+    /// pairs stay accurate.
+    pub distances: Vec<Vec<Option<f64>>>,
 }
 
 #[derive(Deserialize, Debug, Clone)]
@@ -313,14 +679,18 @@ pub struct VerboseSourceToTarget {
     /// - the first element of the time-distance array for one_to_many,
     /// - the last element in a many_to_one, and
     /// - the first and last elements of a many_to_many.
-    pub distance: f32,
+    ///
+    /// `None` if the target cannot be reached from the source.
+    pub distance: Option<f64>,
     /// The computed time between each set of points.
     ///
     /// Time will always be `0` for
     /// - the first element of the time-distance array for one_to_many,
     /// - the last element in a many_to_one, and
     /// - the first and last elements of a many_to_many
-    pub time: u32,
+    ///
+    /// `None` if the target cannot be reached from the source.
+    pub time: Option<u32>,
     /// The destination index into the locations array
     pub from_index: usize,
     /// The origin index into the locations array
@@ -356,5 +726,155 @@ pub struct VerboseSourceToTarget {
     /// - departure/arrival time is unspecified via [`Manifest::date_time`] or [`Location::date_time`]
     ///
     /// Example: `"2024-11-07T15:26"`
+    #[serde(default, deserialize_with = "super::deserialize_naive_date_time_opt")]
     pub date_time: Option<chrono::NaiveDateTime>,
+    /// Fields of the entry which this crate does not model, see [`crate::route::Trip::extra`]
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn chunks_cover_matrix() {
+        let locations: Vec<Location> = (0..5).map(|i| Location::new(i as f32, 0.0)).collect();
+        let manifest = Manifest::builder()
+            .sources_to_targets(locations.clone(), locations[..3].to_vec())
+            .id("chunked");
+        let chunks: Vec<Chunk> = manifest.chunks(4).collect();
+        assert_eq!(chunks.len(), 5);
+        assert_eq!(manifest.chunk_count(4), 5);
+        for (i, chunk) in chunks.iter().enumerate() {
+            assert_eq!(chunk.source_offset, i);
+            assert_eq!(chunk.target_offset, 0);
+            assert_eq!(chunk.manifest.sources, [locations[i]]);
+            assert_eq!(chunk.manifest.targets.len(), 3);
+            assert_eq!(chunk.manifest.id.as_deref(), Some("chunked"));
+        }
+        let chunks: Vec<Chunk> = manifest.chunks(2).collect();
+        assert_eq!(chunks.len(), 10);
+        assert_eq!(manifest.chunk_count(2), 10);
+        assert_eq!(chunks[1].source_offset, 0);
+        assert_eq!(chunks[1].target_offset, 2);
+        assert_eq!(chunks[1].manifest.targets, [locations[2]]);
+    }
+
+    #[test]
+    fn stitch_keeps_indices() {
+        let part = |durations: serde_json::Value, warnings: serde_json::Value| {
+            serde_json::from_value::<Response>(serde_json::json!({
+                "algorithm": "costmatrix",
+                "units": "kilometers",
+                "warnings": warnings,
+                "sources_to_targets": {"durations": durations, "distances": durations}
+            }))
+            .unwrap()
+        };
+        let stitched = stitch(
+            2,
+            3,
+            vec![
+                (
+                    0,
+                    0,
+                    part(
+                        serde_json::json!([[0, 10], [20, 30]]),
+                        serde_json::json!([]),
+                    ),
+                ),
+                (
+                    0,
+                    2,
+                    part(
+                        serde_json::json!([[40], [null]]),
+                        serde_json::json!(["clamped"]),
+                    ),
+                ),
+            ],
+        );
+        assert_eq!(stitched.algorithm, "costmatrix");
+        assert_eq!(stitched.warnings, [serde_json::json!("clamped")]);
+        assert_eq!(
+            stitched.sources_to_targets.durations,
+            [[Some(0), Some(10), Some(40)], [Some(20), Some(30), None]]
+        );
+        assert_eq!(stitched.sources_to_targets.distances[1][0], Some(20.0));
+    }
+
+    #[test]
+    fn distances_keep_their_precision() {
+        let entry: VerboseSourceToTarget = serde_json::from_value(serde_json::json!({
+            "distance": 12345.678,
+            "time": 60,
+            "from_index": 0,
+            "to_index": 1
+        }))
+        .unwrap();
+        assert_eq!(entry.distance, Some(12345.678));
+    }
+
+    #[test]
+    fn location_serialisation() {
+        assert_eq!(
+            serde_json::to_value(Location::new(4.5, 52.0)).unwrap(),
+            serde_json::json!({"lat": 52.0, "lon": 4.5})
+        );
+        assert_eq!(
+            serde_json::to_value(
+                Location::new(4.5, 52.0)
+                    .preferred_side(crate::route::Side::Same)
+                    .heading(90)
+                    .radius(20)
+                    .search_cutoff(5000.0)
+            )
+            .unwrap(),
+            serde_json::json!({"lat": 52.0, "lon": 4.5, "preferred_side": "same", "heading": 90, "radius": 20, "search_cutoff": 5000.0})
+        );
+    }
+
+    #[test]
+    fn csv_sink() {
+        let mut sink = CsvSink::new(Vec::new());
+        sink.write_entries(&[
+            MatrixEntry {
+                from_index: 0,
+                to_index: 1,
+                time: Some(60),
+                distance: Some(1.5),
+            },
+            MatrixEntry {
+                from_index: 1,
+                to_index: 0,
+                time: None,
+                distance: None,
+            },
+        ])
+        .unwrap();
+        assert_eq!(
+            String::from_utf8(sink.into_inner()).unwrap(),
+            "from_index,to_index,time,distance\n0,1,60,1.5\n1,0,,\n"
+        );
+    }
+
+    #[test]
+    fn verbose_date_times_are_parsed_leniently() {
+        let entry: VerboseSourceToTarget = serde_json::from_value(serde_json::json!({
+            "distance": 1.5,
+            "time": 60,
+            "from_index": 0,
+            "to_index": 1,
+            "date_time": "2024-11-07T15:26:30+01:00"
+        }))
+        .unwrap();
+        assert_eq!(
+            entry.date_time,
+            chrono::NaiveDate::from_ymd_opt(2024, 11, 7)
+                .unwrap()
+                .and_hms_opt(15, 26, 30)
+        );
+        let location: VerboseLocation =
+            serde_json::from_value(serde_json::json!({"lat": 52.0, "lon": 4.0})).unwrap();
+        assert_eq!(location.date_time, None);
+    }
 }
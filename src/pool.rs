@@ -0,0 +1,211 @@
+use crate::{matrix, route, status, Error, Valhalla};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+/// How [`ValhallaPool`] picks the instance for the next request
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Balancing {
+    /// Cycle through the instances in order
+    #[default]
+    RoundRobin,
+    /// Use the instance with the fewest requests in flight
+    ///
+    /// Instances with an equal number of requests in flight are used in order.
+    LeastInFlight,
+}
+
+struct Instance {
+    client: Valhalla,
+    healthy: AtomicBool,
+}
+
+/// Distributes requests across several valhalla instances
+///
+/// Useful to fan out large matrix workloads over multiple routers.
+/// The pool can be shared between threads, each request is sent to one healthy instance.
+///
+/// An instance is marked unhealthy if a request to it fails without the server responding
+/// (e.g. the connection was refused) or if [`Self::check_health`] fails.
+/// It is marked healthy again once [`Self::check_health`] succeeds.
+/// If no instance is healthy, requests are distributed over all of them.
+///
+/// # Example:
+/// ```rust,no_run
+/// use valhalla_client::{Valhalla, ValhallaPool};
+/// use valhalla_client::pool::Balancing;
+/// use valhalla_client::costing::Costing;
+/// use valhalla_client::route::{Location, ManifestBuilder};
+///
+/// let pool = ValhallaPool::new([
+///   Valhalla::new(url::Url::parse("http://router-1:8002/").unwrap()),
+///   Valhalla::new(url::Url::parse("http://router-2:8002/").unwrap()),
+/// ])
+/// .balancing(Balancing::LeastInFlight);
+/// pool.check_health();
+///
+/// let manifest = ManifestBuilder::default()
+///   .locations([Location::new(4.9041, 52.3676), Location::new(5.1214, 52.0907)])
+///   .costing(Costing::default());
+/// let trip = pool.route(manifest).unwrap();
+/// ```
+pub struct ValhallaPool {
+    instances: Vec<Instance>,
+    balancing: Balancing,
+    next: AtomicUsize,
+}
+
+impl ValhallaPool {
+    /// Creates a pool from the clients of the individual instances
+    ///
+    /// All instances start out as healthy.
+    ///
+    /// # Panics
+    ///
+    /// If `instances` is empty
+    pub fn new(instances: impl IntoIterator<Item = Valhalla>) -> Self {
+        let instances: Vec<_> = instances
+            .into_iter()
+            .map(|client| Instance {
+                client,
+                healthy: AtomicBool::new(true),
+            })
+            .collect();
+        assert!(!instances.is_empty(), "a pool needs at least one instance");
+        Self {
+            instances,
+            balancing: Default::default(),
+            next: AtomicUsize::new(0),
+        }
+    }
+    /// Configures how the instance for the next request is picked
+    ///
+    /// Default: [`Balancing::RoundRobin`]
+    pub fn balancing(mut self, balancing: Balancing) -> Self {
+        self.balancing = balancing;
+        self
+    }
+    /// Queries the status endpoint of every instance and updates its health accordingly
+    ///
+    /// Returns the number of healthy instances.
+    /// Call this periodically (e.g. from a background thread) to bring recovered instances back.
+    pub fn check_health(&self) -> usize {
+        self.instances
+            .iter()
+            .filter(|instance| {
+                let healthy = instance.client.status(status::Manifest::builder()).is_ok();
+                instance.healthy.store(healthy, Ordering::Relaxed);
+                healthy
+            })
+            .count()
+    }
+    /// Number of instances in the pool
+    pub fn len(&self) -> usize {
+        self.instances.len()
+    }
+    /// Always `false`, as a pool cannot be empty
+    pub fn is_empty(&self) -> bool {
+        self.instances.is_empty()
+    }
+    /// Whether the instance at `index` (in the order passed to [`Self::new`]) is considered healthy
+    pub fn is_healthy(&self, index: usize) -> bool {
+        self.instances[index].healthy.load(Ordering::Relaxed)
+    }
+
+    /// Runs `request` against the next instance
+    ///
+    /// Allows sending any request the [`Valhalla`] client supports through the pool.
+    ///
+    /// # Example:
+    /// ```rust,no_run
+    /// # let pool = valhalla_client::ValhallaPool::new([valhalla_client::Valhalla::default()]);
+    /// use valhalla_client::elevation::Manifest;
+    ///
+    /// let manifest = Manifest::builder().shape([(13.3889, 52.5170)]);
+    /// let heights = pool.execute(|valhalla| valhalla.elevation(manifest)).unwrap();
+    /// ```
+    pub fn execute<T>(
+        &self,
+        request: impl FnOnce(&Valhalla) -> Result<T, Error>,
+    ) -> Result<T, Error> {
+        let instance = &self.instances[self.pick()];
+        let result = request(&instance.client);
+        if result.as_ref().is_err_and(Error::is_connection_failure) {
+            instance.healthy.store(false, Ordering::Relaxed);
+        }
+        result
+    }
+    /// Make a turn-by-turn routing request on the next instance
+    ///
+    /// See [`Valhalla::route`] for details
+    pub fn route(&self, manifest: route::Manifest) -> Result<route::Trip, Error> {
+        self.execute(|valhalla| valhalla.route(manifest))
+    }
+    /// Make a time-distance matrix routing request on the next instance
+    ///
+    /// See [`Valhalla::matrix`] for details
+    pub fn matrix(&self, manifest: matrix::Manifest) -> Result<matrix::Response, Error> {
+        self.execute(|valhalla| valhalla.matrix(manifest))
+    }
+
+    /// Index of the instance the next request is sent to
+    fn pick(&self) -> usize {
+        let healthy: Vec<usize> = (0..self.instances.len())
+            .filter(|&i| self.is_healthy(i))
+            .collect();
+        let candidates = if healthy.is_empty() {
+            (0..self.instances.len()).collect()
+        } else {
+            healthy
+        };
+        match self.balancing {
+            Balancing::RoundRobin => {
+                let next = self.next.fetch_add(1, Ordering::Relaxed);
+                candidates[next % candidates.len()]
+            }
+            Balancing::LeastInFlight => candidates
+                .into_iter()
+                .min_by_key(|&i| self.instances[i].client.in_flight_requests())
+                .expect("a pool cannot be empty"),
+        }
+    }
+}
+
+#[cfg(all(test, any(feature = "reqwest", feature = "ureq")))]
+mod tests {
+    use super::*;
+
+    fn pool(size: usize) -> ValhallaPool {
+        ValhallaPool::new(
+            (0..size).map(|i| {
+                Valhalla::new(url::Url::parse(&format!("http://router-{i}:8002/")).unwrap())
+            }),
+        )
+    }
+
+    #[test]
+    fn round_robin_skips_unhealthy_instances() {
+        let pool = pool(3);
+        assert_eq!(
+            (0..4).map(|_| pool.pick()).collect::<Vec<_>>(),
+            [0, 1, 2, 0]
+        );
+        pool.instances[1].healthy.store(false, Ordering::Relaxed);
+        assert!(!pool.is_healthy(1));
+        assert!((0..4).map(|_| pool.pick()).all(|i| i != 1));
+
+        // without healthy instances, all of them are tried
+        for instance in &pool.instances {
+            instance.healthy.store(false, Ordering::Relaxed);
+        }
+        let mut picked: Vec<_> = (0..3).map(|_| pool.pick()).collect();
+        picked.sort();
+        assert_eq!(picked, [0, 1, 2]);
+    }
+
+    #[test]
+    fn least_in_flight() {
+        let pool = pool(2).balancing(Balancing::LeastInFlight);
+        assert_eq!(pool.pick(), 0);
+        let _permit = pool.instances[0].client.concurrency.acquire();
+        assert_eq!(pool.pick(), 1);
+    }
+}
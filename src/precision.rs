@@ -0,0 +1,167 @@
+//! Rounding of coordinates when serializing manifests
+//!
+//! Widening an `f32` coordinate to `f64` produces long tails of meaningless digits
+//! (`52.3676_f32` becomes `52.367599487304688`), which bloat requests and make their JSON depend
+//! on how a value was converted.
+//! Coordinates of manifests are therefore serialized rounded to a fixed number of decimals.
+//! Types which are also part of responses, such as [`crate::shapes::ShapePoint`], serialize
+//! unrounded; the manifests round them via [`serialize_shape_opt`].
+use std::cell::Cell;
+
+/// Decimals used unless [`crate::Valhalla::coordinate_precision`] says otherwise
+///
+/// Six decimals resolve about 0.1m, matching the precision of valhalla's polyline6 shapes.
+pub(crate) const DEFAULT_DECIMALS: u8 = 6;
+
+thread_local! {
+    static DECIMALS: Cell<u8> = const { Cell::new(DEFAULT_DECIMALS) };
+}
+
+/// Runs `f` with coordinates serialized rounded to `decimals` decimals on this thread
+pub(crate) fn with_decimals<T>(decimals: u8, f: impl FnOnce() -> T) -> T {
+    /// Restores the previous precision, also if `f` panics
+    struct Reset(u8);
+    impl Drop for Reset {
+        fn drop(&mut self) {
+            DECIMALS.set(self.0);
+        }
+    }
+    let _reset = Reset(DECIMALS.replace(decimals));
+    f()
+}
+
+fn round(value: f64) -> f64 {
+    let decimals = DECIMALS.get();
+    // beyond that, f64 cannot represent the rounded value any more precisely
+    if decimals >= 15 || !value.is_finite() {
+        return value;
+    }
+    let factor = 10f64.powi(i32::from(decimals));
+    (value * factor).round() / factor
+}
+
+pub(crate) fn serialize_f64<S: serde::Serializer>(
+    value: &f64,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_f64(round(*value))
+}
+
+fn round_f32(value: f32) -> f64 {
+    // the shortest representation of the f32 is what the user wrote, not its widened binary value
+    let widened = value.to_string().parse().unwrap_or(f64::from(value));
+    round(widened)
+}
+
+pub(crate) fn serialize_f32<S: serde::Serializer>(
+    value: &f32,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_f64(round_f32(*value))
+}
+
+pub(crate) fn serialize_f32_opt<S: serde::Serializer>(
+    value: &Option<f32>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match value {
+        None => serializer.serialize_none(),
+        Some(value) => serialize_f32(value, serializer),
+    }
+}
+
+/// Polygon rings, serialized as `[lon, lat]` pairs
+pub(crate) fn serialize_rings_opt<S: serde::Serializer>(
+    rings: &Option<Vec<Vec<crate::Coordinate>>>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let rounded = rings.as_ref().map(|rings| {
+        rings
+            .iter()
+            .map(|ring| {
+                ring.iter()
+                    .map(|&(lon, lat)| [round_f32(lon), round_f32(lat)])
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>()
+    });
+    serde::Serialize::serialize(&rounded, serializer)
+}
+
+/// A shape, serialized as `{"lon": .., "lat": ..}` objects
+pub(crate) fn serialize_shape_opt<S: serde::Serializer>(
+    shape: &Option<Vec<crate::shapes::ShapePoint>>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    #[derive(serde::Serialize)]
+    struct Rounded {
+        #[serde(serialize_with = "serialize_f64")]
+        lon: f64,
+        #[serde(serialize_with = "serialize_f64")]
+        lat: f64,
+    }
+    let rounded = shape.as_ref().map(|shape| {
+        shape
+            .iter()
+            .map(|p| Rounded {
+                lon: p.lon,
+                lat: p.lat,
+            })
+            .collect::<Vec<_>>()
+    });
+    serde::Serialize::serialize(&rounded, serializer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(serde::Serialize)]
+    struct Point {
+        #[serde(serialize_with = "serialize_f32")]
+        lat: f32,
+        #[serde(serialize_with = "serialize_f64")]
+        lon: f64,
+    }
+
+    #[test]
+    fn rounds_to_decimals() {
+        let point = Point {
+            lat: 52.3676,
+            lon: 4.904_123_456_7,
+        };
+        assert_eq!(
+            serde_json::to_value(&point).unwrap(),
+            serde_json::json!({"lat": 52.3676, "lon": 4.904123})
+        );
+        let json = with_decimals(2, || serde_json::to_string(&point).unwrap());
+        assert_eq!(json, r#"{"lat":52.37,"lon":4.9}"#);
+        assert_eq!(DECIMALS.get(), DEFAULT_DECIMALS);
+    }
+
+    #[test]
+    fn rounds_manifests_only() {
+        let manifest = crate::route::Manifest::default().exclude_polygon([
+            (4.9041, 52.3676),
+            (4.91, 52.37),
+            (4.9041, 52.37),
+        ]);
+        assert_eq!(
+            serde_json::to_value(&manifest).unwrap()["exclude_polygons"],
+            serde_json::json!([[[4.9041, 52.3676], [4.91, 52.37], [4.9041, 52.37]]])
+        );
+        let point = crate::shapes::ShapePoint {
+            lon: 4.904_123_456_7,
+            lat: 52.0,
+        };
+        assert_eq!(
+            serde_json::to_value(&point).unwrap()["lon"],
+            4.904_123_456_7
+        );
+        let elevation = crate::elevation::Manifest::builder().shape([point]);
+        assert_eq!(
+            serde_json::to_value(&elevation).unwrap()["shape"][0]["lon"],
+            4.904123
+        );
+    }
+}
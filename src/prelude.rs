@@ -0,0 +1,23 @@
+//! The types needed for most requests, in one import
+//!
+//! The `Manifest` and `Location` types of [`crate::route`] and [`crate::matrix`] share their
+//! names, so they are exported as `Route*` and `Matrix*`.
+//!
+//! # Example:
+//! ```rust,no_run
+//! use valhalla_client::prelude::*;
+//!
+//! let valhalla = Valhalla::default();
+//! let manifest = RouteManifestBuilder::default()
+//!     .locations([RouteLocation::new(4.9041, 52.3676), RouteLocation::new(5.1214, 52.0907)])
+//!     .costing(Costing::Bicycle(Default::default()))
+//!     .units(Units::Imperial);
+//! let response = valhalla.route(manifest).unwrap();
+//! ```
+
+pub use crate::costing::Costing;
+pub use crate::matrix::{DateTime, Location as MatrixLocation, Manifest as MatrixManifest};
+pub use crate::route::{
+    Location as RouteLocation, Manifest as RouteManifest, ManifestBuilder as RouteManifestBuilder,
+};
+pub use crate::{Coordinate, Error, Language, Units, Valhalla};
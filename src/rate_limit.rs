@@ -0,0 +1,101 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+/// Token bucket limiting the rate at which a client sends requests
+///
+/// Holds up to `burst` tokens, refilled at `per_second` tokens per second.
+/// Each request takes one token, waiting for it if the bucket is empty.
+#[derive(Debug, Clone)]
+pub(crate) struct TokenBucket {
+    per_second: f64,
+    burst: f64,
+    bucket: Arc<Mutex<Bucket>>,
+}
+
+impl TokenBucket {
+    pub(crate) fn new(per_second: f64, burst: u32) -> Self {
+        let burst = f64::from(burst.max(1));
+        Self {
+            per_second,
+            burst,
+            bucket: Arc::new(Mutex::new(Bucket {
+                tokens: burst,
+                last_refill: Instant::now(),
+            })),
+        }
+    }
+    /// Blocks until a request may be sent
+    ///
+    /// Returns `false` without waiting if no token becomes available before `deadline`.
+    pub(crate) fn acquire(&self, deadline: Option<Instant>) -> bool {
+        loop {
+            let now = Instant::now();
+            match self.try_acquire(now) {
+                None => return true,
+                Some(wait)
+                    if deadline.is_some_and(|deadline| {
+                        now.checked_add(wait).is_none_or(|ready| ready > deadline)
+                    }) =>
+                {
+                    return false
+                }
+                Some(wait) => std::thread::sleep(wait),
+            }
+        }
+    }
+    /// Takes a token, or returns how long to wait until one is available
+    fn try_acquire(&self, now: Instant) -> Option<Duration> {
+        let mut bucket = self.bucket.lock().unwrap();
+        let elapsed = now.saturating_duration_since(bucket.last_refill);
+        bucket.tokens = (bucket.tokens + elapsed.as_secs_f64() * self.per_second).min(self.burst);
+        bucket.last_refill = now;
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            None
+        } else {
+            // saturates for rates so low the wait does not fit into a `Duration`
+            let wait = (1.0 - bucket.tokens) / self.per_second;
+            Some(Duration::try_from_secs_f64(wait).unwrap_or(Duration::MAX))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn refills_at_rate() {
+        let bucket = TokenBucket::new(2.0, 3);
+        let start = Instant::now();
+        for _ in 0..3 {
+            assert_eq!(bucket.try_acquire(start), None);
+        }
+        assert_eq!(bucket.try_acquire(start), Some(Duration::from_millis(500)));
+        assert_eq!(
+            bucket.try_acquire(start + Duration::from_millis(250)),
+            Some(Duration::from_millis(250))
+        );
+        assert_eq!(bucket.try_acquire(start + Duration::from_millis(500)), None);
+        // never more than the burst
+        let later = start + Duration::from_secs(60);
+        for _ in 0..3 {
+            assert_eq!(bucket.try_acquire(later), None);
+        }
+        assert!(bucket.try_acquire(later).is_some());
+    }
+
+    #[test]
+    fn tiny_rate() {
+        let bucket = TokenBucket::new(f64::MIN_POSITIVE, 1);
+        let start = Instant::now();
+        assert_eq!(bucket.try_acquire(start), None);
+        assert_eq!(bucket.try_acquire(start), Some(Duration::MAX));
+        assert!(!bucket.acquire(Some(start + Duration::from_secs(1))));
+    }
+}
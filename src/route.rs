@@ -1,10 +1,25 @@
 use crate::costing;
 pub use crate::shapes::ShapePoint;
+use log::warn;
 use serde::{Deserialize, Serialize};
 
 #[derive(Deserialize, Debug, Clone)]
 pub(crate) struct Response {
     pub(crate) trip: Trip,
+    #[serde(default)]
+    pub(crate) alternates: Vec<Alternate>,
+}
+
+/// [`Response`] whose trip is parsed by [`Trip::from_value_lenient`]
+#[derive(Deserialize, Debug)]
+pub(crate) struct LenientResponse {
+    pub(crate) trip: serde_json::Value,
+}
+
+/// Additional trip in the `alternates` array, next to the main `trip` of a response
+#[derive(Deserialize, Debug, Clone)]
+pub(crate) struct Alternate {
+    pub(crate) trip: Trip,
 }
 
 #[derive(Deserialize, Debug, Clone)]
@@ -18,7 +33,139 @@ pub struct Trip {
     pub id: Option<String>,
     pub legs: Vec<Leg>,
     pub summary: Summary,
+    /// Why legs were left out of [`Self::legs`], see [`crate::Valhalla::route_lenient`]
+    ///
+    /// Each warning starts with the index of the leg in the response, e.g. `leg 2: `.
+    /// As the legs left out are missing from [`Self::legs`], the legs after them are found at
+    /// lower indices there.
+    ///
+    /// Always empty for trips returned by other methods.
+    #[serde(skip)]
+    pub parse_warnings: Vec<String>,
+    /// Fields of the trip which this crate does not model
+    ///
+    /// Gives access to fields added by newer versions of valhalla before this crate models them.
+    /// Once a field is modelled, it is no longer part of this map.
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+impl Trip {
+    /// Parses a trip, leaving out the legs which cannot be parsed
+    ///
+    /// Every leg left out is explained in [`Self::parse_warnings`].
+    pub(crate) fn from_value_lenient(mut value: serde_json::Value) -> serde_json::Result<Self> {
+        // leave reporting a malformed trip or legs to the strict parser
+        let legs = match value.get_mut("legs").map(serde_json::Value::take) {
+            Some(serde_json::Value::Array(legs)) => legs,
+            Some(legs) => {
+                value["legs"] = legs;
+                return serde_json::from_value(value);
+            }
+            None => return serde_json::from_value(value),
+        };
+        value["legs"] = serde_json::Value::Array(Vec::new());
+        let mut trip: Self = serde_json::from_value(value)?;
+        for (i, leg) in legs.into_iter().enumerate() {
+            match serde_json::from_value(leg) {
+                Ok(leg) => trip.legs.push(leg),
+                Err(e) => {
+                    warn!("leaving out leg {i} of the trip: {e}");
+                    trip.parse_warnings.push(format!("leg {i}: {e}"));
+                }
+            }
+        }
+        Ok(trip)
+    }
+    /// Buffers the route shape into a corridor polygon `width_meters` wide.
+    ///
+    /// The route runs through the middle of the corridor, i.e. the polygon extends
+    /// `width_meters / 2` to either side of the shape.
+    /// Useful for geofencing a delivery or for excluding a previous route via
+    /// [`Manifest::exclude_polygon`] to force a different path.
+    ///
+    /// The buffer is an approximation on a locally flat earth.
+    /// Turns sharper than the corridor is wide may produce a self-intersecting ring.
+    pub fn corridor(&self, width_meters: f64) -> geo_types::Polygon {
+        let shape: Vec<ShapePoint> = self
+            .legs
+            .iter()
+            .flat_map(|leg| leg.shape.iter().cloned())
+            .collect();
+        crate::shapes::buffer(&shape, width_meters / 2.0)
+    }
+    /// When each stop of the trip is reached and left again
+    ///
+    /// A stop is every location generating a leg, i.e. [`LocationType::Break`] and
+    /// [`LocationType::BreakThrough`] locations as well as the first and last location.
+    /// The time spent at a stop is its [`Location::waiting`], which valhalla includes in the time
+    /// of the leg departing from it.
+    ///
+    /// Useful for delivery tours, where customers are given a window in which the driver arrives
+    /// and the service is done.
+    pub fn service_windows(&self) -> Vec<ServiceWindow> {
+        let last = self.locations.len().saturating_sub(1);
+        let stops = self.locations.iter().enumerate().filter(|(i, location)| {
+            *i == 0
+                || *i == last
+                || matches!(
+                    location.r#type,
+                    None | Some(LocationType::Break | LocationType::BreakThrough)
+                )
+        });
+        let start = self.locations.first().and_then(|l| l.date_time);
+        let mut arrival = 0.0;
+        let mut windows = Vec::with_capacity(self.legs.len() + 1);
+        for ((location_index, location), leg) in
+            stops.zip(std::iter::once(None).chain(self.legs.iter().map(Some)))
+        {
+            if let Some(leg) = leg {
+                arrival += leg.summary.time;
+            }
+            let departure = arrival + f64::from(location.waiting.unwrap_or_default());
+            windows.push(ServiceWindow {
+                location_index,
+                arrival,
+                departure,
+                arrival_date_time: start.map(|start| add_seconds(start, arrival)),
+                departure_date_time: start.map(|start| add_seconds(start, departure)),
+            });
+        }
+        windows
+    }
+}
+fn add_seconds(date_time: chrono::NaiveDateTime, seconds: f64) -> chrono::NaiveDateTime {
+    date_time + chrono::Duration::milliseconds((seconds * 1000.0).round() as i64)
+}
+
+/// Arrival at and departure from a stop of a [`Trip`], see [`Trip::service_windows`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ServiceWindow {
+    /// Index into [`Trip::locations`]
+    pub location_index: usize,
+    /// Seconds from the start of the trip until the stop is reached
+    pub arrival: f64,
+    /// Seconds from the start of the trip until the stop is left again
+    pub departure: f64,
+    /// Local time of the arrival
+    ///
+    /// Present only if the server echoed the departure time of the first location, which it
+    /// does for time dependent routes.
+    pub arrival_date_time: Option<chrono::NaiveDateTime>,
+    /// Local time of the departure, see [`Self::arrival_date_time`]
+    pub departure_date_time: Option<chrono::NaiveDateTime>,
 }
+/// Converts the trip to GPX 1.1
+///
+/// - The shape becomes one track per stretch of the same travel mode (see
+///   [`Leg::travel_mode_stretches`]), with the mode as its `<type>`, e.g. `pedestrian`.
+///   Each stretch of a leg is a track segment; consecutive stretches of the same mode in
+///   different legs share a track, with a segment per leg.
+///   Legs without maneuvers become a segment of a track without `<type>`.
+/// - The maneuvers become the points of a route, with the instruction as `<cmt>` and the time
+///   from the start of the trip as `<desc>`, e.g. `+0:05:30`.
+///   Devices showing route point comments can thus display the guidance.
+///
+/// GPX extensions are not written, as the `gpx` crate does not support them.
 #[cfg(feature = "gpx")]
 impl From<Trip> for gpx::Gpx {
     fn from(trip: Trip) -> Self {
@@ -27,22 +174,56 @@ impl From<Trip> for gpx::Gpx {
             creator: Some("valhalla".to_string()),
             ..Default::default()
         };
-        let track = gpx::Track {
-            name: Some("route".to_string()),
-            segments: trip.legs.iter().map(|leg| leg.into()).collect(),
-            ..Default::default()
-        };
-        gpx.tracks.push(track);
+        // a track per travel mode, so that viewers can tell e.g. walking and transit apart
+        for leg in &trip.legs {
+            let stretches = leg.travel_mode_stretches();
+            let segments: Vec<(Option<TravelMode>, gpx::TrackSegment)> = if stretches.is_empty() {
+                vec![(None, leg.into())]
+            } else {
+                stretches
+                    .iter()
+                    .map(|stretch| {
+                        let points = leg
+                            .stretch_shape(stretch)
+                            .iter()
+                            .map(|location| gpx::Waypoint::new(location.into()))
+                            .collect();
+                        (Some(stretch.travel_mode), gpx::TrackSegment { points })
+                    })
+                    .collect()
+            };
+            for (travel_mode, segment) in segments {
+                let type_ = travel_mode.map(|mode| mode.as_str().to_string());
+                match gpx.tracks.last_mut() {
+                    Some(track) if track.type_ == type_ => track.segments.push(segment),
+                    _ => gpx.tracks.push(gpx::Track {
+                        name: Some("route".to_string()),
+                        type_,
+                        segments: vec![segment],
+                        ..Default::default()
+                    }),
+                }
+            }
+        }
 
+        let mut elapsed: f64 = 0.0;
         let ps = trip
             .legs
             .iter()
-            .flat_map(|leg| {
-                leg.maneuvers.iter().map(|m| {
-                    let p = &leg.shape[m.begin_shape_index];
-
-                    gpx::Waypoint::new(p.into())
-                })
+            .flat_map(|leg| leg.maneuvers.iter().map(move |m| (leg, m)))
+            .filter_map(|(leg, m)| {
+                let offset = elapsed.round() as u64;
+                elapsed += m.time;
+                let p = leg.shape_point(m.begin_shape_index)?;
+                let mut waypoint = gpx::Waypoint::new(p.into());
+                waypoint.comment = Some(m.instruction.clone());
+                waypoint.description = Some(format!(
+                    "+{}:{:02}:{:02}",
+                    offset / 3600,
+                    offset / 60 % 60,
+                    offset % 60
+                ));
+                Some(waypoint)
             })
             .collect();
         let route = gpx::Route {
@@ -78,6 +259,35 @@ pub enum TravelMode {
     #[serde(rename = "transit")]
     Transit,
 }
+impl TravelMode {
+    /// The name valhalla uses for the travel mode, e.g. `"pedestrian"`
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Drive => "drive",
+            Self::Pedestrian => "pedestrian",
+            Self::Bicycle => "bicycle",
+            Self::Transit => "transit",
+        }
+    }
+}
+
+/// Consecutive maneuvers of a [`Leg`] sharing the same [`TravelMode`]
+///
+/// See [`Leg::travel_mode_stretches`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct TravelModeStretch {
+    pub travel_mode: TravelMode,
+    /// Indices into [`Leg::maneuvers`]
+    pub maneuvers: std::ops::Range<usize>,
+    /// Index into [`Leg::shape`] where the stretch starts
+    pub begin_shape_index: usize,
+    /// Index into [`Leg::shape`] where the stretch ends, i.e. where the next one starts
+    pub end_shape_index: usize,
+    /// Sum of the [`Maneuver::length`]s, in the [`super::Units`] of the request
+    pub length: f64,
+    /// Sum of the [`Maneuver::time`]s in seconds
+    pub time: f64,
+}
 
 #[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
 pub enum CarTravelType {
@@ -137,12 +347,117 @@ pub struct Leg {
     pub shape: Vec<ShapePoint>,
 }
 
+impl Leg {
+    /// Shape points from the start to the end of `maneuver`, both inclusive
+    ///
+    /// Never panics: Shape indices beyond the decoded shape (e.g. if the polyline was decoded
+    /// with a different precision than it was encoded with) are clamped to the shape and a
+    /// warning is logged.
+    pub fn maneuver_shape(&self, maneuver: &Maneuver) -> &[ShapePoint] {
+        self.shape_between(maneuver.begin_shape_index, maneuver.end_shape_index)
+    }
+    /// Shape point at `index`, clamped to the last shape point
+    ///
+    /// `None` only if the shape is empty.
+    /// Clamping is logged as a warning, see [`Self::maneuver_shape`].
+    pub fn shape_point(&self, index: usize) -> Option<&ShapePoint> {
+        self.shape.get(self.clamp_shape_index(index))
+    }
+    /// Whether a toll booth is encountered on any maneuver of this leg
+    pub fn has_toll(&self) -> bool {
+        self.maneuvers.iter().any(|m| m.toll == Some(true))
+    }
+    /// Whether a gate is encountered on any maneuver of this leg
+    ///
+    /// Useful for badges such as *"includes gate"*.
+    pub fn has_gate(&self) -> bool {
+        self.maneuvers.iter().any(|m| m.gate == Some(true))
+    }
+    /// Length of the maneuvers with a toll booth, in the [`super::Units`] of the request
+    pub fn tolled_length(&self) -> f64 {
+        self.maneuvers
+            .iter()
+            .filter(|m| m.toll == Some(true))
+            .map(|m| m.length)
+            .sum()
+    }
+    /// Number of ferries taken on this leg
+    ///
+    /// Consecutive maneuvers on a ferry count as a single ferry.
+    pub fn ferry_count(&self) -> usize {
+        let (count, _) = self
+            .maneuvers
+            .iter()
+            .map(|m| m.ferry == Some(true))
+            .fold((0, false), |(count, previous), ferry| {
+                (count + usize::from(ferry && !previous), ferry)
+            });
+        count
+    }
+    /// Splits the leg where the [`TravelMode`] changes
+    ///
+    /// Multimodal legs consist of e.g. a walk to a stop, a transit ride and a walk to the
+    /// destination.
+    /// Other legs form a single stretch, legs without maneuvers none at all.
+    pub fn travel_mode_stretches(&self) -> Vec<TravelModeStretch> {
+        let mut stretches: Vec<TravelModeStretch> = Vec::new();
+        for (i, maneuver) in self.maneuvers.iter().enumerate() {
+            match stretches.last_mut() {
+                Some(last) if last.travel_mode == maneuver.travel_mode => {
+                    last.maneuvers.end = i + 1;
+                    last.end_shape_index = maneuver.end_shape_index;
+                    last.length += maneuver.length;
+                    last.time += maneuver.time;
+                }
+                _ => stretches.push(TravelModeStretch {
+                    travel_mode: maneuver.travel_mode,
+                    maneuvers: i..i + 1,
+                    begin_shape_index: maneuver.begin_shape_index,
+                    end_shape_index: maneuver.end_shape_index,
+                    length: maneuver.length,
+                    time: maneuver.time,
+                }),
+            }
+        }
+        stretches
+    }
+    /// Shape points from the start to the end of `stretch`, both inclusive
+    ///
+    /// Clamped like [`Self::maneuver_shape`].
+    pub fn stretch_shape(&self, stretch: &TravelModeStretch) -> &[ShapePoint] {
+        self.shape_between(stretch.begin_shape_index, stretch.end_shape_index)
+    }
+    fn shape_between(&self, begin: usize, end: usize) -> &[ShapePoint] {
+        if self.shape.is_empty() {
+            return &[];
+        }
+        let begin = self.clamp_shape_index(begin);
+        let end = self.clamp_shape_index(end).max(begin);
+        &self.shape[begin..=end]
+    }
+    fn clamp_shape_index(&self, index: usize) -> usize {
+        let last = self.shape.len().saturating_sub(1);
+        if index > last {
+            warn!(
+                "shape index {index} exceeds the shape of {} points, clamping it",
+                self.shape.len()
+            );
+        }
+        index.min(last)
+    }
+}
+
 #[cfg(feature = "gpx")]
 impl From<&Leg> for gpx::TrackSegment {
     fn from(leg: &Leg) -> Self {
+        let shape = match (leg.maneuvers.first(), leg.maneuvers.last()) {
+            (Some(first), Some(last)) => {
+                leg.shape_between(first.begin_shape_index, last.end_shape_index)
+            }
+            _ => &leg.shape,
+        };
         Self {
-            points: leg.shape[leg.maneuvers[0].begin_shape_index
-                ..leg.maneuvers[leg.maneuvers.len() - 1].end_shape_index]
+            points: shape
                 .iter()
                 .map(|location| gpx::Waypoint::new(location.into()))
                 .collect(),
@@ -293,6 +608,9 @@ pub struct Maneuver {
     ///
     /// Default: [`BssManeuverType::NoneAction`]
     pub bss_maneuver_type: Option<BssManeuverType>,
+    /// Fields of the maneuver which this crate does not model, see [`Trip::extra`]
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
 }
 
 #[derive(Deserialize, Debug, Clone)]
@@ -364,8 +682,10 @@ pub struct TransitStop {
     /// Example: "14 St - Union Sq"
     pub name: String,
     /// Arrival date and time
+    #[serde(deserialize_with = "crate::deserialize_naive_date_time")]
     pub arrival_date_time: chrono::NaiveDateTime,
     /// Departure date and time
+    #[serde(deserialize_with = "crate::deserialize_naive_date_time")]
     pub departure_date_time: chrono::NaiveDateTime,
     /// `true` if this stop is a marked as a parent stop.
     pub is_parent_stop: bool,
@@ -379,6 +699,7 @@ pub struct TransitStop {
 }
 
 #[derive(Serialize, Default, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub enum DirectionsType {
     /// indicating no maneuvers or instructions should be returned.
     #[serde(rename = "none")]
@@ -396,28 +717,120 @@ pub enum DirectionsType {
 }
 
 #[serde_with::skip_serializing_none]
-#[derive(Serialize, Default, Debug)]
+#[derive(Serialize, Default, Debug, Clone)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct Manifest {
     #[serde(flatten)]
     costing: Option<costing::Costing>,
     locations: Vec<Location>,
     units: Option<super::Units>,
     id: Option<String>,
-    language: Option<String>,
+    #[cfg_attr(feature = "schemars", schemars(with = "Option<String>"))]
+    language: Option<crate::Language>,
     directions_type: Option<DirectionsType>,
     alternates: Option<i32>,
     exclude_locations: Option<Vec<Location>>,
+    #[serde(serialize_with = "crate::precision::serialize_rings_opt")]
     exclude_polygons: Option<Vec<Vec<super::Coordinate>>>,
     linear_references: Option<bool>,
     prioritize_bidirectional: Option<bool>,
     roundabout_exits: Option<bool>,
 }
 
+/// Builds a [`Manifest`] which is guaranteed to have its locations and costing set
+///
+/// Forgetting either is a compile error instead of a request valhalla rejects:
+/// [`Self::locations`] returns a [`ManifestWithLocations`], whose [`ManifestWithLocations::costing`]
+/// finally returns the [`Manifest`] to configure further.
+///
+/// # Example:
+/// ```rust
+/// use valhalla_client::costing::Costing;
+/// use valhalla_client::route::{Location, ManifestBuilder};
+///
+/// let manifest = ManifestBuilder::default()
+///   .locations([Location::new(4.9041, 52.3676), Location::new(5.1214, 52.0907)])
+///   .costing(Costing::Bicycle(Default::default()))
+///   .id("commute");
+/// assert_eq!(manifest.summary().locations, 2);
+/// ```
+#[derive(Debug, Default, Clone)]
+#[must_use]
+pub struct ManifestBuilder(());
+impl ManifestBuilder {
+    /// Specify locations to visit as an ordered list
+    ///
+    /// See [`Manifest::locations`] for details.
+    pub fn locations(self, locations: impl IntoIterator<Item = Location>) -> ManifestWithLocations {
+        ManifestWithLocations(Manifest::default().locations(locations))
+    }
+}
+
+/// A [`ManifestBuilder`] whose locations are set, but not yet its costing
+#[derive(Debug, Clone)]
+#[must_use]
+pub struct ManifestWithLocations(Manifest);
+impl ManifestWithLocations {
+    /// Configures the costing model, completing the [`Manifest`]
+    ///
+    /// See [`Manifest::costing`] for details.
+    pub fn costing(self, costing: costing::Costing) -> Manifest {
+        self.0.costing(costing)
+    }
+}
+
 impl Manifest {
+    /// Starts an empty manifest
+    ///
+    /// Prefer [`ManifestBuilder`], which ensures the locations and costing are set.
     #[must_use]
+    #[deprecated(note = "use `ManifestBuilder`, which ensures the locations and costing are set")]
     pub fn builder() -> Self {
         Self::default()
     }
+    /// Checks that there are at least 2 locations and that the costing options are in range
+    ///
+    /// See [`crate::validation`].
+    pub fn validate(&self) -> Result<(), crate::ValidationError> {
+        self.validate_with(&Default::default())
+    }
+    /// [`Self::validate`], also checking against the limits of the server
+    pub fn validate_with(
+        &self,
+        limits: &crate::validation::ServiceLimits,
+    ) -> Result<(), crate::ValidationError> {
+        let mut validator = crate::validation::Validator::default();
+        validator.min_locations("locations", self.locations.len(), 2);
+        validator.costing(self.costing.as_ref());
+        let count = |max: Option<usize>| max.map(|max| max as f64);
+        validator.at_most(
+            "max_locations",
+            self.locations.len() as f64,
+            count(limits.max_locations),
+        );
+        let distance = self
+            .locations
+            .windows(2)
+            .map(|w| {
+                crate::shapes::crow_fly_distance(
+                    (w[0].longitude, w[0].latitude),
+                    (w[1].longitude, w[1].latitude),
+                )
+            })
+            .sum();
+        validator.at_most("max_distance", distance, limits.max_distance);
+        validator.at_most(
+            "max_exclude_locations",
+            self.exclude_locations.as_ref().map_or(0, Vec::len) as f64,
+            count(limits.max_exclude_locations),
+        );
+        validator.at_most(
+            "max_alternates",
+            f64::from(self.alternates.unwrap_or(0)),
+            limits.max_alternates.map(f64::from),
+        );
+        validator.finish()
+    }
     /// Configures the costing model
     ///
     /// Valhalla's routing service uses dynamic, run-time costing to generate the route path.
@@ -447,7 +860,6 @@ impl Manifest {
     /// See [`LocationType`] for further information.
     pub fn locations(mut self, locations: impl IntoIterator<Item = Location>) -> Self {
         self.locations = locations.into_iter().collect();
-        debug_assert!(self.locations.len() >= 2);
         self
     }
 
@@ -478,9 +890,12 @@ impl Manifest {
     /// Currently supported language list can be found here:
     /// <https://valhalla.github.io/valhalla/api/turn-by-turn/api-reference/#supported-language-tags>
     ///
+    /// Accepts a [`crate::Language`] or a tag string, which is matched case-insensitively against
+    /// the supported tags (see [`crate::Language::is_supported`]).
+    ///
     /// Default: `en-US` (United States-based English)
-    pub fn language(mut self, language: impl ToString) -> Self {
-        self.language = Some(language.to_string());
+    pub fn language(mut self, language: impl Into<crate::Language>) -> Self {
+        self.language = Some(language.into());
         self
     }
     /// Sets the directions type
@@ -503,6 +918,8 @@ impl Manifest {
     /// Alternates are not yet supported on
     /// - multipoint routes (i.e. routes with more than 2 locations) and
     /// - time dependent routes
+    ///
+    /// Use [`crate::Valhalla::route_with_alternates`] to receive them.
     pub fn alternates(mut self, alternates: i32) -> Self {
         self.alternates = Some(alternates);
         self
@@ -512,13 +929,49 @@ impl Manifest {
     ///
     /// They are mapped to the closest road or roads and these roads are excluded
     /// from the route path computation.
+    /// Accepts plain [`super::Coordinate`]s as well.
+    ///
+    /// **Note:** Contrary to [`Self::exclude_location`], this OVERRIDES previously set excluded locations.
     pub fn exclude_locations(
         mut self,
-        exclude_locations: impl IntoIterator<Item = Location>,
+        exclude_locations: impl IntoIterator<Item = impl IntoLocation>,
     ) -> Self {
-        self.exclude_locations = Some(exclude_locations.into_iter().collect());
+        self.exclude_locations = Some(
+            exclude_locations
+                .into_iter()
+                .map(IntoLocation::into_location)
+                .collect(),
+        );
         self
     }
+    /// Add one [`Location`] or [`super::Coordinate`] to exclude or avoid within a route
+    ///
+    /// **Note:** Contrary to [`Self::exclude_locations`], this APPENDS to the previously set excluded locations.
+    ///
+    /// # Example:
+    /// ```rust
+    /// use valhalla_client::costing::Costing;
+    /// use valhalla_client::route::{Location, ManifestBuilder};
+    ///
+    /// let roadworks = (4.9372, 52.2561);
+    /// let manifest = ManifestBuilder::default()
+    ///   .locations([Location::new(4.9041, 52.3676), Location::new(5.1214, 52.0907)])
+    ///   .costing(Costing::default())
+    ///   .exclude_location(roadworks)
+    ///   .exclude_location_within((4.9510, 52.2303), 50);
+    /// ```
+    pub fn exclude_location(mut self, exclude_location: impl IntoLocation) -> Self {
+        self.exclude_locations
+            .get_or_insert_with(Vec::new)
+            .push(exclude_location.into_location());
+        self
+    }
+    /// Like [`Self::exclude_location`], excluding the roads within `radius` meters
+    ///
+    /// See [`Location::radius`].
+    pub fn exclude_location_within(self, exclude_location: impl IntoLocation, radius: i32) -> Self {
+        self.exclude_location(exclude_location.into_location().radius(radius))
+    }
 
     /// Sets at least one exterior rings of excluded polygons.
     ///
@@ -532,7 +985,7 @@ impl Manifest {
     /// # Example:
     /// ```rust,no_run
     /// use valhalla_client::Valhalla;
-    /// use valhalla_client::route::{Location, Manifest};
+    /// use valhalla_client::route::{Location, ManifestBuilder};
     /// use valhalla_client::costing::{Costing};
     ///
     /// let polygon_around_midrecht_between_amsterdam_and_utrecht = vec![(4.9904022, 52.2528761), (4.8431168, 52.2392163), (4.8468933, 52.1799052), (4.9845657, 52.2102016), (4.9904022, 52.2528761)];
@@ -540,10 +993,10 @@ impl Manifest {
     /// let amsterdam = Location::new(4.9041, 52.3676);
     /// let utrecht = Location::new(5.1214, 52.0907);
     ///
-    /// let manifest = Manifest::builder()
+    /// let manifest = ManifestBuilder::default()
     ///   .locations([amsterdam, utrecht])
-    ///   .exclude_polygons([polygon_around_leiden, polygon_around_midrecht_between_amsterdam_and_utrecht])
-    ///   .costing(Costing::Bicycle(Default::default()));
+    ///   .costing(Costing::Bicycle(Default::default()))
+    ///   .exclude_polygons([polygon_around_leiden, polygon_around_midrecht_between_amsterdam_and_utrecht]);
     ///
     /// let response = Valhalla::default()
     ///   .route(manifest)
@@ -573,17 +1026,17 @@ impl Manifest {
     /// # Example:
     /// ```rust,no_run
     /// use valhalla_client::Valhalla;
-    /// use valhalla_client::route::{Location, Manifest};
+    /// use valhalla_client::route::{Location, ManifestBuilder};
     /// use valhalla_client::costing::{Costing};
     ///
     /// let polygon_around_leiden = vec![(4.5891266, 52.1979985),(4.4105987, 52.2560249),(4.3034820, 52.1592721),(4.5005493, 52.0935286),(4.5726471, 52.1373684),(4.5898132, 52.1984193),(4.5891266, 52.1979985)];
     /// let amsterdam = Location::new(4.9041, 52.3676);
     /// let utrecht = Location::new(5.1214, 52.0907);
     ///
-    /// let manifest = Manifest::builder()
+    /// let manifest = ManifestBuilder::default()
     ///   .locations([amsterdam, utrecht])
-    ///   .exclude_polygon(polygon_around_leiden)
-    ///   .costing(Costing::Bicycle(Default::default()));
+    ///   .costing(Costing::Bicycle(Default::default()))
+    ///   .exclude_polygon(polygon_around_leiden);
     ///
     /// let response = Valhalla::default()
     ///   .route(manifest)
@@ -631,9 +1084,167 @@ impl Manifest {
         self.roundabout_exits = Some(false);
         self
     }
+
+    /// Lightweight description of this request, see [`RequestSummary`]
+    pub fn summary(&self) -> RequestSummary {
+        RequestSummary {
+            id: self.id.clone(),
+            locations: self.locations.len(),
+            costing: self.costing.as_ref().map_or("auto", costing::Costing::name),
+            units: self.units.unwrap_or_default(),
+        }
+    }
+}
+
+/// What was asked for in a route request
+///
+/// Returned as part of [`RoutedTrip`] so that downstream code can reason about a result
+/// without threading the [`Manifest`] along separately.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestSummary {
+    /// See [`Manifest::id`]
+    pub id: Option<String>,
+    /// Number of locations in [`Manifest::locations`]
+    pub locations: usize,
+    /// Name of the costing model, see [`costing::Costing::name`]
+    pub costing: &'static str,
+    /// See [`Manifest::units`]
+    pub units: super::Units,
+}
+
+/// A [`Trip`] together with a summary of the request which produced it
+///
+/// Returned by [`crate::Valhalla::route_with_request`].
+#[derive(Debug, Clone)]
+pub struct RoutedTrip {
+    pub trip: Trip,
+    pub request: RequestSummary,
+}
+impl RoutedTrip {
+    /// Whether the server echoed the [`Manifest::id`] of the request
+    ///
+    /// A mismatch indicates that the response was mixed up with another request,
+    /// e.g. by a misbehaving cache in front of the server.
+    pub fn id_matches(&self) -> bool {
+        self.trip.id == self.request.id
+    }
+}
+
+/// Configures how [`crate::Valhalla::diverse_routes`] searches for different routes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DiversityOptions {
+    pub(crate) count: usize,
+    pub(crate) corridor_width: f64,
+    pub(crate) min_distinct_fraction: f64,
+    pub(crate) endpoint_clearance: f64,
+    pub(crate) max_attempts: usize,
+}
+impl DiversityOptions {
+    /// Search for up to `count` routes, including the fastest one
+    pub fn new(count: usize) -> Self {
+        Self {
+            count,
+            corridor_width: 100.0,
+            min_distinct_fraction: 0.3,
+            endpoint_clearance: 500.0,
+            max_attempts: 2 * count,
+        }
+    }
+    /// Width (in meters) of the corridor around a previous route which is excluded when
+    /// searching for the next one.
+    ///
+    /// Also used as the tolerance when comparing routes.
+    ///
+    /// Default: `100` meters
+    pub fn corridor_width(mut self, corridor_width_meters: f64) -> Self {
+        self.corridor_width = corridor_width_meters;
+        self
+    }
+    /// Share of a route's length which has to lie outside the corridors of all previously
+    /// accepted routes for it to be accepted.
+    ///
+    /// This is a range of values between `0` and `1`:
+    /// - `0` accepts any route, even if it is identical to a previous one and
+    /// - `1` only accepts routes sharing no road with previous ones.
+    ///
+    /// Default: `0.3`
+    pub fn min_distinct_fraction(mut self, min_distinct_fraction: f64) -> Self {
+        self.min_distinct_fraction = min_distinct_fraction;
+        self
+    }
+    /// Distance (in meters) at the start and end of each leg which is never excluded.
+    ///
+    /// Excluding the area around a location would make it unreachable.
+    ///
+    /// Default: `500` meters
+    pub fn endpoint_clearance(mut self, endpoint_clearance_meters: f64) -> Self {
+        self.endpoint_clearance = endpoint_clearance_meters;
+        self
+    }
+    /// Upper bound of routing requests made, including rejected candidates.
+    ///
+    /// Default: twice the requested number of routes
+    pub fn max_attempts(mut self, max_attempts: usize) -> Self {
+        self.max_attempts = max_attempts;
+        self
+    }
+    /// Checks that `min_distinct_fraction` is between `0` and `1`
+    ///
+    /// See [`crate::validation`].
+    pub fn validate(&self) -> Result<(), crate::ValidationError> {
+        let mut validator = crate::validation::Validator::default();
+        validator.range(
+            "min_distinct_fraction",
+            self.min_distinct_fraction,
+            0.0,
+            1.0,
+        );
+        validator.finish()
+    }
+    /// Corridors of all legs of `trip`, leaving [`Self::endpoint_clearance`] uncovered
+    pub(crate) fn exclusion_corridors(&self, trip: &Trip) -> Vec<Vec<super::Coordinate>> {
+        trip.legs
+            .iter()
+            .filter_map(|leg| {
+                let length = crate::shapes::length(&leg.shape);
+                let middle = crate::shapes::sub_line(
+                    &leg.shape,
+                    self.endpoint_clearance,
+                    length - self.endpoint_clearance,
+                );
+                if middle.len() < 2 {
+                    return None;
+                }
+                let polygon = crate::shapes::buffer(&middle, self.corridor_width / 2.0);
+                Some(
+                    polygon
+                        .exterior()
+                        .0
+                        .iter()
+                        .map(|c| (c.x as f32, c.y as f32))
+                        .collect(),
+                )
+            })
+            .collect()
+    }
+    /// Whether `candidate` differs enough from each of the `accepted` routes
+    pub(crate) fn is_distinct(&self, candidate: &Trip, accepted: &[Trip]) -> bool {
+        let shape_of = |trip: &Trip| -> Vec<ShapePoint> {
+            trip.legs
+                .iter()
+                .flat_map(|leg| leg.shape.iter().cloned())
+                .collect()
+        };
+        let candidate = shape_of(candidate);
+        accepted.iter().all(|trip| {
+            crate::shapes::distinct_fraction(&candidate, &shape_of(trip), self.corridor_width / 2.0)
+                >= self.min_distinct_fraction
+        })
+    }
 }
 
 #[derive(Serialize, Deserialize, Default, Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub enum LocationType {
     #[default]
     #[serde(rename = "break")]
@@ -650,6 +1261,7 @@ pub enum LocationType {
 }
 
 #[derive(Serialize, Deserialize, Default, Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub enum Side {
     #[serde(rename = "same")]
     Same,
@@ -672,21 +1284,29 @@ impl From<&Location> for gpx::Waypoint {
         p
     }
 }
-impl From<super::Coordinate> for Location {
-    fn from((latitude, longitude): super::Coordinate) -> Self {
-        Self {
-            latitude,
-            longitude,
-            ..Default::default()
-        }
+
+/// A [`Location`], or a longitude, latitude [`super::Coordinate`] of one
+///
+/// Accepted where a plain coordinate is enough, e.g. [`Manifest::exclude_location`].
+pub trait IntoLocation {
+    fn into_location(self) -> Location;
+}
+impl IntoLocation for Location {
+    fn into_location(self) -> Location {
+        self
+    }
+}
+impl IntoLocation for super::Coordinate {
+    fn into_location(self) -> Location {
+        Location::from_lon_lat(self)
     }
 }
 
 impl Location {
-    /// Create a Location from latitude/longitude of the location in degrees.
+    /// Create a Location from longitude/latitude of the location in degrees.
     ///
     /// This is assumed to be both routing location and display location is equal.
-    /// See [`Self::display_coordinates`] to change the display location
+    /// See [`Self::display_location`] to change the display location
     pub fn new(longitude: f32, latitude: f32) -> Self {
         Self {
             latitude,
@@ -694,15 +1314,36 @@ impl Location {
             ..Default::default()
         }
     }
+    /// Create a Location from a longitude, latitude [`super::Coordinate`]
+    ///
+    /// Replaces the `From<Coordinate>` conversion of 0.3, which read the latitude first unlike
+    /// the rest of this crate.
+    /// It was removed instead of changed, so code relying on the old order fails to compile
+    /// instead of routing to the wrong place.
+    pub fn from_lon_lat((longitude, latitude): super::Coordinate) -> Self {
+        Self::new(longitude, latitude)
+    }
     /// Display Coordinate location in degrees.
     ///
     /// Will be used to determine the side of street.
     /// Must be valid to achieve the desired effect.
+    #[deprecated(
+        note = "takes the latitude first, unlike the rest of this crate; use `display_location(longitude, latitude)` instead"
+    )]
     pub fn display_coordinates(mut self, display_lat: f32, display_lon: f32) -> Self {
         self.display_lat = Some(display_lat);
         self.display_lon = Some(display_lon);
         self
     }
+    /// Display location in degrees.
+    ///
+    /// Will be used to determine the side of street.
+    /// Must be valid to achieve the desired effect.
+    pub fn display_location(mut self, display_longitude: f32, display_latitude: f32) -> Self {
+        self.display_lat = Some(display_latitude);
+        self.display_lon = Some(display_longitude);
+        self
+    }
 
     /// Sets the Street name.
     ///
@@ -897,15 +1538,40 @@ impl Location {
         self.street_side_cutoff = Some(street_side_cutoff);
         self
     }
+    /// Time in seconds spent at this location, e.g. to deliver a parcel
+    ///
+    /// The departure from the location is delayed by this amount.
+    /// Only honoured for [`LocationType::Break`] and [`LocationType::BreakThrough`] locations.
+    /// See [`Trip::service_windows`] for the resulting arrival and departure times.
+    ///
+    /// Default: `0` seconds
+    pub fn waiting(mut self, waiting_secs: u32) -> Self {
+        self.waiting = Some(waiting_secs);
+        self
+    }
+    /// The waiting time configured via [`Self::waiting`], as echoed in [`Trip::locations`]
+    pub fn waiting_secs(&self) -> Option<u32> {
+        self.waiting
+    }
+    /// Local departure time at this location
+    ///
+    /// Only set on [`Trip::locations`] of time dependent routes, where valhalla reports when each
+    /// location is passed.
+    pub fn date_time(&self) -> Option<chrono::NaiveDateTime> {
+        self.date_time
+    }
 }
 
 #[derive(Serialize, Deserialize, Default, Clone, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct Location {
-    #[serde(rename = "lat")]
+    #[serde(rename = "lat", serialize_with = "crate::precision::serialize_f32")]
     latitude: f32,
-    #[serde(rename = "lon")]
+    #[serde(rename = "lon", serialize_with = "crate::precision::serialize_f32")]
     longitude: f32,
+    #[serde(serialize_with = "crate::precision::serialize_f32_opt")]
     display_lat: Option<f32>,
+    #[serde(serialize_with = "crate::precision::serialize_f32_opt")]
     display_lon: Option<f32>,
     street: Option<String>,
     way_id: Option<i64>,
@@ -923,11 +1589,51 @@ pub struct Location {
     street_side_tolerance: Option<f32>,
     street_side_max_distance: Option<f32>,
     street_side_cutoff: Option<f32>,
+    waiting: Option<u32>,
+    #[serde(
+        default,
+        skip_serializing,
+        deserialize_with = "crate::deserialize_naive_date_time_opt"
+    )]
+    #[cfg_attr(feature = "schemars", schemars(skip))]
+    date_time: Option<chrono::NaiveDateTime>,
 }
 
 #[cfg(test)]
-mod test {
+pub(crate) mod test {
     use super::*;
+
+    /// A leg or trip summary taking `time` seconds for `length` units
+    pub(crate) fn summary(time: f64, length: f64) -> serde_json::Value {
+        serde_json::json!({
+            "time": time, "length": length, "has_toll": false, "has_highway": false,
+            "has_ferry": false, "min_lat": 0.0, "min_lon": 0.0, "max_lat": 0.0, "max_lon": 0.0
+        })
+    }
+
+    /// A trip without locations consisting of `legs`, as valhalla sends it
+    ///
+    /// Left as JSON, so that tests can still change it before parsing.
+    pub(crate) fn trip_with_legs(
+        legs: impl IntoIterator<Item = serde_json::Value>,
+    ) -> serde_json::Value {
+        let legs: Vec<_> = legs.into_iter().collect();
+        let total = |field: &str| -> f64 {
+            legs.iter()
+                .filter_map(|leg| leg["summary"][field].as_f64())
+                .sum()
+        };
+        serde_json::json!({
+            "status": 0,
+            "status_message": "Found route between points",
+            "units": "kilometers",
+            "language": "en-US",
+            "locations": [],
+            "summary": summary(total("time"), total("length")),
+            "legs": legs
+        })
+    }
+
     #[test]
     fn serialisation() {
         assert_eq!(
@@ -935,4 +1641,274 @@ mod test {
             serde_json::json!({"locations": []})
         );
     }
+
+    #[test]
+    fn maneuver_shape_is_clamped() {
+        let maneuver: Maneuver = serde_json::from_value(serde_json::json!({
+            "type": 1,
+            "instruction": "Drive east.",
+            "time": 1.0,
+            "length": 0.1,
+            "begin_shape_index": 1,
+            "end_shape_index": 7,
+            "travel_mode": "drive"
+        }))
+        .unwrap();
+        let leg = Leg {
+            summary: serde_json::from_value(summary(1.0, 0.1)).unwrap(),
+            shape: vec![
+                ShapePoint { lon: 0.0, lat: 0.0 },
+                ShapePoint { lon: 1.0, lat: 0.0 },
+                ShapePoint { lon: 2.0, lat: 0.0 },
+            ],
+            maneuvers: vec![maneuver.clone()],
+        };
+        assert_eq!(leg.maneuver_shape(&maneuver), &leg.shape[1..]);
+        assert_eq!(leg.shape_point(42), leg.shape.last());
+
+        let empty = Leg {
+            shape: vec![],
+            ..leg
+        };
+        assert!(empty.maneuver_shape(&maneuver).is_empty());
+        assert_eq!(empty.shape_point(0), None);
+    }
+
+    #[test]
+    fn unknown_fields_are_kept() {
+        let maneuver: Maneuver = serde_json::from_value(serde_json::json!({
+            "type": 1,
+            "instruction": "Drive east.",
+            "time": 1.0,
+            "length": 0.1,
+            "begin_shape_index": 0,
+            "end_shape_index": 1,
+            "travel_mode": "drive",
+            "lanes": [{"directions": 1}]
+        }))
+        .unwrap();
+        assert_eq!(maneuver.extra.len(), 1);
+        assert_eq!(
+            maneuver.extra["lanes"],
+            serde_json::json!([{"directions": 1}])
+        );
+        assert!(!maneuver.extra.contains_key("instruction"));
+    }
+
+    #[test]
+    fn coordinates_are_longitude_first() {
+        let amsterdam: crate::Coordinate = (4.9041, 52.3676);
+        let expected = serde_json::json!({"lon": 4.9041, "lat": 52.3676});
+        let from_tuple = serde_json::to_value(Location::from_lon_lat(amsterdam)).unwrap();
+        let from_new = serde_json::to_value(Location::new(4.9041, 52.3676)).unwrap();
+        let from_matrix = serde_json::to_value(crate::matrix::Location::from(amsterdam)).unwrap();
+        for value in [&from_tuple, &from_new, &from_matrix] {
+            assert_eq!(value["lon"], expected["lon"]);
+            assert_eq!(value["lat"], expected["lat"]);
+        }
+        let point = ShapePoint::from(amsterdam);
+        assert_eq!((point.lon as f32, point.lat as f32), amsterdam);
+        assert_eq!(crate::Coordinate::from(point), amsterdam);
+
+        let display =
+            serde_json::to_value(Location::new(0.0, 0.0).display_location(4.9, 52.4)).unwrap();
+        assert_eq!(display["display_lon"], serde_json::json!(4.9));
+        assert_eq!(display["display_lat"], serde_json::json!(52.4));
+    }
+
+    #[test]
+    fn exclude_locations() {
+        let manifest = Manifest::default()
+            .exclude_locations([(4.9, 52.4)])
+            .exclude_location(Location::new(5.0, 52.3))
+            .exclude_location_within((5.1, 52.2), 50);
+        let json = serde_json::to_value(manifest).unwrap();
+        let excluded: Vec<_> = json["exclude_locations"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|l| (l["lon"].as_f64(), l["lat"].as_f64(), l["radius"].as_i64()))
+            .collect();
+        assert_eq!(
+            excluded,
+            [
+                (Some(4.9), Some(52.4), None),
+                (Some(5.0), Some(52.3), None),
+                (Some(5.1), Some(52.2), Some(50))
+            ]
+        );
+    }
+
+    #[test]
+    fn request_summary() {
+        let manifest = ManifestBuilder::default()
+            .locations([Location::new(4.9, 52.4), Location::new(5.1, 52.1)])
+            .costing(costing::Costing::Pedestrian(Default::default()))
+            .units(crate::Units::Imperial)
+            .id("walk");
+        assert_eq!(
+            manifest.summary(),
+            RequestSummary {
+                id: Some("walk".to_string()),
+                locations: 2,
+                costing: "pedestrian",
+                units: crate::Units::Imperial,
+            }
+        );
+        assert_eq!(Manifest::default().summary().costing, "auto");
+    }
+
+    #[test]
+    fn leg_aggregates() {
+        let maneuver = |length: f64, flags: serde_json::Value| {
+            let mut maneuver = serde_json::json!({
+                "type": 1, "instruction": "", "time": 0.0, "length": length,
+                "begin_shape_index": 0, "end_shape_index": 0, "travel_mode": "drive"
+            });
+            maneuver
+                .as_object_mut()
+                .unwrap()
+                .extend(flags.as_object().unwrap().clone());
+            maneuver
+        };
+        let leg: Leg = serde_json::from_value(serde_json::json!({
+            "summary": {
+                "time": 0.0, "length": 0.0, "has_toll": true, "has_highway": false,
+                "has_ferry": true, "min_lat": 0.0, "min_lon": 0.0, "max_lat": 0.0, "max_lon": 0.0
+            },
+            "maneuvers": [
+                maneuver(1.5, serde_json::json!({"toll": true})),
+                maneuver(2.0, serde_json::json!({"ferry": true})),
+                maneuver(0.5, serde_json::json!({"ferry": true, "toll": true})),
+                maneuver(3.0, serde_json::json!({})),
+                maneuver(1.0, serde_json::json!({"ferry": true})),
+            ],
+            "shape": ""
+        }))
+        .unwrap();
+        assert!(leg.has_toll());
+        assert!(!leg.has_gate());
+        assert_eq!(leg.tolled_length(), 2.0);
+        assert_eq!(leg.ferry_count(), 2);
+    }
+
+    /// Walk, ride transit and walk again
+    fn multimodal_leg() -> serde_json::Value {
+        let maneuver = |mode: &str, begin: usize, end: usize| {
+            serde_json::json!({
+                "type": 1, "instruction": format!("{mode} {begin}"), "time": 60.0, "length": 0.5,
+                "begin_shape_index": begin, "end_shape_index": end, "travel_mode": mode
+            })
+        };
+        serde_json::json!({
+            "summary": summary(0.0, 0.0),
+            "maneuvers": [
+                maneuver("pedestrian", 0, 1),
+                maneuver("pedestrian", 1, 2),
+                maneuver("transit", 2, 5),
+                maneuver("pedestrian", 5, 6),
+                maneuver("pedestrian", 6, 6),
+            ],
+            "shape": "_p~iF~ps|U_ulLnnqC_mqNvxq`@_p~iF~ps|U_ulLnnqC_mqNvxq`@_p~iF~ps|U"
+        })
+    }
+
+    #[test]
+    fn travel_mode_stretches() {
+        let leg: Leg = serde_json::from_value(multimodal_leg()).unwrap();
+        let stretches = leg.travel_mode_stretches();
+        assert_eq!(
+            stretches
+                .iter()
+                .map(|s| (
+                    s.travel_mode,
+                    s.maneuvers.clone(),
+                    s.begin_shape_index,
+                    s.end_shape_index
+                ))
+                .collect::<Vec<_>>(),
+            [
+                (TravelMode::Pedestrian, 0..2, 0, 2),
+                (TravelMode::Transit, 2..3, 2, 5),
+                (TravelMode::Pedestrian, 3..5, 5, 6),
+            ]
+        );
+        assert_eq!((stretches[0].length, stretches[0].time), (1.0, 120.0));
+        assert_eq!(leg.stretch_shape(&stretches[1]).len(), 4);
+    }
+
+    #[test]
+    fn service_windows() {
+        let mut trip = trip_with_legs([
+            serde_json::json!({"summary": summary(600.0, 1.0), "maneuvers": [], "shape": ""}),
+            serde_json::json!({"summary": summary(900.0, 1.0), "maneuvers": [], "shape": ""}),
+        ]);
+        trip["locations"] = serde_json::json!([
+            {"lat": 52.37, "lon": 4.90, "type": "break", "date_time": "2024-11-07T15:00"},
+            {"lat": 52.30, "lon": 4.95, "type": "via"},
+            {"lat": 52.20, "lon": 5.00, "type": "break", "waiting": 300},
+            {"lat": 52.09, "lon": 5.12, "type": "break"}
+        ]);
+        let trip: Trip = serde_json::from_value(trip).unwrap();
+        assert_eq!(trip.locations[2].waiting_secs(), Some(300));
+        let at = |h, m| {
+            chrono::NaiveDate::from_ymd_opt(2024, 11, 7)
+                .unwrap()
+                .and_hms_opt(h, m, 0)
+        };
+        let windows = trip.service_windows();
+        assert_eq!(
+            windows
+                .iter()
+                .map(|w| (w.location_index, w.arrival, w.departure))
+                .collect::<Vec<_>>(),
+            [(0, 0.0, 0.0), (2, 600.0, 900.0), (3, 1500.0, 1500.0)]
+        );
+        assert_eq!(windows[1].arrival_date_time, at(15, 10));
+        assert_eq!(windows[1].departure_date_time, at(15, 15));
+    }
+
+    #[test]
+    fn lenient_legs() {
+        let trip = trip_with_legs([
+            serde_json::json!({"summary": summary(1.0, 1.0), "maneuvers": [], "shape": 42}),
+            serde_json::json!({"summary": summary(1.0, 1.0), "maneuvers": [], "shape": ""}),
+        ]);
+        assert!(serde_json::from_value::<Trip>(trip.clone()).is_err());
+        let trip = Trip::from_value_lenient(trip).unwrap();
+        assert_eq!(trip.legs.len(), 1);
+        assert_eq!(trip.parse_warnings.len(), 1);
+        assert!(trip.parse_warnings[0].starts_with("leg 0: "));
+        // e.g. mangled by a proxy
+        assert!(Trip::from_value_lenient(serde_json::json!("trip")).is_err());
+        assert!(Trip::from_value_lenient(serde_json::json!([])).is_err());
+    }
+
+    #[cfg(feature = "gpx")]
+    #[test]
+    fn gpx_by_travel_mode() {
+        let trip: Trip =
+            serde_json::from_value(trip_with_legs([multimodal_leg(), multimodal_leg()])).unwrap();
+        let gpx = gpx::Gpx::from(trip);
+        let tracks: Vec<_> = gpx
+            .tracks
+            .iter()
+            .map(|t| (t.type_.as_deref().unwrap(), t.segments.len()))
+            .collect();
+        assert_eq!(
+            tracks,
+            [
+                ("pedestrian", 1),
+                ("transit", 1),
+                ("pedestrian", 2),
+                ("transit", 1),
+                ("pedestrian", 1)
+            ]
+        );
+        let points = &gpx.routes[0].points;
+        assert_eq!(points.len(), 10);
+        assert_eq!(points[2].comment.as_deref(), Some("transit 2"));
+        assert_eq!(points[2].description.as_deref(), Some("+0:02:00"));
+        assert_eq!(points[9].description.as_deref(), Some("+0:09:00"));
+    }
 }
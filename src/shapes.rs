@@ -2,6 +2,7 @@ use serde::{Deserialize, Serialize};
 
 /// Specifies the optional format for the path shape of each connection
 #[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub enum ShapeFormat {
     #[serde(rename = "polyline6")]
     Polyline6,
@@ -14,6 +15,7 @@ pub enum ShapeFormat {
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct ShapePoint {
     pub lon: f64,
     pub lat: f64,
@@ -25,6 +27,18 @@ impl From<&ShapePoint> for geo_types::Point {
     }
 }
 
+impl From<geo_types::Coord> for ShapePoint {
+    fn from(c: geo_types::Coord) -> Self {
+        Self { lon: c.x, lat: c.y }
+    }
+}
+
+impl From<geo_types::Point> for ShapePoint {
+    fn from(p: geo_types::Point) -> Self {
+        p.0.into()
+    }
+}
+
 impl From<ShapePoint> for super::Coordinate {
     fn from(p: ShapePoint) -> Self {
         (p.lon as f32, p.lat as f32)
@@ -56,8 +70,8 @@ fn decode_shape(encoded: &str) -> Vec<ShapePoint> {
         }
 
         decoded.push(ShapePoint {
-            lon: -1.0 * f64::from(ll[1]) * inv,
-            lat: -1.0 * f64::from(ll[0]) * inv,
+            lon: -f64::from(ll[1]) * inv,
+            lat: -f64::from(ll[0]) * inv,
         });
     }
 
@@ -70,3 +84,459 @@ where
     let s = String::deserialize(deserializer)?;
     Ok(decode_shape(s.as_str()))
 }
+
+/// Mean earth radius in meters, as used by Valhalla
+const EARTH_RADIUS_METERS: f64 = 6_371_008.8;
+/// Number of segments used to approximate a half circle when buffering
+const HALF_CIRCLE_SEGMENTS: usize = 8;
+
+/// Equirectangular projection to meters around a reference point
+///
+/// Accurate enough for the extent of a route, but not for continent-sized geometries.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct LocalProjection {
+    origin_lon: f64,
+    origin_lat: f64,
+    cos_lat: f64,
+}
+impl LocalProjection {
+    pub(crate) fn new(origin: &ShapePoint) -> Self {
+        Self {
+            origin_lon: origin.lon,
+            origin_lat: origin.lat,
+            cos_lat: origin.lat.to_radians().cos(),
+        }
+    }
+    pub(crate) fn project(&self, p: &ShapePoint) -> (f64, f64) {
+        (
+            (p.lon - self.origin_lon).to_radians() * EARTH_RADIUS_METERS * self.cos_lat,
+            (p.lat - self.origin_lat).to_radians() * EARTH_RADIUS_METERS,
+        )
+    }
+    pub(crate) fn unproject(&self, (x, y): (f64, f64)) -> geo_types::Coord {
+        geo_types::Coord {
+            x: self.origin_lon + (x / (EARTH_RADIUS_METERS * self.cos_lat)).to_degrees(),
+            y: self.origin_lat + (y / EARTH_RADIUS_METERS).to_degrees(),
+        }
+    }
+}
+
+/// Length of a line in meters
+pub(crate) fn length(shape: &[ShapePoint]) -> f64 {
+    let Some(origin) = shape.first() else {
+        return 0.0;
+    };
+    let projection = LocalProjection::new(origin);
+    shape
+        .windows(2)
+        .map(|w| {
+            let (ax, ay) = projection.project(&w[0]);
+            let (bx, by) = projection.project(&w[1]);
+            (bx - ax).hypot(by - ay)
+        })
+        .sum()
+}
+
+/// Great-circle distance between two coordinates in meters
+pub(crate) fn crow_fly_distance(a: crate::Coordinate, b: crate::Coordinate) -> f64 {
+    let (a_lon, a_lat) = (f64::from(a.0).to_radians(), f64::from(a.1).to_radians());
+    let (b_lon, b_lat) = (f64::from(b.0).to_radians(), f64::from(b.1).to_radians());
+    let h = ((b_lat - a_lat) / 2.0).sin().powi(2)
+        + a_lat.cos() * b_lat.cos() * ((b_lon - a_lon) / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_METERS * h.sqrt().min(1.0).asin()
+}
+
+/// The part of a line between `from` and `to` meters along it
+pub(crate) fn sub_line(shape: &[ShapePoint], from: f64, to: f64) -> Vec<ShapePoint> {
+    let Some(origin) = shape.first() else {
+        return vec![];
+    };
+    let projection = LocalProjection::new(origin);
+    let interpolate = |a: &ShapePoint, b: &ShapePoint, fraction: f64| ShapePoint {
+        lon: a.lon + (b.lon - a.lon) * fraction,
+        lat: a.lat + (b.lat - a.lat) * fraction,
+    };
+    let mut result = Vec::new();
+    let mut travelled = 0.0;
+    for w in shape.windows(2) {
+        let (ax, ay) = projection.project(&w[0]);
+        let (bx, by) = projection.project(&w[1]);
+        let segment = (bx - ax).hypot(by - ay);
+        let (start, end) = (travelled, travelled + segment);
+        travelled = end;
+        if end < from || start > to || segment == 0.0 {
+            continue;
+        }
+        if result.is_empty() {
+            result.push(interpolate(
+                &w[0],
+                &w[1],
+                ((from - start) / segment).max(0.0),
+            ));
+        }
+        if end > to {
+            result.push(interpolate(&w[0], &w[1], (to - start) / segment));
+            break;
+        }
+        result.push(w[1].clone());
+    }
+    result
+}
+
+/// Distance of `p` to the segment from `a` to `b`, in projected coordinates
+fn segment_distance(p: (f64, f64), a: (f64, f64), b: (f64, f64)) -> f64 {
+    squared_segment_distance(p, a, b).sqrt()
+}
+/// Square of [`segment_distance`], which is cheaper to compare
+fn squared_segment_distance(
+    (px, py): (f64, f64),
+    (ax, ay): (f64, f64),
+    (bx, by): (f64, f64),
+) -> f64 {
+    let (dx, dy) = (bx - ax, by - ay);
+    let squared_length = dx * dx + dy * dy;
+    let t = if squared_length == 0.0 {
+        0.0
+    } else {
+        (((px - ax) * dx + (py - ay) * dy) / squared_length).clamp(0.0, 1.0)
+    };
+    let (ex, ey) = (px - (ax + t * dx), py - (ay + t * dy));
+    ex * ex + ey * ey
+}
+
+/// Half the size of a web mercator pixel at the equator in meters, per zoom level `0..=22`
+///
+/// Deviations below half a pixel are invisible on a map.
+const ZOOM_TOLERANCES_METERS: [f64; 23] = [
+    78_271.5, 39_135.8, 19_567.9, 9_783.9, 4_892.0, 2_446.0, 1_223.0, 611.5, 305.7, 152.9, 76.4,
+    38.2, 19.1, 9.55, 4.78, 2.39, 1.19, 0.597, 0.299, 0.149, 0.075, 0.037, 0.019,
+];
+
+/// Smallest rectangle containing all points of `shape`
+///
+/// `None` if the shape is empty.
+/// Useful to fit a map's viewport to a route.
+pub fn bounding_box(shape: &[ShapePoint]) -> Option<geo_types::Rect> {
+    let first = shape.first()?;
+    let (mut min, mut max) = (first.clone(), first.clone());
+    for p in shape {
+        min.lon = min.lon.min(p.lon);
+        min.lat = min.lat.min(p.lat);
+        max.lon = max.lon.max(p.lon);
+        max.lat = max.lat.max(p.lat);
+    }
+    Some(geo_types::Rect::new(
+        geo_types::coord! { x: min.lon, y: min.lat },
+        geo_types::coord! { x: max.lon, y: max.lat },
+    ))
+}
+
+/// Simplifies `shape` with the [Douglas-Peucker algorithm](https://en.wikipedia.org/wiki/Ramer%E2%80%93Douglas%E2%80%93Peucker_algorithm)
+///
+/// Removes points until no removed point is further than `tolerance` meters from the
+/// simplified line.
+/// The first and last point are always kept.
+pub fn simplify(shape: &[ShapePoint], tolerance: f64) -> Vec<ShapePoint> {
+    let Some(origin) = shape.first() else {
+        return vec![];
+    };
+    let projection = LocalProjection::new(origin);
+    let projected: Vec<(f64, f64)> = shape.iter().map(|p| projection.project(p)).collect();
+    let mut keep = vec![false; shape.len()];
+    keep[0] = true;
+    keep[shape.len() - 1] = true;
+    let mut ranges = vec![(0, shape.len() - 1)];
+    while let Some((start, end)) = ranges.pop() {
+        let (a, b) = (projected[start], projected[end]);
+        let furthest = (start + 1..end)
+            .map(|i| (i, squared_segment_distance(projected[i], a, b)))
+            .max_by(|a, b| a.1.total_cmp(&b.1));
+        if let Some((i, squared_distance)) = furthest {
+            if squared_distance > tolerance * tolerance {
+                keep[i] = true;
+                ranges.push((start, i));
+                ranges.push((i, end));
+            }
+        }
+    }
+    shape
+        .iter()
+        .zip(keep)
+        .filter(|&(_, keep)| keep)
+        .map(|(p, _)| p.clone())
+        .collect()
+}
+
+/// Simplifies `shape` for display on a web mercator map at `zoom`
+///
+/// Mobile map renderers waste a lot of time on the tens of thousands of points of a long
+/// route, most of which fall onto the same pixel when zoomed out.
+/// This keeps only the points which make a visible difference at `zoom` (`0..=22`, larger
+/// values are treated as `22`), see [`simplify`].
+///
+/// # Example:
+/// ```rust
+/// use valhalla_client::shapes::{simplify_for_zoom, ShapePoint};
+///
+/// let shape: Vec<ShapePoint> = (0..=1000)
+///     .map(|i| ShapePoint { lon: 4.9 + i as f64 * 1e-4, lat: 52.37 + (i % 2) as f64 * 1e-6 })
+///     .collect();
+/// assert_eq!(simplify_for_zoom(&shape, 10).len(), 2);
+/// assert_eq!(simplify_for_zoom(&shape, 22).len(), shape.len());
+/// ```
+pub fn simplify_for_zoom(shape: &[ShapePoint], zoom: u8) -> Vec<ShapePoint> {
+    let Some(first) = shape.first() else {
+        return vec![];
+    };
+    // web mercator pixels shrink towards the poles
+    let tolerance =
+        ZOOM_TOLERANCES_METERS[usize::from(zoom.min(22))] * first.lat.to_radians().cos();
+    // Douglas-Peucker is quadratic in the worst case; dropping points which fall onto the same
+    // pixel as their predecessor first makes it cheap for dense shapes
+    let projection = LocalProjection::new(first);
+    let mut previous = projection.project(first);
+    let mut thinned = vec![first.clone()];
+    for (i, p) in shape.iter().enumerate().skip(1) {
+        let (x, y) = projection.project(p);
+        if (x - previous.0).hypot(y - previous.1) > tolerance || i == shape.len() - 1 {
+            thinned.push(p.clone());
+            previous = (x, y);
+        }
+    }
+    simplify(&thinned, tolerance)
+}
+
+/// Fraction (by length) of `shape` which is further than `distance` meters away from `other`
+pub(crate) fn distinct_fraction(shape: &[ShapePoint], other: &[ShapePoint], distance: f64) -> f64 {
+    let Some(origin) = shape.first() else {
+        return 0.0;
+    };
+    let projection = LocalProjection::new(origin);
+    let other: Vec<(f64, f64)> = other.iter().map(|p| projection.project(p)).collect();
+    let distance_to_other = |(px, py): (f64, f64)| {
+        if other.len() == 1 {
+            return (px - other[0].0).hypot(py - other[0].1);
+        }
+        other
+            .windows(2)
+            .map(|w| segment_distance((px, py), w[0], w[1]))
+            .fold(f64::INFINITY, f64::min)
+    };
+    let mut total = 0.0;
+    let mut distinct = 0.0;
+    for w in shape.windows(2) {
+        let (ax, ay) = projection.project(&w[0]);
+        let (bx, by) = projection.project(&w[1]);
+        let segment = (bx - ax).hypot(by - ay);
+        total += segment;
+        if other.is_empty() || distance_to_other(((ax + bx) / 2.0, (ay + by) / 2.0)) > distance {
+            distinct += segment;
+        }
+    }
+    if total == 0.0 {
+        0.0
+    } else {
+        distinct / total
+    }
+}
+
+/// Buffers a line by `distance` meters on both sides.
+///
+/// The line is projected onto a local equirectangular plane, which is accurate enough for the
+/// distances of a route corridor.
+/// Joins are mitred (bevelled at sharp angles) and both ends get round caps.
+/// Turns sharper than the buffer is wide may produce a self-intersecting ring.
+pub(crate) fn buffer(shape: &[ShapePoint], distance: f64) -> geo_types::Polygon {
+    let Some(origin) = shape.first() else {
+        return geo_types::Polygon::new(geo_types::LineString::new(vec![]), vec![]);
+    };
+    let projection = LocalProjection::new(origin);
+    let project = |p: &ShapePoint| projection.project(p);
+    let unproject = |p: (f64, f64)| projection.unproject(p);
+
+    let mut points: Vec<(f64, f64)> = Vec::with_capacity(shape.len());
+    for (x, y) in shape.iter().map(project) {
+        if points
+            .last()
+            .is_none_or(|&(px, py)| (x - px).hypot(y - py) > 1e-6)
+        {
+            points.push((x, y));
+        }
+    }
+    let arc = |center: (f64, f64), from_angle: f64| {
+        (1..HALF_CIRCLE_SEGMENTS).map(move |k| {
+            let angle = from_angle - std::f64::consts::PI * k as f64 / HALF_CIRCLE_SEGMENTS as f64;
+            (
+                center.0 + distance * angle.cos(),
+                center.1 + distance * angle.sin(),
+            )
+        })
+    };
+    if points.len() == 1 {
+        let center = points[0];
+        let ring = arc(center, 0.0)
+            .chain([(center.0 - distance, center.1)])
+            .chain(arc(center, std::f64::consts::PI))
+            .chain([(center.0 + distance, center.1)])
+            .map(unproject)
+            .collect::<Vec<_>>();
+        return geo_types::Polygon::new(geo_types::LineString::new(ring), vec![]);
+    }
+
+    // unit normals pointing to the left of each segment
+    let normals: Vec<(f64, f64)> = points
+        .windows(2)
+        .map(|w| {
+            let (dx, dy) = (w[1].0 - w[0].0, w[1].1 - w[0].1);
+            let length = dx.hypot(dy);
+            (-dy / length, dx / length)
+        })
+        .collect();
+    let offset = |side: f64| {
+        let mut line = Vec::with_capacity(points.len());
+        for (i, &(x, y)) in points.iter().enumerate() {
+            let prev = normals[i.saturating_sub(1)];
+            let next = normals[i.min(normals.len() - 1)];
+            let cos_angle = prev.0 * next.0 + prev.1 * next.1;
+            if cos_angle < 0.0 {
+                // bevel instead of producing an arbitrarily long mitre
+                line.push((x + side * distance * prev.0, y + side * distance * prev.1));
+                line.push((x + side * distance * next.0, y + side * distance * next.1));
+            } else {
+                let (mx, my) = (prev.0 + next.0, prev.1 + next.1);
+                let scale = side * distance * 2.0 / (mx * mx + my * my);
+                line.push((x + mx * scale, y + my * scale));
+            }
+        }
+        line
+    };
+    let angle_of = |(nx, ny): (f64, f64)| ny.atan2(nx);
+
+    let mut ring = offset(1.0);
+    ring.extend(arc(
+        points[points.len() - 1],
+        angle_of(normals[normals.len() - 1]),
+    ));
+    ring.extend(offset(-1.0).into_iter().rev());
+    ring.extend(arc(points[0], angle_of(normals[0]) + std::f64::consts::PI));
+    let ring = ring.into_iter().map(unproject).collect::<Vec<_>>();
+    geo_types::Polygon::new(geo_types::LineString::new(ring), vec![])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn simplification() {
+        let point = |lon, lat| ShapePoint { lon, lat };
+        // ~11m wiggles around a ~1km detour in the middle
+        let shape = [
+            point(0.0, 0.0),
+            point(0.01, 0.0001),
+            point(0.02, 0.0),
+            point(0.03, 0.01),
+            point(0.04, 0.0),
+            point(0.05, 0.0001),
+            point(0.06, 0.0),
+        ];
+        assert_eq!(simplify(&shape, 0.0).len(), shape.len());
+        assert_eq!(
+            simplify(&shape, 20.0),
+            [
+                point(0.0, 0.0),
+                point(0.02, 0.0),
+                point(0.03, 0.01),
+                point(0.04, 0.0),
+                point(0.06, 0.0)
+            ]
+        );
+        assert_eq!(simplify_for_zoom(&shape, 0).len(), 2);
+        assert_eq!(simplify_for_zoom(&shape[..1], 0), [point(0.0, 0.0)]);
+        assert!(simplify(&[], 1.0).is_empty());
+
+        let bbox = bounding_box(&shape).unwrap();
+        assert_eq!(bbox.min(), geo_types::coord! { x: 0.0, y: 0.0 });
+        assert_eq!(bbox.max(), geo_types::coord! { x: 0.06, y: 0.01 });
+        assert_eq!(bounding_box(&[]), None);
+    }
+    #[test]
+    fn buffer_straight_line() {
+        // ~1.1km due east along the equator
+        let line = [
+            ShapePoint { lon: 0.0, lat: 0.0 },
+            ShapePoint {
+                lon: 0.005,
+                lat: 0.0,
+            },
+            ShapePoint {
+                lon: 0.01,
+                lat: 0.0,
+            },
+        ];
+        let polygon = buffer(&line, 10.0);
+        let ys: Vec<f64> = polygon.exterior().0.iter().map(|c| c.y).collect();
+        let max_lat = ys.iter().copied().fold(f64::MIN, f64::max);
+        let min_lat = ys.iter().copied().fold(f64::MAX, f64::min);
+        let ten_meters_in_degrees = (10.0 / EARTH_RADIUS_METERS).to_degrees();
+        assert!((max_lat - ten_meters_in_degrees).abs() < 1e-9);
+        assert!((min_lat + ten_meters_in_degrees).abs() < 1e-9);
+        let xs: Vec<f64> = polygon.exterior().0.iter().map(|c| c.x).collect();
+        assert!(xs.iter().copied().fold(f64::MAX, f64::min) < 0.0);
+        assert!(xs.iter().copied().fold(f64::MIN, f64::max) > 0.01);
+        assert_eq!(
+            polygon.exterior().0.first(),
+            polygon.exterior().0.last(),
+            "ring must be closed"
+        );
+    }
+
+    #[test]
+    fn buffer_single_point() {
+        let polygon = buffer(
+            &[ShapePoint {
+                lon: 5.0,
+                lat: 52.0,
+            }],
+            10.0,
+        );
+        assert_eq!(polygon.exterior().0.len(), 2 * HALF_CIRCLE_SEGMENTS + 1);
+    }
+
+    #[test]
+    fn sub_line_and_distinct_fraction() {
+        let line = [
+            ShapePoint { lon: 0.0, lat: 0.0 },
+            ShapePoint {
+                lon: 0.01,
+                lat: 0.0,
+            },
+        ];
+        let total = length(&line);
+        assert!((total - 1111.95).abs() < 0.1, "{total}");
+        let middle = sub_line(&line, total / 4.0, total * 3.0 / 4.0);
+        assert_eq!(middle.len(), 2);
+        assert!((middle[0].lon - 0.0025).abs() < 1e-9);
+        assert!((middle[1].lon - 0.0075).abs() < 1e-9);
+        assert!(sub_line(&line, total + 1.0, total + 2.0).is_empty());
+
+        assert_eq!(distinct_fraction(&line, &line, 1.0), 0.0);
+        assert_eq!(distinct_fraction(&line, &middle, 1.0), 0.0);
+        assert_eq!(distinct_fraction(&middle, &[], 1.0), 1.0);
+        let north = [
+            ShapePoint {
+                lon: 0.0,
+                lat: 0.01,
+            },
+            ShapePoint {
+                lon: 0.01,
+                lat: 0.01,
+            },
+        ];
+        assert_eq!(distinct_fraction(&line, &north, 100.0), 1.0);
+    }
+
+    #[test]
+    fn buffer_empty() {
+        assert!(buffer(&[], 10.0).exterior().0.is_empty());
+    }
+}
@@ -0,0 +1,214 @@
+use crate::batch::CancellationToken;
+use crate::Error;
+use std::collections::HashMap;
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+/// How often a waiting follower checks its [`CancellationToken`]
+const CANCELLATION_POLL: Duration = Duration::from_millis(10);
+
+#[derive(Debug)]
+struct State<T> {
+    /// `None` while the leader is still running, then whether it succeeded
+    result: Option<Option<T>>,
+    followers: usize,
+}
+
+#[derive(Debug)]
+struct Call<T> {
+    state: Mutex<State<T>>,
+    done: Condvar,
+}
+impl<T> Default for Call<T> {
+    fn default() -> Self {
+        Self {
+            state: Mutex::new(State {
+                result: None,
+                followers: 0,
+            }),
+            done: Condvar::new(),
+        }
+    }
+}
+
+/// Coalesces identical concurrent requests into a single one
+///
+/// The first caller of a key (the leader) does the work; callers arriving while it runs wait
+/// and receive a copy of its response.
+/// Errors are not shared: if the leader fails, every follower sends its own request.
+/// Followers stop waiting once their own deadline has passed or they are cancelled.
+#[derive(Debug)]
+pub(crate) struct SingleFlight<T> {
+    calls: Mutex<HashMap<String, Arc<Call<T>>>>,
+}
+impl<T> Default for SingleFlight<T> {
+    fn default() -> Self {
+        Self {
+            calls: Default::default(),
+        }
+    }
+}
+
+impl<T: Clone> SingleFlight<T> {
+    pub(crate) fn run(
+        &self,
+        key: String,
+        deadline: Option<Instant>,
+        cancellation: Option<&CancellationToken>,
+        fetch: impl FnOnce() -> Result<T, Error>,
+    ) -> Result<T, Error> {
+        let existing = {
+            let mut calls = self.calls.lock().unwrap();
+            match calls.get(&key) {
+                Some(call) => Some(call.clone()),
+                None => {
+                    calls.insert(key.clone(), Default::default());
+                    None
+                }
+            }
+        };
+        let Some(call) = existing else {
+            let leader = Leader {
+                flight: self,
+                key,
+                published: false,
+            };
+            let result = fetch();
+            leader.finish(result.as_ref().ok().cloned());
+            return result;
+        };
+        let mut state = call.state.lock().unwrap();
+        state.followers += 1;
+        while state.result.is_none() {
+            if cancellation.is_some_and(CancellationToken::is_cancelled) {
+                return Err(Error::Cancelled);
+            }
+            let now = Instant::now();
+            if deadline.is_some_and(|deadline| now >= deadline) {
+                return Err(Error::DeadlineExceeded);
+            }
+            let timeout = deadline
+                .map(|deadline| deadline - now)
+                .into_iter()
+                .chain(cancellation.map(|_| CANCELLATION_POLL))
+                .min();
+            state = match timeout {
+                None => call.done.wait(state).unwrap(),
+                Some(timeout) => call.done.wait_timeout(state, timeout).unwrap().0,
+            };
+        }
+        match state.result.clone().flatten() {
+            Some(response) => Ok(response),
+            None => {
+                drop(state);
+                fetch()
+            }
+        }
+    }
+
+    #[cfg(test)]
+    fn followers(&self, key: &str) -> usize {
+        let calls = self.calls.lock().unwrap();
+        calls
+            .get(key)
+            .map_or(0, |call| call.state.lock().unwrap().followers)
+    }
+}
+
+/// Publishes the leaders result, also if fetching panicked
+struct Leader<'a, T> {
+    flight: &'a SingleFlight<T>,
+    key: String,
+    published: bool,
+}
+impl<T> Leader<'_, T> {
+    fn finish(mut self, result: Option<T>) {
+        self.publish(result);
+    }
+    fn publish(&mut self, result: Option<T>) {
+        self.published = true;
+        let call = self.flight.calls.lock().unwrap().remove(&self.key);
+        if let Some(call) = call {
+            call.state.lock().unwrap().result = Some(result);
+            call.done.notify_all();
+        }
+    }
+}
+impl<T> Drop for Leader<'_, T> {
+    fn drop(&mut self) {
+        if !self.published {
+            self.publish(None);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn coalesces_concurrent_calls() {
+        let flight = SingleFlight::<String>::default();
+        let fetches = AtomicUsize::new(0);
+        let fetch = || {
+            fetches.fetch_add(1, Ordering::SeqCst);
+            while flight.followers("route {}") < 3 {
+                std::thread::sleep(Duration::from_millis(1));
+            }
+            Ok("response".to_string())
+        };
+        let results = std::thread::scope(|scope| {
+            let leader = scope.spawn(|| flight.run("route {}".into(), None, None, fetch));
+            while flight.calls.lock().unwrap().is_empty() {
+                std::thread::sleep(Duration::from_millis(1));
+            }
+            let followers: Vec<_> = (0..3)
+                .map(|_| scope.spawn(|| flight.run("route {}".into(), None, None, fetch)))
+                .collect();
+            let mut results = vec![leader.join().unwrap()];
+            results.extend(followers.into_iter().map(|f| f.join().unwrap()));
+            results
+        });
+        assert_eq!(fetches.load(Ordering::SeqCst), 1);
+        assert!(results
+            .iter()
+            .all(|r| matches!(r.as_deref(), Ok("response"))));
+        assert!(flight.calls.lock().unwrap().is_empty());
+
+        // the next call is not coalesced with the finished one
+        let text = flight.run("route {}".into(), None, None, || Ok("fresh".into()));
+        assert_eq!(text.unwrap(), "fresh");
+    }
+
+    #[test]
+    fn followers_give_up() {
+        let flight = SingleFlight::<String>::default();
+        let (done, token) = (CancellationToken::new(), CancellationToken::new());
+        let slow = || {
+            while !done.is_cancelled() {
+                std::thread::sleep(Duration::from_millis(1));
+            }
+            Ok("response".to_string())
+        };
+        std::thread::scope(|scope| {
+            let leader = scope.spawn(|| flight.run("route {}".into(), None, None, slow));
+            while flight.calls.lock().unwrap().is_empty() {
+                std::thread::sleep(Duration::from_millis(1));
+            }
+            let deadline = Instant::now() + Duration::from_millis(20);
+            let result = flight.run("route {}".into(), Some(deadline), None, slow);
+            assert!(matches!(result, Err(Error::DeadlineExceeded)));
+
+            let follower = scope.spawn(|| flight.run("route {}".into(), None, Some(&token), slow));
+            while flight.followers("route {}") < 2 {
+                std::thread::sleep(Duration::from_millis(1));
+            }
+            token.cancel();
+            assert!(matches!(follower.join().unwrap(), Err(Error::Cancelled)));
+
+            done.cancel();
+            assert_eq!(leader.join().unwrap().unwrap(), "response");
+        });
+    }
+}
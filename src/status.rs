@@ -1,9 +1,10 @@
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use std::collections::HashSet;
+use std::collections::BTreeSet;
 
 #[serde_with::skip_serializing_none]
 #[derive(Serialize, Default, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct Manifest {
     verbose: Option<bool>,
 }
@@ -39,7 +40,9 @@ pub struct Response {
     ///
     /// Can be used in applications to enable/disable parts of the UI such as an elevation map.
     /// Example: `["expansion","height","status","trace_attributes","trace_route","optimized_route","sources_to_targets","isochrone","route","locate"]`
-    pub available_actions: HashSet<String>,
+    ///
+    /// Ordered alphabetically, so that its `Debug` output is stable, e.g. for snapshot tests.
+    pub available_actions: BTreeSet<String>,
     /// Verbose information about the deployment
     ///
     /// Only included if
@@ -48,6 +51,44 @@ pub struct Response {
     #[serde(flatten)]
     pub verbose: Option<VerboseStatus>,
 }
+impl Response {
+    /// Whether the server version matches [`crate::MIN_SUPPORTED_VALHALLA`]
+    pub fn is_supported_version(&self) -> bool {
+        let version = &self.version;
+        // `VersionReq` does not match pre-releases, but development builds of valhalla are
+        // versioned e.g. `3.5.1-dev` and support what the release does
+        let release = semver::Version::new(version.major, version.minor, version.patch);
+        crate::MIN_SUPPORTED_VALHALLA.matches(&release)
+    }
+    /// Summarizes how up to date the data served by the server is, as of `now`
+    ///
+    /// The fields from [`VerboseStatus`] are `None` unless verbose output was requested and
+    /// returned.
+    pub fn freshness(&self, now: chrono::DateTime<chrono::Utc>) -> Freshness {
+        Freshness {
+            tileset_last_modified: self.tileset_last_modified,
+            tileset_age: now - self.tileset_last_modified,
+            osm_changeset: self.verbose.as_ref().and_then(|v| v.osm_changeset),
+            has_live_traffic: self.verbose.as_ref().map(|v| v.has_live_traffic),
+            has_transit_tiles: self.verbose.as_ref().and_then(|v| v.has_transit_tiles),
+        }
+    }
+}
+
+/// How up to date the data of a server is, see [`Response::freshness`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Freshness {
+    /// Time the tileset was last modified
+    pub tileset_last_modified: chrono::DateTime<chrono::Utc>,
+    /// Time elapsed since [`Self::tileset_last_modified`]
+    pub tileset_age: chrono::TimeDelta,
+    /// Last OSM changeset included in the tileset
+    pub osm_changeset: Option<u64>,
+    /// Whether live traffic is available
+    pub has_live_traffic: Option<bool>,
+    /// Whether transit routing is available
+    pub has_transit_tiles: Option<bool>,
+}
 #[derive(Deserialize, Debug, Clone)]
 pub struct VerboseStatus {
     /// Whether a valid tileset is currently loaded
@@ -58,6 +99,16 @@ pub struct VerboseStatus {
     pub has_timezones: bool,
     /// Whether live traffic tiles are currently available
     pub has_live_traffic: bool,
+    /// Whether the current tileset contains transit tiles
+    ///
+    /// Only reported by valhalla 3.4 and newer
+    #[serde(default)]
+    pub has_transit_tiles: Option<bool>,
+    /// ID of the last OSM changeset the tileset was built from
+    ///
+    /// Only reported by valhalla 3.4 and newer, if the tileset was built with this information
+    #[serde(default)]
+    pub osm_changeset: Option<u64>,
     /// GeoJSON of the tileset extent
     ///
     /// This is likely humongous, be cautions
@@ -73,9 +124,10 @@ pub struct VerboseStatus {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::Valhalla;
+    #[cfg(any(feature = "reqwest", feature = "ureq"))]
     #[test]
     fn test_status_verbose() {
+        use crate::Valhalla;
         let request = Manifest::builder().verbose_output(true);
         let response = Valhalla::default().status(request).unwrap();
         assert!(response.version >= semver::Version::parse("3.1.4").unwrap());
@@ -84,4 +136,65 @@ mod tests {
         assert!(verbose.bbox.is_object());
         assert!(verbose.warnings.is_empty());
     }
+
+    #[test]
+    fn stable_debug_output() {
+        let response: Response = serde_json::from_value(serde_json::json!({
+            "version": "3.5.1",
+            "tileset_last_modified": 1700000000,
+            "available_actions": ["status", "route", "height", "locate"]
+        }))
+        .unwrap();
+        assert_eq!(
+            format!("{:?}", response.available_actions),
+            r#"{"height", "locate", "route", "status"}"#
+        );
+        assert!(response.is_supported_version());
+    }
+
+    #[test]
+    fn freshness() {
+        let response: Response = serde_json::from_value(serde_json::json!({
+            "version": "3.5.1",
+            "tileset_last_modified": 1700000000,
+            "available_actions": ["status"],
+            "has_tiles": true,
+            "has_admins": true,
+            "has_timezones": true,
+            "has_live_traffic": false,
+            "has_transit_tiles": true,
+            "osm_changeset": 144000000,
+            "bbox": {}
+        }))
+        .unwrap();
+        let now = response.tileset_last_modified + chrono::TimeDelta::days(3);
+        let freshness = response.freshness(now);
+        assert_eq!(freshness.tileset_age, chrono::TimeDelta::days(3));
+        assert_eq!(freshness.osm_changeset, Some(144000000));
+        assert_eq!(freshness.has_live_traffic, Some(false));
+        assert_eq!(freshness.has_transit_tiles, Some(true));
+    }
+
+    #[test]
+    fn unsupported_version() {
+        let response: Response = serde_json::from_value(serde_json::json!({
+            "version": "3.0.9",
+            "tileset_last_modified": 1700000000,
+            "available_actions": []
+        }))
+        .unwrap();
+        assert!(!response.is_supported_version());
+        assert_eq!(response.freshness(chrono::Utc::now()).osm_changeset, None);
+        assert_eq!(
+            crate::Error::UnsupportedVersion(response.version).to_string(),
+            "valhalla 3.0.9 is not supported, >=3.1.4 is required"
+        );
+        let response: Response = serde_json::from_value(serde_json::json!({
+            "version": "3.1.4-dev",
+            "tileset_last_modified": 1700000000,
+            "available_actions": []
+        }))
+        .unwrap();
+        assert!(response.is_supported_version());
+    }
 }
@@ -0,0 +1,529 @@
+//! The HTTP layer of the client
+//!
+//! [`crate::Valhalla`] only needs to send a request and read back the status code and body.
+//! Implement [`HttpTransport`] to use another HTTP client (hyper, ureq, isahc, ...) or a test
+//! double which returns canned responses.
+//! Implementations are provided for
+//! - `reqwest::blocking::Client` (feature `reqwest`, enabled by default) and
+//! - `ureq::Agent` (feature `ureq`), which does not need an async runtime.
+//!
+//! # Compression
+//!
+//! The transports created by [`crate::Valhalla::new`] ask for compressed responses and
+//! decompress them transparently, if the matching features are enabled:
+//! `gzip` (enabled by default), `brotli` and `zstd` (reqwest only).
+//! Custom transports are responsible for this themselves; [`HttpResponse::body`] is expected
+//! to be decompressed.
+//!
+//! # Without a transport
+//!
+//! Where the request cannot be sent synchronously, e.g. from async code or over a message
+//! queue, build it via [`crate::Valhalla::dry_run`], send it by any means and read the response
+//! via [`crate::Valhalla::parse_response`].
+//!
+//! # Example:
+//! ```rust
+//! use valhalla_client::transport::{HttpRequest, HttpResponse, HttpTransport};
+//! use valhalla_client::{Error, Valhalla};
+//!
+//! /// Answers every request with the same response
+//! struct Canned(&'static str);
+//! impl HttpTransport for Canned {
+//!     fn send(&self, _request: HttpRequest) -> Result<HttpResponse, Error> {
+//!         Ok(HttpResponse {
+//!             status: 200,
+//!             headers: Vec::new(),
+//!             body: self.0.as_bytes().to_vec(),
+//!         })
+//!     }
+//! }
+//!
+//! let valhalla = Valhalla::with_transport(
+//!     Canned(r#"{"version": "3.5.1", "tileset_last_modified": 0, "available_actions": []}"#),
+//!     url::Url::parse("http://localhost:8002/").unwrap(),
+//! );
+//! let status = valhalla.status(Default::default()).unwrap();
+//! assert_eq!(status.version.to_string(), "3.5.1");
+//! ```
+
+use crate::{Error, RequestMethod};
+use std::time::Duration;
+
+/// A request to the valhalla server
+///
+/// The `Debug` output does not contain the credentials: header values other than those
+/// describing the body and query parameters other than `json` are redacted.
+#[derive(Clone, PartialEq, Eq)]
+pub struct HttpRequest {
+    pub method: RequestMethod,
+    /// Full url of the action, including the `json` query parameter of GET requests
+    pub url: url::Url,
+    /// Additional headers, such as the credentials of [`crate::Auth`]
+    pub headers: Vec<(String, String)>,
+    /// The JSON encoded manifest of POST requests
+    ///
+    /// Is to be sent with `Content-Type: application/json`.
+    /// Compressed if [`Self::headers`] contain a `Content-Encoding`.
+    pub body: Option<Vec<u8>>,
+    /// How long the transport may take to complete the request, if limited
+    ///
+    /// Set to the time remaining until the deadline of [`crate::Valhalla::with_deadline`].
+    pub timeout: Option<std::time::Duration>,
+}
+
+impl HttpRequest {
+    /// Identifies identical requests, without containing the credentials they carry
+    ///
+    /// Requests with different credentials get different fingerprints, as the credentials are
+    /// hashed into it.
+    pub(crate) fn fingerprint(&self) -> String {
+        use std::hash::{Hash, Hasher};
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        self.url.as_str().hash(&mut hasher);
+        self.headers.hash(&mut hasher);
+        format!("{self:?} {:016x}", hasher.finish())
+    }
+}
+impl std::fmt::Debug for HttpRequest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        const REDACTED: &str = "<redacted>";
+        let mut url = self.url.clone();
+        if url.password().is_some() {
+            let _ = url.set_password(Some(REDACTED));
+        }
+        if url.query().is_some() {
+            let pairs: Vec<_> = self
+                .url
+                .query_pairs()
+                .map(|(name, value)| match name.as_ref() {
+                    "json" => (name, value),
+                    _ => (name, REDACTED.into()),
+                })
+                .collect();
+            url.query_pairs_mut().clear().extend_pairs(pairs);
+        }
+        let headers: Vec<_> = self
+            .headers
+            .iter()
+            .map(|(name, value)| {
+                let harmless = ["Content-Encoding", "Content-Type"]
+                    .iter()
+                    .any(|harmless| name.eq_ignore_ascii_case(harmless));
+                (name, if harmless { value.as_str() } else { REDACTED })
+            })
+            .collect();
+        f.debug_struct("HttpRequest")
+            .field("method", &self.method)
+            .field("url", &url.as_str().replace("%3Credacted%3E", REDACTED))
+            .field("headers", &headers)
+            .field("body", &self.body)
+            .field("timeout", &self.timeout)
+            .finish()
+    }
+}
+
+/// The response of the valhalla server, whatever its status code
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpResponse {
+    /// HTTP status code, e.g. `200`
+    pub status: u16,
+    /// The headers of the response
+    ///
+    /// Only those read by this crate need to be included, i.e. the ones of [`RateLimit`].
+    pub headers: Vec<(String, String)>,
+    /// The body, after undoing any `Content-Encoding`
+    pub body: Vec<u8>,
+}
+
+impl HttpResponse {
+    /// The value of the header `name`, compared case-insensitively
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.trim())
+    }
+
+    /// The rate limit reported via the headers of this response, if any
+    pub fn rate_limit(&self) -> Option<RateLimit> {
+        let number = |name: &str| {
+            self.header(&format!("x-ratelimit-{name}"))
+                .or_else(|| self.header(&format!("ratelimit-{name}")))
+                .and_then(|value| value.parse::<u64>().ok())
+        };
+        let now = std::time::SystemTime::now();
+        let rate_limit = RateLimit {
+            limit: number("limit"),
+            remaining: number("remaining"),
+            reset: number("reset").map(|reset| {
+                // some providers send the time of the reset instead of the time until it
+                let epoch = std::time::UNIX_EPOCH + Duration::from_secs(reset);
+                if reset > 1_000_000_000 {
+                    epoch.duration_since(now).unwrap_or_default()
+                } else {
+                    Duration::from_secs(reset)
+                }
+            }),
+            retry_after: self.header("retry-after").and_then(|value| {
+                if let Ok(seconds) = value.parse() {
+                    return Some(Duration::from_secs(seconds));
+                }
+                let at = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+                Some(
+                    std::time::SystemTime::from(at)
+                        .duration_since(now)
+                        .unwrap_or_default(),
+                )
+            }),
+        };
+        (rate_limit != RateLimit::default()).then_some(rate_limit)
+    }
+
+    /// The url to send `request` to instead, if this response redirects it
+    ///
+    /// 307 and 308 keep the method and body, so are always followed.
+    /// 301, 302 and 303 are only followed for GET requests: HTTP clients turn a redirected POST
+    /// into a GET without the manifest.
+    pub(crate) fn redirect(&self, request: &HttpRequest) -> Option<url::Url> {
+        let follow = match self.status {
+            307 | 308 => true,
+            301..=303 => request.method == RequestMethod::Get,
+            _ => false,
+        };
+        if !follow {
+            return None;
+        }
+        request.url.join(self.header("location")?).ok()
+    }
+
+    /// The response if it is successful, else the error the server reported
+    pub(crate) fn error_for_status(self) -> Result<Self, Error> {
+        if self.status == 429 {
+            return Err(Error::RateLimited(self.rate_limit().unwrap_or_default()));
+        }
+        if !(200..300).contains(&self.status) {
+            return Err(match serde_json::from_slice(&self.body) {
+                Ok(remote_error) => Error::RemoteError(remote_error),
+                // the request was rejected for a reason the server should have explained
+                Err(e) if (400..500).contains(&self.status) && self.status != 408 => {
+                    Error::Serde(e)
+                }
+                Err(_) => Error::HttpStatus(self.status),
+            });
+        }
+        Ok(self)
+    }
+}
+
+/// Rate limit state reported by the server, or by a proxy in front of it, via response headers
+///
+/// Hosted providers announce their limits via the de-facto standard `X-RateLimit-*` headers or
+/// their standardized `RateLimit-*` successors, and send `Retry-After` with HTTP 429.
+/// Fields are `None` if the header is missing or cannot be parsed.
+/// See [`crate::Valhalla::last_rate_limit`] and [`Error::RateLimited`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RateLimit {
+    /// Requests allowed per window, `X-RateLimit-Limit`
+    pub limit: Option<u64>,
+    /// Requests left in the current window, `X-RateLimit-Remaining`
+    pub remaining: Option<u64>,
+    /// Time until the window resets, `X-RateLimit-Reset`
+    pub reset: Option<Duration>,
+    /// How long to wait before sending the next request, `Retry-After`
+    pub retry_after: Option<Duration>,
+}
+
+/// Sends [`HttpRequest`]s
+///
+/// Responses with an error status code are to be returned as [`HttpResponse`] as well,
+/// as valhalla explains what went wrong in their body.
+/// Only failures to get a response at all (e.g. connection errors) are errors.
+/// Redirects are to be returned as well, [`crate::Valhalla`] follows them.
+pub trait HttpTransport: Send + Sync {
+    fn send(&self, request: HttpRequest) -> Result<HttpResponse, Error>;
+}
+
+/// Settings of the transport created by [`crate::Valhalla::with_transport_config`]
+///
+/// Covers TLS and connection pooling.
+/// Settings which are not listed here can be applied to a reqwest client built by hand and
+/// passed to [`crate::Valhalla::with_client`].
+///
+/// The TLS library is chosen via the features `native-tls` and `rustls-tls`.
+/// Without either, reqwest uses native-tls and ureq uses rustls.
+/// If both are enabled, rustls is used.
+#[cfg(any(feature = "reqwest", feature = "ureq"))]
+#[derive(Debug, Clone, Default)]
+pub struct TransportConfig {
+    root_certificates: Vec<Vec<u8>>,
+    accept_invalid_certs: bool,
+    pool_max_idle_per_host: Option<usize>,
+    pool_idle_timeout: Option<std::time::Duration>,
+    tcp_keepalive: Option<std::time::Duration>,
+    http2_prior_knowledge: bool,
+    timeout: Option<std::time::Duration>,
+}
+#[cfg(any(feature = "reqwest", feature = "ureq"))]
+impl TransportConfig {
+    pub fn builder() -> Self {
+        Self::default()
+    }
+    /// Trusts the PEM encoded certificate `pem`, e.g. the root certificate of a private CA
+    ///
+    /// With reqwest, the certificate is trusted in addition to the system's root certificates.
+    /// With ureq, only the certificates added here are trusted.
+    pub fn add_root_certificate_pem(mut self, pem: impl Into<Vec<u8>>) -> Self {
+        self.root_certificates.push(pem.into());
+        self
+    }
+    /// Accepts any certificate, including expired and self-signed ones
+    ///
+    /// **Warning:** Anyone on the network path can impersonate the server.
+    /// Only use this for local test setups.
+    ///
+    /// Default: `false`
+    pub fn danger_accept_invalid_certs(mut self, accept_invalid_certs: bool) -> Self {
+        self.accept_invalid_certs = accept_invalid_certs;
+        self
+    }
+    /// Fails requests which take longer than `timeout`, including connecting and reading the
+    /// response
+    ///
+    /// For the requests [`crate::Valhalla::with_deadline`] applies to, reqwest uses the
+    /// deadline instead, ureq whichever of the two is sooner.
+    ///
+    /// Default: 30 seconds with reqwest, no timeout with ureq
+    pub fn timeout(mut self, timeout: std::time::Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+    /// Keeps at most `max` idle connections to the server open for reuse
+    ///
+    /// Services sending many concurrent requests (see [`crate::Valhalla::max_concurrent_requests`])
+    /// should keep about as many connections open, to avoid reconnecting for every request.
+    ///
+    /// Default: unlimited with reqwest, 3 with ureq
+    pub fn pool_max_idle_per_host(mut self, max: usize) -> Self {
+        self.pool_max_idle_per_host = Some(max);
+        self
+    }
+    /// Closes connections which were idle for longer than `timeout`
+    ///
+    /// Should be shorter than the idle timeout of the server or load balancer, so requests are
+    /// not sent on connections which the other side is closing.
+    ///
+    /// Default: 90 seconds with reqwest, 15 seconds with ureq
+    pub fn pool_idle_timeout(mut self, timeout: std::time::Duration) -> Self {
+        self.pool_idle_timeout = Some(timeout);
+        self
+    }
+    /// Sends TCP keep-alive probes after the connection was idle for `interval`
+    ///
+    /// Keeps idle pooled connections from being dropped silently by firewalls and NAT gateways.
+    /// Only supported by reqwest, ignored by ureq.
+    ///
+    /// Default: no keep-alive probes
+    pub fn tcp_keepalive(mut self, interval: std::time::Duration) -> Self {
+        self.tcp_keepalive = Some(interval);
+        self
+    }
+    /// Talks HTTP/2 right away, without negotiating it
+    ///
+    /// HTTP/2 multiplexes concurrent requests over a single connection.
+    /// Over TLS it is negotiated automatically; this is for servers accepting unencrypted
+    /// HTTP/2 (h2c) only, such as some service meshes.
+    /// Only supported by reqwest, ignored by ureq, which only speaks HTTP/1.1.
+    ///
+    /// Default: `false`
+    pub fn http2_prior_knowledge(mut self, http2_prior_knowledge: bool) -> Self {
+        self.http2_prior_knowledge = http2_prior_knowledge;
+        self
+    }
+
+    /// A reqwest client using these settings
+    ///
+    /// Does not follow redirects itself, see [`crate::Valhalla::new`].
+    #[cfg(feature = "reqwest")]
+    pub fn reqwest_client(&self) -> Result<reqwest::blocking::Client, Error> {
+        let mut builder = reqwest::blocking::Client::builder()
+            .redirect(reqwest::redirect::Policy::none())
+            .danger_accept_invalid_certs(self.accept_invalid_certs);
+        #[cfg(feature = "rustls-tls")]
+        {
+            builder = builder.use_rustls_tls();
+        }
+        for pem in &self.root_certificates {
+            let certificate = reqwest::Certificate::from_pem(pem).map_err(Error::Reqwest)?;
+            builder = builder.add_root_certificate(certificate);
+        }
+        if let Some(max) = self.pool_max_idle_per_host {
+            builder = builder.pool_max_idle_per_host(max);
+        }
+        if let Some(timeout) = self.pool_idle_timeout {
+            builder = builder.pool_idle_timeout(timeout);
+        }
+        if let Some(interval) = self.tcp_keepalive {
+            builder = builder.tcp_keepalive(interval);
+        }
+        if self.http2_prior_knowledge {
+            builder = builder.http2_prior_knowledge();
+        }
+        if let Some(timeout) = self.timeout {
+            builder = builder.timeout(timeout);
+        }
+        builder.build().map_err(Error::Reqwest)
+    }
+
+    /// A ureq agent using these settings
+    ///
+    /// Does not follow redirects itself, see [`crate::Valhalla::new`].
+    ///
+    /// Pass it to [`crate::Valhalla::with_transport`] to use ureq although reqwest is enabled.
+    #[cfg(feature = "ureq")]
+    pub fn ureq_agent(&self) -> Result<ureq::Agent, Error> {
+        use ureq::tls::{Certificate, RootCerts, TlsProvider};
+        let mut tls =
+            ureq::tls::TlsConfig::builder().disable_verification(self.accept_invalid_certs);
+        if cfg!(all(feature = "native-tls", not(feature = "rustls-tls"))) {
+            tls = tls.provider(TlsProvider::NativeTls);
+        }
+        if !self.root_certificates.is_empty() {
+            let certificates = self
+                .root_certificates
+                .iter()
+                .map(|pem| Certificate::from_pem(pem))
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(Error::Ureq)?;
+            tls = tls.root_certs(RootCerts::new_with_certs(&certificates));
+        }
+        let mut config = ureq::Agent::config_builder()
+            .max_redirects(0)
+            .tls_config(tls.build());
+        if let Some(max) = self.pool_max_idle_per_host {
+            config = config.max_idle_connections_per_host(max);
+        }
+        if let Some(timeout) = self.pool_idle_timeout {
+            config = config.max_idle_age(timeout);
+        }
+        if let Some(timeout) = self.timeout {
+            config = config.timeout_global(Some(timeout));
+        }
+        let config = config.build();
+        Ok(ureq::Agent::new_with_config(config))
+    }
+}
+
+#[cfg(feature = "reqwest")]
+impl HttpTransport for reqwest::blocking::Client {
+    fn send(&self, request: HttpRequest) -> Result<HttpResponse, Error> {
+        let builder = match request.method {
+            RequestMethod::Post => self
+                .post(request.url)
+                .header(reqwest::header::CONTENT_TYPE, "application/json"),
+            RequestMethod::Get => self.get(request.url),
+        };
+        let builder = request
+            .headers
+            .iter()
+            .fold(builder, |builder, (name, value)| {
+                builder.header(name, value)
+            });
+        let builder = match request.body {
+            Some(body) => builder.body(body),
+            None => builder,
+        };
+        let builder = match request.timeout {
+            Some(timeout) => builder.timeout(timeout),
+            None => builder,
+        };
+        let response = builder.send().map_err(Error::Reqwest)?;
+        Ok(HttpResponse {
+            status: response.status().as_u16(),
+            headers: response
+                .headers()
+                .iter()
+                .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.into())))
+                .collect(),
+            body: response.bytes().map_err(Error::Reqwest)?.to_vec(),
+        })
+    }
+}
+
+/// Reads responses of any size, without the 10 MiB limit ureq applies by default, as verbose
+/// matrices and long routes exceed it
+#[cfg(feature = "ureq")]
+impl HttpTransport for ureq::Agent {
+    fn send(&self, request: HttpRequest) -> Result<HttpResponse, Error> {
+        fn with_headers<B>(
+            builder: ureq::RequestBuilder<B>,
+            headers: &[(String, String)],
+        ) -> ureq::RequestBuilder<B> {
+            headers.iter().fold(builder, |builder, (name, value)| {
+                builder.header(name, value)
+            })
+        }
+        // the request config starts from the agents, so keep its timeout unless the deadline is sooner
+        let timeout = request
+            .timeout
+            .into_iter()
+            .chain(self.config().timeouts().global)
+            .min();
+        let response = match request.method {
+            RequestMethod::Post => with_headers(self.post(request.url.as_str()), &request.headers)
+                .header("Content-Type", "application/json")
+                .config()
+                .http_status_as_error(false)
+                .timeout_global(timeout)
+                .build()
+                .send(request.body.unwrap_or_default()),
+            RequestMethod::Get => with_headers(self.get(request.url.as_str()), &request.headers)
+                .config()
+                .http_status_as_error(false)
+                .timeout_global(timeout)
+                .build()
+                .call(),
+        };
+        let mut response = response.map_err(Error::Ureq)?;
+        Ok(HttpResponse {
+            status: response.status().as_u16(),
+            headers: response
+                .headers()
+                .iter()
+                .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.into())))
+                .collect(),
+            body: response
+                .body_mut()
+                .with_config()
+                .limit(u64::MAX)
+                .read_to_vec()
+                .map_err(Error::Ureq)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "ureq")]
+    #[test]
+    fn ureq_timeout() {
+        use super::*;
+        // accepts connections, but never responds
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/status", listener.local_addr().unwrap());
+        let agent = TransportConfig::default()
+            .timeout(Duration::from_millis(100))
+            .ureq_agent()
+            .unwrap();
+        let request = HttpRequest {
+            method: RequestMethod::Get,
+            url: url::Url::parse(&url).unwrap(),
+            headers: Vec::new(),
+            body: None,
+            timeout: None,
+        };
+        let started = std::time::Instant::now();
+        assert!(agent.send(request).is_err());
+        assert!(started.elapsed() < Duration::from_secs(5));
+        drop(listener);
+    }
+}
@@ -0,0 +1,183 @@
+//! Checks of manifests before they are sent
+//!
+//! The `validate` methods of the manifests (e.g. [`crate::route::Manifest::validate`]) report
+//! every problem valhalla would reject the request for, as far as it can be known without
+//! asking the server.
+//! The typed methods of [`crate::Valhalla`] validate manifests before sending them, failing
+//! with [`crate::Error::Validation`].
+//! Given the [`ServiceLimits`] of the server via [`crate::Valhalla::service_limits`], they also
+//! reject manifests exceeding them.
+
+use crate::costing;
+
+/// A problem with a manifest
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum Violation {
+    /// Fewer locations than the action needs, e.g. a route needs at least 2
+    TooFewLocations {
+        /// Name of the option, as sent to valhalla
+        field: &'static str,
+        count: usize,
+        min: usize,
+    },
+    /// Neither of the options was set, but one of them is required
+    Missing(&'static str, &'static str),
+    /// Both options were set, but only one of them may be
+    MutuallyExclusive(&'static str, &'static str),
+    /// A costing option is outside the range valhalla accepts
+    Costing(costing::RangeViolation),
+    /// An option of this crate is outside its range, e.g.
+    /// [`crate::route::DiversityOptions::min_distinct_fraction`]
+    OutOfRange {
+        field: &'static str,
+        value: f64,
+        min: f64,
+        max: f64,
+    },
+    /// The manifest exceeds one of the [`ServiceLimits`]
+    ExceedsLimit {
+        /// Name of the limit in the configuration of valhalla, e.g. `max_locations`
+        limit: &'static str,
+        value: f64,
+        max: f64,
+    },
+}
+impl std::fmt::Display for Violation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::TooFewLocations { field, count, min } => {
+                write!(f, "{field} has {count} locations, but needs at least {min}")
+            }
+            Self::Missing(a, b) => write!(f, "either {a} or {b} is required"),
+            Self::MutuallyExclusive(a, b) => write!(f, "{a} and {b} cannot be combined"),
+            Self::Costing(e) => e.fmt(f),
+            Self::OutOfRange {
+                field,
+                value,
+                min,
+                max,
+            } => write!(f, "{field} is {value}, but must be between {min} and {max}"),
+            Self::ExceedsLimit { limit, value, max } => {
+                write!(f, "{value:.0} exceeds the {limit} of {max:.0}")
+            }
+        }
+    }
+}
+
+/// Limits of the server, as configured in the `service_limits` of its `valhalla.json`
+///
+/// Valhalla rejects requests exceeding them with errors such as `Exceeded max locations of 20`.
+/// Checking them before sending, see [`crate::Valhalla::service_limits`], names the limit and
+/// saves the round trip.
+/// The status endpoint of valhalla does not report them, so they have to be provided.
+/// Most of them are configured per costing model; provide the ones of the costing used.
+/// Limits which are `None` are not checked.
+///
+/// # Example:
+/// ```rust
+/// use valhalla_client::validation::ServiceLimits;
+///
+/// // the defaults of valhalla for auto costing
+/// let limits = ServiceLimits {
+///   max_locations: Some(20),
+///   max_distance: Some(5_000_000.0),
+///   max_matrix_location_pairs: Some(2500),
+///   max_matrix_distance: Some(400_000.0),
+///   ..Default::default()
+/// };
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ServiceLimits {
+    /// Locations of a route, `service_limits.<costing>.max_locations`
+    pub max_locations: Option<usize>,
+    /// Sum of the straight line distances between consecutive locations of a route in meters,
+    /// `service_limits.<costing>.max_distance`
+    pub max_distance: Option<f64>,
+    /// Number of sources times number of targets of a matrix,
+    /// `service_limits.<costing>.max_matrix_location_pairs`
+    pub max_matrix_location_pairs: Option<usize>,
+    /// Largest straight line distance between a source and a target of a matrix in meters,
+    /// `service_limits.<costing>.max_matrix_distance`
+    pub max_matrix_distance: Option<f64>,
+    /// Excluded locations of a route, `service_limits.max_exclude_locations`
+    pub max_exclude_locations: Option<usize>,
+    /// Alternates of a route, `service_limits.max_alternates`
+    pub max_alternates: Option<u32>,
+    /// Points of the shape of an elevation request, `service_limits.skadi.max_shape`
+    pub max_shape: Option<usize>,
+}
+
+/// Every [`Violation`] of a manifest
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidationError {
+    /// Never empty
+    pub violations: Vec<Violation>,
+}
+impl std::fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, violation) in self.violations.iter().enumerate() {
+            if i > 0 {
+                f.write_str("; ")?;
+            }
+            violation.fmt(f)?;
+        }
+        Ok(())
+    }
+}
+impl std::error::Error for ValidationError {}
+
+/// Collects every [`Violation`] instead of stopping at the first one
+#[derive(Default)]
+pub(crate) struct Validator(Vec<Violation>);
+impl Validator {
+    pub(crate) fn min_locations(&mut self, field: &'static str, count: usize, min: usize) {
+        if count < min {
+            self.0
+                .push(Violation::TooFewLocations { field, count, min });
+        }
+    }
+    /// Exactly one of `a` and `b` has to be set
+    pub(crate) fn one_of(&mut self, a: (&'static str, bool), b: (&'static str, bool)) {
+        match (a.1, b.1) {
+            (false, false) => self.0.push(Violation::Missing(a.0, b.0)),
+            (true, true) => self.0.push(Violation::MutuallyExclusive(a.0, b.0)),
+            _ => {}
+        }
+    }
+    /// At most one of `a` and `b` may be set
+    pub(crate) fn exclusive(&mut self, a: (&'static str, bool), b: (&'static str, bool)) {
+        if a.1 && b.1 {
+            self.0.push(Violation::MutuallyExclusive(a.0, b.0));
+        }
+    }
+    pub(crate) fn range(&mut self, field: &'static str, value: f64, min: f64, max: f64) {
+        if !(min..=max).contains(&value) {
+            self.0.push(Violation::OutOfRange {
+                field,
+                value,
+                min,
+                max,
+            });
+        }
+    }
+    /// `value` may not exceed `max`, the limit named `limit`
+    pub(crate) fn at_most(&mut self, limit: &'static str, value: f64, max: Option<f64>) {
+        if let Some(max) = max.filter(|max| value > *max) {
+            self.0.push(Violation::ExceedsLimit { limit, value, max });
+        }
+    }
+    pub(crate) fn costing(&mut self, costing: Option<&costing::Costing>) {
+        if let Some(Err(violations)) = costing.map(costing::Costing::validate) {
+            self.0
+                .extend(violations.into_iter().map(Violation::Costing));
+        }
+    }
+    pub(crate) fn finish(self) -> Result<(), ValidationError> {
+        if self.0.is_empty() {
+            Ok(())
+        } else {
+            Err(ValidationError { violations: self.0 })
+        }
+    }
+}